@@ -1,58 +1,274 @@
-use clap::Parser;
+use alloy_primitives::Address;
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
 
-use tel_core::{config, dexes::uniswap_v3};
-use tel_indexer::{run_indexer, run_indexer_fetch};
+use tel_core::config::{self, Config, IndexerMode};
+use tel_core::storage::export::{ExportFilter, ExportFormat};
+use tel_indexer::{
+    run_backfill, run_discover, run_export, run_indexer, run_migrate, run_once, run_pool,
+    run_status,
+};
 
-use tracing::Level;
+use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    /// What to do; defaults to `run`
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Options shared by every subcommand
+#[derive(ClapArgs)]
+struct GlobalArgs {
     /// Path to config file
-    #[arg(short, long, default_value = "config/config.toml")]
-    config: String,
+    #[arg(short, long, global = true, default_value = "config/config.toml")]
+    config: PathBuf,
 
-    /// Optional DEX name to index
-    #[arg(short, long)]
-    dex: Option<String>,
+    /// Only work on the DEXes of this chain
+    #[arg(long, global = true)]
+    chain_id: Option<u64>,
 
-    /// Optional pool address to index
-    #[arg(short, long)]
-    pair: Option<String>,
+    /// Do all the reads and RPC calls but skip every database write
+    #[arg(long, global = true)]
+    dry_run: bool,
 
+    /// Log as JSON lines and print command output as JSON
+    #[arg(long, global = true)]
+    json: bool,
 
-    /// Fetch all blocks
-    #[arg(long)]
-    fetch_all: bool,
+    /// Override the configured indexer mode: light (watchlist only) or full
+    #[arg(long, global = true, value_parser = IndexerMode::from_str)]
+    mode: Option<IndexerMode>,
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .with_file(true)
-        .with_line_number(true)
-        .init();
-
-    // Parse command line arguments
-    let args = Args::parse();
+#[derive(Subcommand)]
+enum Command {
+    /// Index continuously, one cycle every indexer.interval_secs
+    Run,
+    /// Run a single indexing cycle and exit
+    Once,
+    /// Register one pool and store its current liquidity distribution
+    Pool {
+        /// DEX name, e.g. uniswap_v3
+        dex: String,
+
+        /// Pool address
+        address: String,
+    },
+    /// Scan factory events for new pools
+    Discover,
+    /// Write historical snapshots of a pool
+    Backfill {
+        /// Pool address
+        #[arg(long)]
+        pool: String,
+
+        /// DEX of the pool, needed only if it is not indexed yet
+        #[arg(long)]
+        dex: Option<String>,
+
+        /// First snapshot block (defaults to the pool's creation block)
+        #[arg(long)]
+        from: Option<u64>,
 
-    // Load config
-    let config = config::load_config(&args.config)?;
+        /// Last block (defaults to the chain head)
+        #[arg(long)]
+        to: Option<u64>,
 
-    // Run indexer
+        /// Blocks between snapshots (defaults to indexer.backfill_every_blocks)
+        #[arg(long)]
+        every: Option<u64>,
+    },
+    /// Create the database or bring it up to the current schema
+    Migrate,
+    /// Export tokens, pools and price levels for analysis
+    Export {
+        /// Output format: parquet, csv or jsonl
+        #[arg(short, long, default_value = "parquet", value_parser = ExportFormat::from_str)]
+        format: ExportFormat,
+
+        /// Directory the files are written to
+        #[arg(short, long, default_value = "export")]
+        out: PathBuf,
+
+        /// Only export this DEX
+        #[arg(long)]
+        dex: Option<String>,
+
+        /// Only export this pair (either token order), e.g. --pair 0xA0b8...,0xC02a...
+        #[arg(long, value_parser = parse_pair)]
+        pair: Option<(Address, Address)>,
+
+        /// Start of the snapshot range, RFC 3339 or YYYY-MM-DD
+        #[arg(long, value_parser = parse_time)]
+        from: Option<DateTime<Utc>>,
+
+        /// End of the snapshot range, RFC 3339 or YYYY-MM-DD
+        #[arg(long, value_parser = parse_time)]
+        to: Option<DateTime<Utc>>,
+    },
+    /// Print recent runs and pool health; exits with 1 if any listed pool is stale
+    Status {
+        /// Only list pools of this DEX
+        #[arg(long)]
+        dex: Option<String>,
+
+        /// Only list pools whose data is at least this many seconds old, and fail if any are
+        #[arg(long)]
+        stale_secs: Option<i64>,
+
+        /// Number of recent runs to show
+        #[arg(long, default_value_t = 5)]
+        runs: usize,
+    },
+}
 
-    if args.fetch_all {
-        run_indexer_fetch(config).await?;
+fn parse_pair(s: &str) -> Result<(Address, Address), String> {
+    let (token0, token1) = s
+        .split_once(',')
+        .ok_or_else(|| "expected two comma-separated token addresses".to_string())?;
+    let parse = |a: &str| Address::from_str(a.trim()).map_err(|e| format!("invalid address `{a}`: {e}"));
+    Ok((parse(token0)?, parse(token1)?))
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(s) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("invalid time `{s}`, expected RFC 3339 or YYYY-MM-DD"))
+}
+
+fn init_logging(json: bool) {
+    // Logs go to stderr so that command output on stdout stays scriptable
+    let builder = FmtSubscriber::builder()
+        .with_max_level(Level::INFO)
+        .with_writer(std::io::stderr)
+        .with_target(false);
+    if json {
+        builder.json().init();
     } else {
-        run_indexer(config, args.dex, args.pair, false).await?;
+        builder
+            .with_thread_ids(true)
+            .with_thread_names(true)
+            .with_file(true)
+            .with_line_number(true)
+            .init();
+    }
+}
+
+/// Applies the global options to the loaded config.
+fn apply_global(config: &mut Config, global: &GlobalArgs) {
+    if let Some(mode) = global.mode {
+        config.indexer.mode = mode;
+    }
+    if let Some(chain_id) = global.chain_id {
+        config.dexes.retain(|dex| dex.chain_id == chain_id);
     }
+    config.indexer.dry_run |= global.dry_run;
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    init_logging(args.global.json);
 
+    let mut config = config::load_config(&args.global.config)?;
+    apply_global(&mut config, &args.global);
+    let chain_id = args.global.chain_id;
+
+    match args.command.unwrap_or(Command::Run) {
+        Command::Run => run_indexer(config).await?,
+        Command::Once => {
+            let stats = run_once(config).await?;
+            if stats.failed > 0 {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Pool { dex, address } => {
+            run_pool(config, &dex, &address).await?;
+        }
+        Command::Discover => run_discover(config).await?,
+        Command::Backfill {
+            pool,
+            dex,
+            from,
+            to,
+            every,
+        } => {
+            run_backfill(config, chain_id, dex, &pool, from, to, every).await?;
+        }
+        Command::Migrate => run_migrate(&config)?,
+        Command::Export {
+            format,
+            out,
+            dex,
+            pair,
+            from,
+            to,
+        } => {
+            let filter = ExportFilter {
+                chain_id,
+                dex,
+                pair,
+                from,
+                to,
+            };
+            run_export(config, filter, format, &out).await?;
+        }
+        Command::Status {
+            dex,
+            stale_secs,
+            runs,
+        } => {
+            let status = run_status(&config, chain_id, dex.as_deref(), stale_secs, runs).await?;
+            if args.global.json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else {
+                for run in &status.runs {
+                    println!(
+                        "run {}  {:>7} ms  {} pools, {} snapshotted, {} failed",
+                        run.started_at.to_rfc3339(),
+                        run.duration_ms,
+                        run.pools,
+                        run.snapshotted,
+                        run.failed
+                    );
+                }
+                for health in &status.pools {
+                    let pool = &health.status;
+                    let stale = health
+                        .stale_secs
+                        .map_or("never refreshed".to_string(), |s| format!("{s}s stale"));
+                    println!(
+                        "{:>6} {:<12} {}  {:<16} errors {}{}",
+                        pool.chain_id,
+                        pool.dex,
+                        pool.pool,
+                        stale,
+                        pool.error_count,
+                        pool.last_error
+                            .as_deref()
+                            .map_or(String::new(), |e| format!(" (last: {e})"))
+                    );
+                }
+            }
+            if stale_secs.is_some() && !status.pools.is_empty() {
+                info!("{} pools are stale", status.pools.len());
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
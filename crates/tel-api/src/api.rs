@@ -1,9 +1,17 @@
-use tel_core::config::Config;
+use tel_core::config::{CanonicalAsset, Config, RetentionConfig};
 use tel_core::error::Error;
-use tel_core::models::{LiquidityDistribution, LiquidityWallsResponse, LiquidityWall, Side, Token, Pool};
+use tel_core::core::bucketing::{bucket_levels, Grid};
+use tel_core::core::depth::{depth_curve, DEFAULT_DEPTH_MOVES};
+use tel_core::core::liquidity::{identify_walls, weighted_price};
+use tel_core::core::orderbook::{default_tick, order_book};
+use tel_core::core::pricing::PriceOracle;
+use tel_core::core::router::QuoteRouter;
+use tel_core::core::support_resistance::identify_support_resistance;
+use tel_core::models::{IndexerStatus, LiquidityDistribution, LiquidityWallsResponse, MarketDepthResponse, OrderBookResponse, QuoteResponse, CrossChainLiquidityResponse, SnapshotStats, SupportResistanceLevel, Token, Pool};
 use tel_core::providers::ProviderManager;
-use tel_core::storage::{aggregate_liquidity_token1, aggregate_liquidity_dexes, Storage};
+use tel_core::storage::{aggregate_asset_liquidity, aggregate_token_liquidity, get_pair_distributions, indexer_status, Storage};
 use tel_core::storage::SqliteStorage;
+use tel_core::utils::shutdown_signal;
 use alloy_primitives::{Address, hex};
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
@@ -11,11 +19,11 @@ use axum::response::{IntoResponse, Json};
 use axum::routing::get;
 use axum::Router;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 use tracing::{info, warn, debug, error};
-use std::collections::HashMap;
 use tower_http::cors::CorsLayer;
 use tower_http::cors::Any;
 
@@ -59,6 +67,86 @@ fn parse_address(addr_str: &str) -> Result<Address, ApiError> {
 pub struct LiquidityWallsQuery {
     dex: Option<String>,
     chain_id: Option<u64>,
+    /// Bucket grid, `linear:<width>`, `log:<step>` or `percent:<step>`
+    bucket: Option<String>,
+    /// Keep liquidity within this fraction of the current price (0.2 = ±20%)
+    range: Option<f64>,
+}
+
+/// Query parameters for the support/resistance levels endpoint
+#[derive(Debug, Deserialize)]
+pub struct LevelsQuery {
+    dex: Option<String>,
+    chain_id: Option<u64>,
+    /// Drop levels weaker than this, relative to the deepest level
+    min_strength: Option<f64>,
+    /// Number of levels to return, strongest first
+    limit: Option<usize>,
+    /// Bucket grid, `linear:<width>`, `log:<step>` or `percent:<step>`
+    bucket: Option<String>,
+    /// Keep liquidity within this fraction of the current price (0.2 = ±20%)
+    range: Option<f64>,
+}
+
+/// Query parameters for the market depth endpoint
+#[derive(Debug, Deserialize)]
+pub struct DepthQuery {
+    dex: Option<String>,
+    chain_id: Option<u64>,
+    /// Comma-separated price moves as fractions of the current price, e.g. `0.01,0.02`
+    moves: Option<String>,
+}
+
+/// Query parameters for the order book endpoint
+#[derive(Debug, Deserialize)]
+pub struct OrderBookQuery {
+    dex: Option<String>,
+    chain_id: Option<u64>,
+    /// Price step between levels, in the quote token
+    tick: Option<f64>,
+    /// Number of levels per side
+    depth: Option<usize>,
+    /// Keep liquidity within this fraction of the current price (0.2 = ±20%)
+    range: Option<f64>,
+}
+
+/// Query parameters for the swap quote endpoint
+#[derive(Debug, Deserialize)]
+pub struct QuoteQuery {
+    token_in: String,
+    token_out: String,
+    /// Amount of token_in to sell, in whole tokens
+    amount: f64,
+    chain_id: Option<u64>,
+    /// Route through this DEX only instead of every indexed one
+    dex: Option<String>,
+    /// Longest path considered, in pools
+    max_hops: Option<usize>,
+    /// Number of chunks the amount is split into
+    parts: Option<usize>,
+}
+
+/// Query parameters for the token aggregate endpoint
+#[derive(Debug, Deserialize)]
+pub struct AggregateQuery {
+    /// Aggregate this DEX only instead of every enabled one
+    dex: Option<String>,
+    chain_id: Option<u64>,
+    /// Bucket grid, `linear:<width>`, `log:<step>` or `percent:<step>`
+    bucket: Option<String>,
+    /// Keep liquidity within this fraction of the current price (0.2 = ±20%)
+    range: Option<f64>,
+}
+
+/// Query parameters for the cross-chain asset liquidity endpoint
+#[derive(Debug, Deserialize)]
+pub struct AssetLiquidityQuery {
+    /// Aggregate this DEX only instead of every enabled one
+    dex: Option<String>,
+    /// Bucket grid, `linear:<width>`, `log:<step>` or `percent:<step>`
+    bucket: Option<String>,
+    /// Keep liquidity within this fraction of the current price (0.2 = ±20%)
+    range: Option<f64>,
 }
 
 /// Query parameters for pagination
@@ -68,6 +156,32 @@ pub struct PaginationQuery {
     limit: Option<u64>,
 }
 
+/// Response for the retention admin endpoint
+#[derive(Debug, Serialize)]
+pub struct RetentionStatus {
+    policy: RetentionConfig,
+    snapshots: SnapshotStats,
+}
+
+/// Query parameters for the indexer status endpoint
+#[derive(Debug, Deserialize)]
+pub struct IndexerStatusQuery {
+    chain_id: Option<u64>,
+    dex: Option<String>,
+    /// Only list pools whose data is at least this many seconds old
+    stale_secs: Option<i64>,
+    /// Number of recent runs to return
+    runs: Option<usize>,
+}
+
+const DEFAULT_STATUS_RUNS: usize = 20;
+const DEFAULT_BOOK_DEPTH: usize = 100;
+const DEFAULT_MAX_HOPS: usize = 2;
+const MAX_HOPS: usize = 3;
+const DEFAULT_QUOTE_PARTS: usize = 50;
+const MAX_QUOTE_PARTS: usize = 500;
+const DEFAULT_MIN_STRENGTH: f64 = 0.1;
+const DEFAULT_LEVELS: usize = 20;
 const DEFAULT_PAGE_SIZE: u64 = 100;
 const MAX_PAGE_SIZE: u64 = 1000;
 
@@ -76,6 +190,7 @@ pub struct AppState {
     storage: Arc<dyn Storage>,
     config: Config,
     provider_manager: Arc<ProviderManager>,
+    prices: PriceOracle,
 }
 
 /// API error response
@@ -130,13 +245,25 @@ fn routes(state: Arc<AppState>) -> Router {
             "/v1/liquidity/walls/:token0/:token1",
             get(get_liquidity_walls),
         )
+        .route("/v1/levels/:token0/:token1", get(get_levels))
+        .route("/v1/depth/:token0/:token1", get(get_market_depth))
+        .route("/v1/quote", get(get_quote))
+        .route("/v1/orderbook/:base/:quote", get(get_order_book))
         .route("/v1/tokens/:chain_id/:address", get(get_token_info))
         .route("/v1/pools/:dex/:chain_id", get(get_pools_by_dex))
         .route("/v1/chains/:chain_id/pools", get(get_all_pools))
         .route(
-            "/v1/tokens/:token1_addr/aggregate/:dex/:chain_id",
+            "/v1/tokens/:address/aggregate",
             get(get_token_aggregate_liquidity),
         )
+        .route(
+            "/v1/tokens/:address/aggregate/:dex/:chain_id",
+            get(get_token_aggregate_liquidity_legacy),
+        )
+        .route("/v1/assets", get(get_assets))
+        .route("/v1/assets/:asset/liquidity", get(get_asset_liquidity))
+        .route("/v1/admin/retention", get(get_retention_status))
+        .route("/v1/indexer/status", get(get_indexer_status))
         .with_state(state)
 }
 
@@ -145,67 +272,74 @@ async fn health_check() -> impl IntoResponse {
     StatusCode::OK
 }
 
-/// Get liquidity walls for a token pair
-async fn get_liquidity_walls(
-    Path((token0_addr, token1_addr)): Path<(String, String)>,
-    Query(params): Query<LiquidityWallsQuery>,
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<LiquidityWallsResponse>, ApiError> {
+/// Names of the DEXes enabled in the config for `chain_id`.
+fn enabled_dexes(state: &AppState, chain_id: u64) -> Vec<String> {
+    state
+        .config
+        .dexes
+        .iter()
+        .filter(|d| d.enabled && d.chain_id == chain_id)
+        .map(|d| d.name.clone())
+        .collect()
+}
+
+/// Looks up both tokens of a pair and the latest distribution of the pair on every DEX enabled
+/// for the chain, or on `dex` only, each quoted as token0 in token1.
+async fn pair_liquidity(
+    state: &AppState,
+    token0_addr: &str,
+    token1_addr: &str,
+    dex_filter: Option<&str>,
+    chain_id: u64,
+) -> Result<(Token, Token, Vec<LiquidityDistribution>), ApiError> {
     // Validate addresses
     // Parse addresses with more lenient validation
-    let token0_address = parse_address(&token0_addr)?;
-    let token1_address = parse_address(&token1_addr)?;
-
-    let chain_id = params.chain_id.unwrap_or(1);
+    let token0_address = parse_address(token0_addr)?;
+    let token1_address = parse_address(token1_addr)?;
 
     // Get tokens from database
     let token0 = state
         .storage
-        .get_token(token0_address, chain_id)?
+        .get_token(token0_address, chain_id)
+        .await?
         .ok_or_else(|| ApiError {
             message: format!("Token {} not found in database", token0_address),
             code: 404,
         })?;
     let token1 = state
         .storage
-        .get_token(token1_address, chain_id)?
+        .get_token(token1_address, chain_id)
+        .await?
         .ok_or_else(|| ApiError {
             message: format!("Token {} not found in database", token1_address),
             code: 404,
         })?;
 
     // Get liquidity distributions from database
-    let dex_filter = params.dex.as_deref();
     let mut all_distributions: Vec<LiquidityDistribution> = Vec::new();
     
-    // Define supported DEXes
-    let dexes = if let Some(dex) = dex_filter {
-        vec![dex.to_string()]
-    } else {
-        vec![
-            "uniswap_v3".to_string(),
-            "uniswap_v2".to_string(),
-            "sushiswap".to_string(),
-            "curve".to_string(),
-            "balancer".to_string(),
-        ]
+    let dexes = match dex_filter {
+        Some(dex) => vec![dex.to_string()],
+        None => enabled_dexes(state, chain_id),
     };
 
-    // TODO: Collect and merge liquidity distributions from all relevant DEXes
     for dex in dexes {
-        match state.storage.get_liquidity_distribution(
+        match get_pair_distributions(
+            state.storage.clone(),
             token0_address,
             token1_address,
             &dex,
             chain_id,
-        ) {
-            Ok(Some(distribution)) => {
-                info!("Found liquidity distribution for {} DEX", dex);
-                all_distributions.push(distribution);
-            }
-            Ok(None) => {
+        )
+        .await
+        {
+            Ok(distributions) if distributions.is_empty() => {
                 info!("No liquidity distribution found for {} DEX", dex);
             }
+            Ok(distributions) => {
+                info!("Found {} liquidity distributions for {} DEX", distributions.len(), dex);
+                all_distributions.extend(distributions);
+            }
             Err(e) => {
                 error!("Error getting liquidity distribution for {}: {}", dex, e);
             }
@@ -220,57 +354,286 @@ async fn get_liquidity_walls(
     }
 
     debug!("distributions: {:#?}", all_distributions);
+    Ok((token0, token1, all_distributions))
+}
 
-    let distribution = all_distributions.first().unwrap();
-
-    let current_price = distribution.current_price;
+/// Bucket grid and price range from the `bucket` and `range` query parameters, defaulting to
+/// `walls.bucket_size` around the current price over the whole book.
+fn bucketing(
+    state: &AppState,
+    bucket: Option<&str>,
+    range: Option<f64>,
+) -> Result<(Grid, Option<f64>), ApiError> {
+    let grid = match bucket {
+        Some(bucket) => bucket.parse().map_err(|e: Error| ApiError {
+            message: e.to_string(),
+            code: 400,
+        })?,
+        None => Grid::Percent(state.config.walls.bucket_size),
+    };
+    if let Some(range) = range {
+        if !(range > 0.0 && range.is_finite()) {
+            return Err(ApiError {
+                message: format!("Invalid range {}, expected a positive fraction", range),
+                code: 400,
+            });
+        }
+    }
+    Ok((grid, range))
+}
 
-    let buy_walls = distribution
-        .price_levels
-        .iter()
-        .filter(|d| d.side == Side::Buy)
-        .map(|d| LiquidityWall {
-            price_lower: d.lower_price,
-            price_upper: d.upper_price,
-            liquidity_value: d.token1_liquidity,
-            dex_sources: HashMap::new(),
-        })
-        .collect();
-    let sell_distribution = distribution
-    .price_levels
-    .iter()
-    .filter(|d| d.side == Side::Sell);
-    let sell_walls_in_wall_price = 
-        sell_distribution.clone().map(|d| LiquidityWall {
-            price_lower: d.lower_price,
-            price_upper: d.upper_price,
-            liquidity_value: d.token0_liquidity * (d.upper_price + d.lower_price) / 2.0, // displayed in token1 value
-            dex_sources: HashMap::new(),
-        })
-        .collect();
+/// USD price of `token1`, routed directly or through `token0` at `spot`.
+async fn token1_usd(
+    state: &AppState,
+    token0: &Token,
+    token1: &Token,
+    spot: f64,
+    chain_id: u64,
+) -> Result<Option<f64>, ApiError> {
+    if let Some(usd) = state.prices.usd_price(token1.address, chain_id).await? {
+        return Ok(Some(usd));
+    }
+    if spot > 0.0 {
+        let usd = state.prices.usd_price(token0.address, chain_id).await?;
+        return Ok(usd.map(|usd| usd / spot));
+    }
+    Ok(None)
+}
 
-    let sell_walls_in_current_price = 
-        sell_distribution.clone().map(|d| LiquidityWall {
-            price_lower: d.lower_price,
-            price_upper: d.upper_price,
-            liquidity_value: d.token0_liquidity * current_price / 2.0, // displayed in token1 value
-            dex_sources: HashMap::new(),
-        })
-        .collect();
+/// Get liquidity walls for a token pair
+async fn get_liquidity_walls(
+    Path((token0_addr, token1_addr)): Path<(String, String)>,
+    Query(params): Query<LiquidityWallsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LiquidityWallsResponse>, ApiError> {
+    let chain_id = params.chain_id.unwrap_or(1);
+    let (token0, token1, all_distributions) = pair_liquidity(
+        &state,
+        &token0_addr,
+        &token1_addr,
+        params.dex.as_deref(),
+        chain_id,
+    )
+    .await?;
+
+    let (grid, range) = bucketing(&state, params.bucket.as_deref(), params.range)?;
+    let mut walls = identify_walls(&all_distributions, grid, range, &state.config.walls);
+    let token1_usd = token1_usd(&state, &token0, &token1, walls.price, chain_id).await?;
+    for wall in walls
+        .buy_walls
+        .iter_mut()
+        .chain(&mut walls.sell_walls_in_wall_price)
+        .chain(&mut walls.sell_walls_in_current_price)
+    {
+        wall.liquidity_usd = token1_usd.map(|usd| wall.liquidity_value * usd);
+    }
 
     let response = LiquidityWallsResponse {
         token0,
         token1,
-        price: current_price,
-        buy_walls,
-        sell_walls_in_wall_price,
-        sell_walls_in_current_price,
+        price: walls.price,
+        buy_walls: walls.buy_walls,
+        sell_walls_in_wall_price: walls.sell_walls_in_wall_price,
+        sell_walls_in_current_price: walls.sell_walls_in_current_price,
         timestamp: chrono::Utc::now(),
     };
 
     Ok(Json(response))
 }
 
+/// Get the cost of moving a token pair's price by each of a set of percentages
+async fn get_market_depth(
+    Path((token0_addr, token1_addr)): Path<(String, String)>,
+    Query(params): Query<DepthQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<MarketDepthResponse>, ApiError> {
+    let chain_id = params.chain_id.unwrap_or(1);
+    let moves = match params.moves.as_deref() {
+        Some(moves) => parse_moves(moves)?,
+        None => DEFAULT_DEPTH_MOVES.to_vec(),
+    };
+    let (token0, token1, all_distributions) = pair_liquidity(
+        &state,
+        &token0_addr,
+        &token1_addr,
+        params.dex.as_deref(),
+        chain_id,
+    )
+    .await?;
+
+    let spot = weighted_price(&all_distributions);
+    let token1_usd = token1_usd(&state, &token0, &token1, spot, chain_id).await?;
+    let depth = depth_curve(&all_distributions, &moves, token1_usd);
+
+    Ok(Json(MarketDepthResponse {
+        token0,
+        token1,
+        price: depth.price,
+        asks: depth.asks,
+        bids: depth.bids,
+        timestamp: chrono::Utc::now(),
+    }))
+}
+
+/// Get a token pair's liquidity as an L2 order book of bids and asks in the base token
+async fn get_order_book(
+    Path((base_addr, quote_addr)): Path<(String, String)>,
+    Query(params): Query<OrderBookQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<OrderBookResponse>, ApiError> {
+    let chain_id = params.chain_id.unwrap_or(1);
+    if let Some(tick) = params.tick {
+        if !(tick > 0.0 && tick.is_finite()) {
+            return Err(ApiError {
+                message: format!("Invalid tick {}, expected a positive price step", tick),
+                code: 400,
+            });
+        }
+    }
+    let (_, range) = bucketing(&state, None, params.range)?;
+    let (base, quote, all_distributions) = pair_liquidity(
+        &state,
+        &base_addr,
+        &quote_addr,
+        params.dex.as_deref(),
+        chain_id,
+    )
+    .await?;
+
+    let tick = params
+        .tick
+        .unwrap_or_else(|| default_tick(weighted_price(&all_distributions)));
+    let depth = params.depth.unwrap_or(DEFAULT_BOOK_DEPTH).min(MAX_PAGE_SIZE as usize);
+    let book = order_book(&all_distributions, tick, range, depth);
+
+    Ok(Json(OrderBookResponse {
+        base,
+        quote,
+        price: book.price,
+        tick: book.tick,
+        bids: book.bids,
+        asks: book.asks,
+        timestamp: chrono::Utc::now(),
+    }))
+}
+
+/// Looks up a token by address, 404 when it isn't stored.
+async fn stored_token(state: &AppState, addr: &str, chain_id: u64) -> Result<Token, ApiError> {
+    let address = parse_address(addr)?;
+    state
+        .storage
+        .get_token(address, chain_id)
+        .await?
+        .ok_or_else(|| ApiError {
+            message: format!("Token {} not found in database", address),
+            code: 404,
+        })
+}
+
+/// Get the best split of a swap across every indexed pool and path
+async fn get_quote(
+    Query(params): Query<QuoteQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<QuoteResponse>, ApiError> {
+    let chain_id = params.chain_id.unwrap_or(1);
+    if !(params.amount > 0.0 && params.amount.is_finite()) {
+        return Err(ApiError {
+            message: format!("Invalid amount {}, expected a positive number", params.amount),
+            code: 400,
+        });
+    }
+    let token_in = stored_token(&state, &params.token_in, chain_id).await?;
+    let token_out = stored_token(&state, &params.token_out, chain_id).await?;
+    if token_in.address == token_out.address {
+        return Err(ApiError {
+            message: "token_in and token_out must differ".to_string(),
+            code: 400,
+        });
+    }
+
+    let router = QuoteRouter::load(state.storage.clone(), chain_id, params.dex.as_deref()).await?;
+    let max_hops = params.max_hops.unwrap_or(DEFAULT_MAX_HOPS).clamp(1, MAX_HOPS);
+    let parts = params.parts.unwrap_or(DEFAULT_QUOTE_PARTS).clamp(1, MAX_QUOTE_PARTS);
+    let swap = router
+        .quote(token_in.address, token_out.address, params.amount, max_hops, parts)
+        .ok_or_else(|| ApiError {
+            message: format!(
+                "No route from {} to {} within {} hops",
+                token_in.address, token_out.address, max_hops
+            ),
+            code: 404,
+        })?;
+
+    let filled = params.amount - swap.unfilled;
+    let effective_price = if filled > 0.0 { swap.amount_out / filled } else { 0.0 };
+    let slippage = if filled > 0.0 { 1.0 - effective_price / swap.spot_price } else { 0.0 };
+    Ok(Json(QuoteResponse {
+        token_in,
+        token_out,
+        amount_in: params.amount,
+        amount_out: swap.amount_out,
+        spot_price: swap.spot_price,
+        effective_price,
+        slippage,
+        unfilled: swap.unfilled,
+        routes: swap.routes,
+        pools: swap.pools,
+        timestamp: chrono::Utc::now(),
+    }))
+}
+
+/// Parses a comma-separated list of positive price moves.
+fn parse_moves(moves: &str) -> Result<Vec<f64>, ApiError> {
+    moves
+        .split(',')
+        .map(|m| match m.trim().parse::<f64>() {
+            Ok(m) if m > 0.0 && m.is_finite() => Ok(m),
+            _ => Err(ApiError {
+                message: format!("Invalid price move `{}`, expected a positive fraction", m),
+                code: 400,
+            }),
+        })
+        .collect()
+}
+
+/// Get ranked support and resistance levels for a token pair
+async fn get_levels(
+    Path((token0_addr, token1_addr)): Path<(String, String)>,
+    Query(params): Query<LevelsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SupportResistanceLevel>>, ApiError> {
+    let chain_id = params.chain_id.unwrap_or(1);
+    let (grid, range) = bucketing(&state, params.bucket.as_deref(), params.range)?;
+    let (token0, token1, all_distributions) = pair_liquidity(
+        &state,
+        &token0_addr,
+        &token1_addr,
+        params.dex.as_deref(),
+        chain_id,
+    )
+    .await?;
+
+    let spot = weighted_price(&all_distributions);
+    let price_levels: Vec<_> = all_distributions
+        .into_iter()
+        .flat_map(|d| d.price_levels)
+        .collect();
+    let price_levels = bucket_levels(&price_levels, grid, spot, range);
+
+    let token1_usd = token1_usd(&state, &token0, &token1, spot, chain_id).await?;
+
+    let mut levels = identify_support_resistance(
+        &price_levels,
+        spot,
+        token1_usd,
+        &token0,
+        &token1,
+        params.min_strength.unwrap_or(DEFAULT_MIN_STRENGTH),
+    );
+    levels.truncate(params.limit.unwrap_or(DEFAULT_LEVELS).min(MAX_PAGE_SIZE as usize));
+    Ok(Json(levels))
+}
+
 /// Get token information
 async fn get_token_info(
     Path((chain_id, address_str)): Path<(u64, String)>,
@@ -280,7 +643,8 @@ async fn get_token_info(
 
     let token = state
         .storage
-        .get_token(address, chain_id)?
+        .get_token(address, chain_id)
+        .await?
         .ok_or_else(|| ApiError {
             message: format!("Token {} not found in database", address),
             code: 404,
@@ -298,7 +662,11 @@ async fn get_pools_by_dex(
     let page = pagination.page.unwrap_or(1);
     let offset = (page.saturating_sub(1)) * limit;
     
-    match state.storage.get_pools_by_dex_paginated(&dex, chain_id, limit, offset) {
+    match state
+        .storage
+        .get_pools_by_dex_paginated(&dex, chain_id, limit, offset)
+        .await
+    {
         Ok(pools) => Ok(Json(pools)),
         Err(e) => {
             warn!("Error getting pools by DEX: {}", e);
@@ -318,7 +686,11 @@ async fn get_all_pools(
     let page = pagination.page.unwrap_or(1);
     let offset = (page.saturating_sub(1)) * limit;
     
-    match state.storage.get_all_pools_paginated(chain_id, limit, offset) {
+    match state
+        .storage
+        .get_all_pools_paginated(chain_id, limit, offset)
+        .await
+    {
         Ok(pools) => Ok(Json(pools)),
         Err(e) => {
             warn!("Error getting all pools: {}", e);
@@ -328,34 +700,158 @@ async fn get_all_pools(
     }
 }
 
+/// Get the liquidity of a token across every pool containing it, merged into one book in USD
 pub async fn get_token_aggregate_liquidity(
-    Path((token1_addr, dex, chain_id)): Path<(String, String, u64)>,
+    Path(token_addr): Path<String>,
+    Query(params): Query<AggregateQuery>,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<LiquidityDistribution>, ApiError> {
-    let token1_address = parse_address(&token1_addr)
-        .map_err(|e| ApiError {
-            message: format!("Invalid token address `{}`: {:?}", token1_addr, e),
-            code: 400,
-        })?;
+    let chain_id = params.chain_id.unwrap_or(1);
+    let (grid, range) = bucketing(&state, params.bucket.as_deref(), params.range)?;
+    aggregate_token(&state, &token_addr, params.dex.as_deref(), chain_id, grid, range)
+        .await
+        .map(Json)
+}
 
-    let dist = aggregate_liquidity_dexes(
+/// Former path of [`get_token_aggregate_liquidity`]; the `dex` segment is ignored and every
+/// enabled DEX is aggregated, as before.
+async fn get_token_aggregate_liquidity_legacy(
+    Path((token_addr, _dex, chain_id)): Path<(String, String, u64)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LiquidityDistribution>, ApiError> {
+    let (grid, range) = bucketing(&state, None, None)?;
+    aggregate_token(&state, &token_addr, None, chain_id, grid, range)
+        .await
+        .map(Json)
+}
+
+/// Aggregates `token` over `dex`, or over every DEX enabled on `chain_id`.
+async fn aggregate_token(
+    state: &AppState,
+    token_addr: &str,
+    dex: Option<&str>,
+    chain_id: u64,
+    grid: Grid,
+    range: Option<f64>,
+) -> Result<LiquidityDistribution, ApiError> {
+    let token = parse_address(token_addr)?;
+    let dexes: Vec<String> = match dex {
+        Some(dex) => vec![dex.to_string()],
+        None => enabled_dexes(state, chain_id),
+    };
+
+    aggregate_token_liquidity(
         state.storage.clone(),
-        token1_address,
+        &state.prices,
+        token,
         chain_id,
+        &dexes,
+        grid,
+        range,
+    )
+    .await
+    .map_err(|e| match e {
+        Error::DexError(message) => ApiError { message, code: 404 },
+        Error::InvalidAddress(address) => ApiError {
+            message: format!("Token {} not found in database", address),
+            code: 404,
+        },
+        e => {
+            error!("Error aggregating liquidity: {}", e);
+            e.into()
+        }
+    })
+}
+
+/// Get the configured canonical assets and their token on each chain
+async fn get_assets(State(state): State<Arc<AppState>>) -> Json<Vec<CanonicalAsset>> {
+    Json(state.config.assets.canonical.clone())
+}
+
+/// Get the liquidity of a canonical asset across every chain, with per-chain price divergence
+async fn get_asset_liquidity(
+    Path(asset_id): Path<String>,
+    Query(params): Query<AssetLiquidityQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CrossChainLiquidityResponse>, ApiError> {
+    let asset = state.config.assets.asset(&asset_id).ok_or_else(|| ApiError {
+        message: format!("Unknown asset {}", asset_id),
+        code: 404,
+    })?;
+    let (grid, range) = bucketing(&state, params.bucket.as_deref(), params.range)?;
+
+    let mut dexes: HashMap<u64, Vec<String>> = HashMap::new();
+    for token in &asset.tokens {
+        dexes.entry(token.chain_id).or_insert_with(|| match &params.dex {
+            Some(dex) => vec![dex.clone()],
+            None => enabled_dexes(&state, token.chain_id),
+        });
+    }
+
+    let book = aggregate_asset_liquidity(
+        state.storage.clone(),
+        &state.prices,
+        asset,
+        &dexes,
+        grid,
+        range,
+        state.config.assets.divergence_threshold,
     )
     .await
-    .map_err(|e| {
-        error!("Error aggregating liquidity: {}", e);
-        ApiError {
-            message: "Internal server error".to_string(),
-            code: 500,
+    .map_err(|e| match e {
+        Error::DexError(message) => ApiError { message, code: 404 },
+        e => {
+            error!("Error aggregating asset liquidity: {}", e);
+            e.into()
         }
     })?;
 
-    Ok(Json(dist))
+    Ok(Json(CrossChainLiquidityResponse {
+        asset: asset.id.clone(),
+        price: book.price,
+        max_divergence: book.max_divergence,
+        chains: book.chains,
+        price_levels: book.levels,
+        timestamp: chrono::Utc::now(),
+    }))
+}
+
+/// Get the snapshot retention policy and the current size of the snapshot history
+async fn get_retention_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<RetentionStatus>, ApiError> {
+    let snapshots = state.storage.get_snapshot_stats().await?;
+    Ok(Json(RetentionStatus {
+        policy: state.config.retention.clone(),
+        snapshots,
+    }))
+}
+
+/// Get the recent indexer runs and the refresh health of every pool
+async fn get_indexer_status(
+    Query(params): Query<IndexerStatusQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<IndexerStatus>, ApiError> {
+    let limit = params
+        .runs
+        .unwrap_or(DEFAULT_STATUS_RUNS)
+        .min(MAX_PAGE_SIZE as usize);
+    let status = indexer_status(
+        state.storage.clone(),
+        params.chain_id,
+        params.dex.as_deref(),
+        params.stale_secs,
+        limit,
+        chrono::Utc::now(),
+    )
+    .await?;
+    Ok(Json(status))
 }
 
-/// Run the API server
+/// Run the API server until SIGINT or SIGTERM
+///
+/// On shutdown the server stops accepting connections, lets open requests finish and flushes
+/// storage.
 pub async fn run_server(config: Config) -> Result<(), Error> {
     // Initialize the database connection
     let storage = Arc::new(SqliteStorage::new(&config.database.url)?);
@@ -364,9 +860,10 @@ pub async fn run_server(config: Config) -> Result<(), Error> {
     let provider_manager = Arc::new(ProviderManager::new(&config.ethereum, None, None, None)?);
 
     let state = Arc::new(AppState {
-        storage,
+        storage: storage.clone(),
         config: config.clone(),
         provider_manager,
+        prices: PriceOracle::new(storage.clone(), config.pricing.clone()),
     });
 
     let cors = CorsLayer::new()
@@ -389,8 +886,11 @@ pub async fn run_server(config: Config) -> Result<(), Error> {
     info!("Starting API server on {}", addr);
     axum::Server::bind(&addr)
         .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .map_err(|e| Error::Unknown(format!("Server error: {}", e)))?;
 
+    storage.flush().await?;
+    info!("API server stopped");
     Ok(())
 }
@@ -1,12 +1,17 @@
 use anyhow::Result;
 use config::{Config as ConfigLib, Environment, File};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct RpcConfig {
     pub url: String,
     pub timeout_secs: u64,
+    /// Pools refreshed in parallel against this endpoint; defaults to `indexer.batch_size`.
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -23,7 +28,338 @@ pub struct ApiConfig {
 #[derive(Debug, Deserialize, Clone)]
 pub struct IndexerConfig {
     pub interval_secs: u64,
+    /// Pools refreshed in parallel per chain, unless the chain's RPC sets `max_concurrency`.
     pub batch_size: usize,
+    #[serde(default)]
+    pub mode: IndexerMode,
+    /// Largest block range requested per `eth_getLogs` call; shrunk automatically on rejection.
+    #[serde(default = "default_log_range_blocks")]
+    pub log_range_blocks: u64,
+    /// Apply pool events incrementally instead of re-reading every pool each cycle.
+    #[serde(default = "default_event_updates")]
+    pub event_updates: bool,
+    /// How often event-tracked pools are fully re-read as a consistency check.
+    #[serde(default = "default_full_refresh_secs")]
+    pub full_refresh_secs: u64,
+    /// Block cadence of the snapshots written by `backfill`.
+    #[serde(default = "default_backfill_every_blocks")]
+    pub backfill_every_blocks: u64,
+    /// Blocks behind the head after which snapshots are final and no longer rolled back.
+    #[serde(default = "default_confirmations")]
+    pub confirmations: u64,
+    /// How long in-flight pool refreshes may run after a shutdown signal.
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// Drop every storage write and only log it; normally set with `--dry-run`.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn default_log_range_blocks() -> u64 {
+    crate::dexes::discovery::DEFAULT_LOG_RANGE
+}
+
+fn default_event_updates() -> bool {
+    true
+}
+
+fn default_full_refresh_secs() -> u64 {
+    3600
+}
+
+fn default_backfill_every_blocks() -> u64 {
+    7200
+}
+
+fn default_confirmations() -> u64 {
+    12
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+/// Which pools the indexer refreshes each cycle.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexerMode {
+    /// Only the pools selected by the `[watchlist]` section.
+    #[default]
+    Light,
+    /// Every stored pool of every enabled DEX.
+    Full,
+}
+
+impl FromStr for IndexerMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "light" => Ok(IndexerMode::Light),
+            "full" => Ok(IndexerMode::Full),
+            other => Err(format!("unknown indexer mode `{other}`, expected light or full")),
+        }
+    }
+}
+
+/// Pools tracked in light mode. The three selectors are combined.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct WatchlistConfig {
+    /// Explicit pool addresses keyed by chain ID, then DEX name. Pools not yet stored are
+    /// fetched on-chain.
+    pub pools: HashMap<u64, HashMap<String, Vec<String>>>,
+    /// Token pairs, each resolved to every pool of the pair across DEXes and fee tiers, stored
+    /// or looked up in the factories.
+    pub pairs: Vec<[String; 2]>,
+    /// Also tracks the N pools with the highest estimated TVL on each chain. Pools are
+    /// discovered from the factories first, and those without a snapshot are valued from their
+    /// token balances, a bounded number of them per cycle.
+    pub top_by_tvl: Option<usize>,
+}
+
+/// How long liquidity snapshots are kept, and at which resolution.
+///
+/// Snapshots younger than `raw_hours` are all kept. Older ones are thinned to one per
+/// hour until they are `hourly_days` old, and to one per day after that.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct RetentionConfig {
+    pub enabled: bool,
+    pub raw_hours: u64,
+    pub hourly_days: u64,
+    pub compaction_interval_secs: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            raw_hours: 24,
+            hourly_days: 30,
+            compaction_interval_secs: 3600,
+        }
+    }
+}
+
+/// How tokens are priced in USD.
+///
+/// Prices are routed through the indexed pools to a numeraire token per chain, a USD stablecoin
+/// by default, over at most `max_hops` pools. Pools holding less than `min_liquidity` of the
+/// numeraire are not used.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct PricingConfig {
+    pub numeraires: Vec<Numeraire>,
+    pub max_hops: usize,
+    pub min_liquidity: f64,
+    /// How long a chain's price graph is reused before it is rebuilt from storage.
+    pub cache_secs: u64,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        let usdc = |chain_id, token: &str| Numeraire {
+            chain_id,
+            token: token.to_string(),
+        };
+        Self {
+            numeraires: vec![
+                usdc(1, "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+                usdc(10, "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+                usdc(137, "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359"),
+                usdc(42161, "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"),
+            ],
+            max_hops: 3,
+            min_liquidity: 10_000.0,
+            cache_secs: 60,
+        }
+    }
+}
+
+impl PricingConfig {
+    /// Numeraire token of `chain_id`, if one is configured.
+    pub fn numeraire(&self, chain_id: u64) -> Option<&str> {
+        self.numeraires
+            .iter()
+            .find(|n| n.chain_id == chain_id)
+            .map(|n| n.token.as_str())
+    }
+}
+
+/// The token every price on a chain is expressed in.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Numeraire {
+    pub chain_id: u64,
+    pub token: String,
+}
+
+/// Canonical assets, each grouping its native and bridged tokens across chains under one ID.
+///
+/// Cross-chain views flag a token whose USD price lies more than `divergence_threshold` (0.005
+/// = 0.5%) from the asset's price across all chains.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct AssetsConfig {
+    pub divergence_threshold: f64,
+    pub canonical: Vec<CanonicalAsset>,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        let asset = |id: &str, tokens: &[(u64, &str)]| CanonicalAsset {
+            id: id.to_string(),
+            tokens: tokens
+                .iter()
+                .map(|(chain_id, token)| AssetToken {
+                    chain_id: *chain_id,
+                    token: token.to_string(),
+                })
+                .collect(),
+        };
+        Self {
+            divergence_threshold: 0.005,
+            canonical: vec![
+                asset(
+                    "usdc",
+                    &[
+                        (1, "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+                        (10, "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+                        (10, "0x7F5c764cBc14f9669B88837ca1490cCa17c31607"), // USDC.e
+                        (137, "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359"),
+                        (137, "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174"), // USDC.e
+                        (42161, "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"),
+                        (42161, "0xFF970A61A04b1cA14834A43f5dE4533eBDDB5CC8"), // USDC.e
+                    ],
+                ),
+                asset(
+                    "weth",
+                    &[
+                        (1, "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+                        (10, "0x4200000000000000000000000000000000000006"),
+                        (137, "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619"),
+                        (42161, "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+                    ],
+                ),
+            ],
+        }
+    }
+}
+
+impl AssetsConfig {
+    /// Asset with ID `id`, ignoring case.
+    pub fn asset(&self, id: &str) -> Option<&CanonicalAsset> {
+        self.canonical.iter().find(|a| a.id.eq_ignore_ascii_case(id))
+    }
+}
+
+/// One asset and the tokens standing for it on each chain.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CanonicalAsset {
+    pub id: String,
+    pub tokens: Vec<AssetToken>,
+}
+
+/// A token of a canonical asset on one chain; a chain may hold several, e.g. native and bridged.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AssetToken {
+    pub chain_id: u64,
+    pub token: String,
+}
+
+/// How liquidity walls are picked out of the merged price buckets of a pair.
+///
+/// A bucket stands out when its liquidity is `min_z_score` standard deviations above the
+/// mean of the `window` buckets on either side. Adjacent standing-out buckets form one wall,
+/// which is kept if its peak rises at least `min_prominence` (a fraction of the largest
+/// bucket on that side) above the surrounding liquidity.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct WallsConfig {
+    /// Width of the price buckets, relative to the current price.
+    pub bucket_size: f64,
+    pub window: usize,
+    pub min_z_score: f64,
+    pub min_prominence: f64,
+    /// Walls reported per side, largest first.
+    pub max_walls: usize,
+}
+
+impl Default for WallsConfig {
+    fn default() -> Self {
+        Self {
+            bucket_size: 0.01,
+            window: 10,
+            min_z_score: 2.0,
+            min_prominence: 0.1,
+            max_walls: 10,
+        }
+    }
+}
+
+/// Adaptive per-pool refresh intervals, so RPC calls go to the pools whose liquidity moves.
+///
+/// Each pool is placed in the first tier whose thresholds it meets; meeting any one of them is
+/// enough, and a tier without thresholds takes every pool. Within its tier a pool is refreshed
+/// every `min_interval_secs` while it keeps changing and backs off towards `max_interval_secs`
+/// while it doesn't. Intervals are rounded up to whole `indexer.interval_secs` cycles.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    pub enabled: bool,
+    /// Most active tier first.
+    pub tiers: Vec<RefreshTier>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tiers: vec![
+                RefreshTier {
+                    name: "hot".to_string(),
+                    min_tvl_usd: Some(10_000_000.0),
+                    min_swaps_per_hour: Some(60.0),
+                    min_volatility: Some(0.01),
+                    min_interval_secs: 60,
+                    max_interval_secs: 300,
+                },
+                RefreshTier {
+                    name: "warm".to_string(),
+                    min_tvl_usd: Some(100_000.0),
+                    min_swaps_per_hour: Some(5.0),
+                    min_volatility: Some(0.002),
+                    min_interval_secs: 300,
+                    max_interval_secs: 1800,
+                },
+                RefreshTier {
+                    name: "cold".to_string(),
+                    min_tvl_usd: None,
+                    min_swaps_per_hour: None,
+                    min_volatility: None,
+                    min_interval_secs: 1800,
+                    max_interval_secs: 21600,
+                },
+            ],
+        }
+    }
+}
+
+/// One activity tier of the refresh scheduler.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RefreshTier {
+    pub name: String,
+    /// Estimated TVL in USD.
+    #[serde(default)]
+    pub min_tvl_usd: Option<f64>,
+    /// Swaps per hour, smoothed over recent refreshes.
+    #[serde(default)]
+    pub min_swaps_per_hour: Option<f64>,
+    /// Hourly volatility of the pool price (0.01 = 1%), smoothed over recent refreshes.
+    #[serde(default)]
+    pub min_volatility: Option<f64>,
+    pub min_interval_secs: u64,
+    pub max_interval_secs: u64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -32,6 +368,8 @@ pub struct SupportedDex {
     pub chain_id: u64,
     pub factory_address: String,
     pub enabled: bool,
+    /// First block scanned for factory events; defaults to the DEX's mainnet deployment block.
+    pub start_block: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -43,6 +381,18 @@ pub struct Config {
     pub database: DatabaseConfig,
     pub api: ApiConfig,
     pub indexer: IndexerConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub watchlist: WatchlistConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    #[serde(default)]
+    pub walls: WallsConfig,
+    #[serde(default)]
+    pub pricing: PricingConfig,
+    #[serde(default)]
+    pub assets: AssetsConfig,
     pub dexes: Vec<SupportedDex>,
 }
 
@@ -56,9 +406,8 @@ pub fn load_config<P: AsRef<Path>>(path: P) -> Result<Config> {
         .build()?;
 
     let parsed_config: Config = config.try_deserialize()?;
-    
-    println!("DEBUG: Ethereum URL being used: {}", parsed_config.ethereum.url);
-    
+    tracing::debug!("Ethereum URL being used: {}", parsed_config.ethereum.url);
+
     Ok(parsed_config)
 }
 
@@ -77,6 +426,16 @@ pub fn ensure_default_config() -> Result<()> {
 [ethereum]
 url = "https://eth.llamarpc.com"
 timeout_secs = 30
+# max_concurrency = 16  # parallel pool refreshes on this RPC (defaults to indexer.batch_size)
+
+# Needed by the DEXes on chains 10 and 42161 below
+# [optimism]
+# url = "https://mainnet.optimism.io"
+# timeout_secs = 30
+#
+# [arbitrum]
+# url = "https://arb1.arbitrum.io/rpc"
+# timeout_secs = 30
 
 [database]
 url = "sqlite_tel_on_chain.db"
@@ -87,7 +446,142 @@ port = 8080
 
 [indexer]
 interval_secs = 600  # 10 minutes
-batch_size = 1000
+batch_size = 32  # pools refreshed in parallel per chain
+mode = "light"  # light: watchlist only, full: every stored pool
+log_range_blocks = 10000  # max eth_getLogs span, halved automatically when rejected
+event_updates = true  # apply Swap/Mint/Burn/Sync logs instead of re-reading pools
+full_refresh_secs = 3600  # full re-read of event-tracked pools as a consistency check
+backfill_every_blocks = 7200  # snapshot cadence of `tel-indexer backfill` (~1 day)
+confirmations = 12  # reorg depth after which snapshots are final
+shutdown_timeout_secs = 30  # grace period for in-flight pools on SIGINT/SIGTERM
+
+# Snapshot history: every snapshot for raw_hours, hourly for hourly_days, daily afterwards
+[retention]
+enabled = true
+raw_hours = 24
+hourly_days = 30
+compaction_interval_secs = 3600
+
+# Adaptive refresh: pools go to the first tier whose thresholds they meet, then back off from
+# min_interval_secs to max_interval_secs while they don't change
+[scheduler]
+enabled = false
+
+[[scheduler.tiers]]
+name = "hot"
+min_tvl_usd = 10000000
+min_swaps_per_hour = 60
+min_volatility = 0.01  # hourly price volatility
+min_interval_secs = 60
+max_interval_secs = 300
+
+[[scheduler.tiers]]
+name = "warm"
+min_tvl_usd = 100000
+min_swaps_per_hour = 5
+min_volatility = 0.002
+min_interval_secs = 300
+max_interval_secs = 1800
+
+[[scheduler.tiers]]
+name = "cold"  # no thresholds: every other pool
+min_interval_secs = 1800
+max_interval_secs = 21600
+
+# USD prices are routed through indexed pools to a stablecoin per chain
+[pricing]
+max_hops = 3
+min_liquidity = 10000  # in the numeraire; shallower pools are not used for pricing
+cache_secs = 60
+
+[[pricing.numeraires]]
+chain_id = 1
+token = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"  # USDC
+
+[[pricing.numeraires]]
+chain_id = 10
+token = "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"  # USDC
+
+[[pricing.numeraires]]
+chain_id = 42161
+token = "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"  # USDC
+
+# Canonical assets: one ID over the native and bridged tokens of an asset on each chain.
+# Cross-chain views flag tokens priced more than divergence_threshold away from the asset.
+[assets]
+divergence_threshold = 0.005  # 0.5%
+
+[[assets.canonical]]
+id = "usdc"
+tokens = [
+    { chain_id = 1, token = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48" },
+    { chain_id = 10, token = "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85" },
+    { chain_id = 10, token = "0x7F5c764cBc14f9669B88837ca1490cCa17c31607" },  # USDC.e
+    { chain_id = 42161, token = "0xaf88d065e77c8cC2239327C5EDb3A432268e5831" },
+    { chain_id = 42161, token = "0xFF970A61A04b1cA14834A43f5dE4533eBDDB5CC8" },  # USDC.e
+]
+
+[[assets.canonical]]
+id = "weth"
+tokens = [
+    { chain_id = 1, token = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2" },
+    { chain_id = 10, token = "0x4200000000000000000000000000000000000006" },
+    { chain_id = 42161, token = "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1" },
+]
+
+# Wall detection: a price bucket stands out at min_z_score standard deviations above its
+# window buckets on each side; its peak must rise min_prominence of the largest bucket
+[walls]
+bucket_size = 0.01  # 1% of the current price
+window = 10
+min_z_score = 2.0
+min_prominence = 0.1
+max_walls = 10
+
+# Pools refreshed in light mode; edit and restart, no rebuild needed
+[watchlist]
+pairs = [
+    ["0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"], # USDC/WETH
+]
+# top_by_tvl = 20
+
+# Explicit pools by chain ID, then DEX name
+[watchlist.pools.1]
+uniswap_v2 = [
+    "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc", # USDC/ETH
+    "0xBb2b8038a1640196FbE3e38816F3e67Cba72D940", # WBTC/ETH
+    "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852", # ETH/USDT
+    "0xA478c2975Ab1Ea89e8196811F51A7B7Ade33eB11", # DAI/ETH
+    "0xd3d2E2692501A5c9Ca623199D38826e513033a17", # UNI/ETH
+    "0xebfb684dd2b01e698ca6c14f10e4f289934a54d6", # UNI/USDC
+    "0x5ac13261c181a9c3938bfe1b649e65d10f98566b", # UNI/USDT
+    "0xA43fe16908251ee70EF74718545e4FE6C5cCEc9f", # PEPE/WETH
+    "0x004375dff511095cc5a197a54140a24efef3a416", # WBTC/USDC
+]
+uniswap_v3 = [
+    "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640",
+    "0xCBCdF9626bC03E24f779434178A73a0B4bad62eD",
+    "0x99ac8cA7087fA4A2A1FB6357269965A2014ABc35",
+    "0xe8f7c89C5eFa061e340f2d2F206EC78FD8f7e124",
+    "0x5777d92f208679DB4b9778590Fa3CAB3aC9e2168",
+    "0x4e68Ccd3E89f51C3074ca5072bbAC773960dFa36",
+    "0xC5c134A1f112efA96003f8559Dba6fAC0BA77692",
+    "0x1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801",
+    "0x9Db9e0e53058C89e5B94e29621a205198648425B",
+    "0x8ad599c3A0ff1De082011EFDDc58f1908eb6e6D8",
+    "0xD0fC8bA7E267f2bc56044A7715A489d851dC6D78",
+    "0x3470447f3cecffac709d3e783a307790b0208d60",
+    "0xe0554a476a092703abdb3ef35c80e0d76d32939f",
+    "0x11950d141ecb863f01007add7d1a342041227b58",
+    "0x3139Ffc91B99aa94DA8A2dc13f1fC36F9BDc98eE",
+    "0x12EDE161c702D1494612d19f05992f43aa6A26FB",
+    "0x07F068ca326a469Fc1d87d85d448990C8cBa7dF9",
+    "0xAE461cA67B15dc8dc81CE7615e0320dA1A9aB8D5",
+    "0xCe407CD7b95B39d3B4d53065E711e713dd5C5999",
+    "0x33C2d48Bc95FB7D0199C5C693e7a9F527145a9Af",
+    "0xB6909B960DbbE7392D405429eB2b3649752b4838",
+    "0x30EB5E15476E6a80F4F3cd8479749b4881DAB1b8",
+]
 
 # Supported DEXes
 [[dexes]]
@@ -107,6 +601,21 @@ name = "sushiswap"
 chain_id = 1
 factory_address = "0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac"
 enabled = true
+
+# Uniswap V3 on Optimism and Arbitrum; set the [optimism]/[arbitrum] RPC and enable them
+[[dexes]]
+name = "uniswap_v3"
+chain_id = 10
+factory_address = "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+enabled = false
+start_block = 0  # part of the chain's genesis
+
+[[dexes]]
+name = "uniswap_v3"
+chain_id = 42161
+factory_address = "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+enabled = false
+start_block = 165
 "#;
         std::fs::write(default_config_path, default_config)?;
     }
@@ -0,0 +1,201 @@
+use crate::error::Error;
+use crate::models::{PriceLiquidity, Side};
+use crate::Result;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Levels spanning more buckets than this are not split; they go whole to the bucket holding
+/// their middle price, which keeps full-range positions from producing millions of buckets.
+const MAX_SPLIT: i64 = 10_000;
+
+/// Layout of the price buckets liquidity is summed into.
+///
+/// Parsed from `kind:size`, e.g. `percent:0.01`, `linear:25` or `log:0.005`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grid {
+    /// Buckets `size` wide in token1, with edges at multiples of `size`.
+    Linear(f64),
+    /// Buckets whose edges grow by a factor of `1 + size`, with an edge at 1.0.
+    Log(f64),
+    /// Buckets `size` wide relative to the spot price, the middle one centred on it.
+    Percent(f64),
+}
+
+impl Grid {
+    fn size(&self) -> f64 {
+        match *self {
+            Grid::Linear(size) | Grid::Log(size) | Grid::Percent(size) => size,
+        }
+    }
+
+    /// Position of `price` on the grid: bucket `k` covers `[k, k + 1)`.
+    fn coordinate(&self, price: f64, spot: f64) -> f64 {
+        match *self {
+            Grid::Linear(size) => price / size,
+            Grid::Log(size) => price.max(f64::MIN_POSITIVE).ln() / size.ln_1p(),
+            Grid::Percent(size) => (price / spot - 1.0) / size + 0.5,
+        }
+    }
+
+    /// Lower edge of bucket `k`.
+    fn edge(&self, k: i64, spot: f64) -> f64 {
+        let k = k as f64;
+        let edge = match *self {
+            Grid::Linear(size) => k * size,
+            Grid::Log(size) => (k * size.ln_1p()).exp(),
+            Grid::Percent(size) => spot * (1.0 + (k - 0.5) * size),
+        };
+        edge.max(0.0)
+    }
+
+    /// Price range of bucket `k`.
+    pub fn bounds(&self, k: i64, spot: f64) -> (f64, f64) {
+        (self.edge(k, spot), self.edge(k + 1, spot))
+    }
+
+    /// Bucket holding `price`.
+    pub fn index(&self, price: f64, spot: f64) -> i64 {
+        self.coordinate(price, spot).floor() as i64
+    }
+
+    /// Splits the price range `[lower, upper]` over the buckets it overlaps, returning each
+    /// bucket with the share of the range inside it. Liquidity is assumed to be spread evenly
+    /// over the range; a range of zero width goes whole to the bucket holding it.
+    pub fn split(&self, lower: f64, upper: f64, spot: f64) -> Vec<(i64, f64)> {
+        let (lower, upper) = (lower.min(upper), lower.max(upper));
+        let first = self.index(lower, spot);
+        let last = self.index(upper, spot);
+        let width = upper - lower;
+        if first == last || width <= 0.0 {
+            return vec![(first, 1.0)];
+        }
+        if last - first > MAX_SPLIT {
+            return vec![(self.index((lower + upper) / 2.0, spot), 1.0)];
+        }
+        (first..=last)
+            .filter_map(|k| {
+                let (low, high) = self.bounds(k, spot);
+                let overlap = high.min(upper) - low.max(lower);
+                (overlap > 0.0).then(|| (k, overlap / width))
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Grid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            Error::ConfigError(format!(
+                "invalid bucket grid `{s}`, expected linear:<width>, log:<step> or percent:<step>"
+            ))
+        };
+        let (kind, size) = s.split_once(':').ok_or_else(invalid)?;
+        let size: f64 = size.trim().parse().map_err(|_| invalid())?;
+        if !(size > 0.0 && size.is_finite()) {
+            return Err(invalid());
+        }
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "linear" => Ok(Grid::Linear(size)),
+            "log" => Ok(Grid::Log(size)),
+            "percent" | "pct" => Ok(Grid::Percent(size)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Grid::Linear(_) => "linear",
+            Grid::Log(_) => "log",
+            Grid::Percent(_) => "percent",
+        };
+        write!(f, "{}:{}", kind, self.size())
+    }
+}
+
+/// Clips the price range `[lower, upper]` to within `range` (0.2 = ±20%) of `spot`, returning
+/// the part kept and its share of the range, or `None` when nothing is left.
+fn clip(lower: f64, upper: f64, spot: f64, range: Option<f64>) -> Option<(f64, f64, f64)> {
+    let Some(range) = range else {
+        return Some((lower, upper, 1.0));
+    };
+    let (min, max) = (spot * (1.0 - range).max(0.0), spot * (1.0 + range));
+    let (low, high) = (lower.max(min), upper.min(max));
+    if high < low || (high == low && upper > lower) {
+        return None;
+    }
+    let share = if upper > lower { (high - low) / (upper - lower) } else { 1.0 };
+    Some((low, high, share))
+}
+
+/// Buckets of `grid` overlapped by the part of `[lower, upper]` within `range` of `spot`, each
+/// with the share of the whole range it holds.
+pub fn spread(grid: Grid, lower: f64, upper: f64, spot: f64, range: Option<f64>) -> Vec<(i64, f64)> {
+    let Some((lower, upper, kept)) = clip(lower, upper, spot, range) else {
+        return Vec::new();
+    };
+    grid.split(lower, upper, spot)
+        .into_iter()
+        .map(|(k, share)| (k, share * kept))
+        .collect()
+}
+
+/// Sums price levels into the buckets of `grid` around `spot`, sorted by price.
+///
+/// A level spanning several buckets is split between them in proportion to the part of its
+/// price range each one covers, and with `range` set only the part within that fraction of
+/// `spot` is kept. Both sides may fall into one bucket; it takes the side holding more of its
+/// value in token1.
+pub fn bucket_levels(
+    levels: &[PriceLiquidity],
+    grid: Grid,
+    spot: f64,
+    range: Option<f64>,
+) -> Vec<PriceLiquidity> {
+    if !(spot > 0.0 && spot.is_finite()) {
+        return Vec::new();
+    }
+
+    let mut buckets: BTreeMap<i64, PriceLiquidity> = BTreeMap::new();
+    for level in levels {
+        let finite = [level.lower_price, level.upper_price, level.token0_liquidity, level.token1_liquidity]
+            .iter()
+            .all(|v| v.is_finite());
+        if !finite {
+            continue;
+        }
+        for (k, share) in spread(grid, level.lower_price, level.upper_price, spot, range) {
+            let bucket = buckets.entry(k).or_insert_with(|| {
+                let (lower_price, upper_price) = grid.bounds(k, spot);
+                PriceLiquidity {
+                    side: level.side,
+                    lower_price,
+                    upper_price,
+                    token0_liquidity: 0.0,
+                    token1_liquidity: 0.0,
+                    timestamp: level.timestamp,
+                }
+            });
+            bucket.token0_liquidity += level.token0_liquidity * share;
+            bucket.token1_liquidity += level.token1_liquidity * share;
+            bucket.timestamp = bucket.timestamp.max(level.timestamp);
+        }
+    }
+
+    buckets
+        .into_values()
+        .map(|mut bucket| {
+            let mid = (bucket.lower_price + bucket.upper_price) / 2.0;
+            bucket.side = if bucket.token1_liquidity >= bucket.token0_liquidity * mid {
+                Side::Buy
+            } else {
+                Side::Sell
+            };
+            bucket
+        })
+        .collect()
+}
@@ -0,0 +1,108 @@
+use super::bucketing::{spread, Grid};
+use super::liquidity::weighted_price;
+use crate::models::{ChainPrice, CrossChainLevel, LiquidityDistribution, Side};
+use std::collections::BTreeMap;
+
+/// Books of one asset on several chains, merged in USD.
+#[derive(Debug, Clone, Default)]
+pub struct CrossChainBook {
+    /// Liquidity-weighted average of the tokens' USD prices.
+    pub price: f64,
+    pub max_divergence: f64,
+    /// One entry per token, deepest first.
+    pub chains: Vec<ChainPrice>,
+    pub levels: Vec<CrossChainLevel>,
+}
+
+/// Merges per-token books of one asset into one USD book with chain attribution.
+///
+/// Each book quotes its token (token0) in USD (token1) on its own chain, as built by
+/// `storage::aggregate_token_liquidity`, and may hold raw or bucketed levels. The levels are
+/// spread over `grid` around the cross-chain price, within `range` of it when set, so a bucket
+/// holds liquidity at the same USD price on every chain. Tokens priced more than `threshold`
+/// away from the cross-chain price are marked divergent.
+pub fn merge_chains(
+    books: &[LiquidityDistribution],
+    grid: Grid,
+    range: Option<f64>,
+    threshold: f64,
+) -> CrossChainBook {
+    let price = weighted_price(books);
+    if !(price > 0.0 && price.is_finite()) {
+        return CrossChainBook::default();
+    }
+
+    let mut chains: Vec<ChainPrice> = books
+        .iter()
+        .filter(|b| b.current_price > 0.0 && b.current_price.is_finite())
+        .map(|book| {
+            let divergence = book.current_price / price - 1.0;
+            ChainPrice {
+                chain_id: book.chain_id,
+                token: book.token0.clone(),
+                price: book.current_price,
+                liquidity_usd: book
+                    .price_levels
+                    .iter()
+                    .map(|l| l.token0_liquidity * book.current_price + l.token1_liquidity)
+                    .filter(|v| v.is_finite())
+                    .sum(),
+                divergence,
+                divergent: divergence.abs() > threshold,
+            }
+        })
+        .collect();
+    chains.sort_by(|a, b| b.liquidity_usd.total_cmp(&a.liquidity_usd));
+    let highest = chains.iter().map(|c| c.price).fold(f64::MIN, f64::max);
+    let lowest = chains.iter().map(|c| c.price).fold(f64::MAX, f64::min);
+    let max_divergence = if chains.is_empty() { 0.0 } else { (highest - lowest) / price };
+
+    let mut buckets: BTreeMap<i64, CrossChainLevel> = BTreeMap::new();
+    for book in books {
+        for level in &book.price_levels {
+            let finite = [level.lower_price, level.upper_price, level.token0_liquidity, level.token1_liquidity]
+                .iter()
+                .all(|v| v.is_finite() && *v >= 0.0);
+            if !finite {
+                continue;
+            }
+            for (k, share) in spread(grid, level.lower_price, level.upper_price, price, range) {
+                let (lower_price, upper_price) = grid.bounds(k, price);
+                let bucket = buckets.entry(k).or_insert_with(|| CrossChainLevel {
+                    side: level.side,
+                    lower_price,
+                    upper_price,
+                    asset_liquidity: 0.0,
+                    usd_liquidity: 0.0,
+                    chain_sources: Default::default(),
+                });
+                let asset = level.token0_liquidity * share;
+                let usd = level.token1_liquidity * share;
+                bucket.asset_liquidity += asset;
+                bucket.usd_liquidity += usd;
+                *bucket.chain_sources.entry(book.chain_id).or_default() +=
+                    asset * (lower_price + upper_price) / 2.0 + usd;
+            }
+        }
+    }
+
+    let levels = buckets
+        .into_values()
+        .map(|mut bucket| {
+            let mid = (bucket.lower_price + bucket.upper_price) / 2.0;
+            bucket.side = if bucket.usd_liquidity >= bucket.asset_liquidity * mid {
+                Side::Buy
+            } else {
+                Side::Sell
+            };
+            bucket
+        })
+        .collect();
+
+    CrossChainBook {
+        price,
+        max_divergence,
+        chains,
+        levels,
+    }
+}
@@ -0,0 +1,105 @@
+use super::liquidity::weighted_price;
+use crate::models::{DepthAmount, DepthLevel, LiquidityDistribution, PriceLiquidity};
+use std::collections::HashMap;
+
+/// Price moves reported when none are asked for: ±0.5%, 1%, 2%, 5% and 10%.
+pub const DEFAULT_DEPTH_MOVES: [f64; 5] = [0.005, 0.01, 0.02, 0.05, 0.1];
+
+/// Depth of one pair on both sides of its current price, summed across DEXes.
+#[derive(Debug, Clone, Default)]
+pub struct DepthCurve {
+    /// Liquidity-weighted average of the DEXes' current prices.
+    pub price: f64,
+    /// One level per move, smallest first.
+    pub asks: Vec<DepthLevel>,
+    pub bids: Vec<DepthLevel>,
+}
+
+/// Integrates the distributions of one pair into the liquidity met by moving its price by each
+/// of `moves` (0.02 = 2%), up and down.
+///
+/// Every distribution must quote token0 in token1. Moving the price up takes the token0 held
+/// between the current price and the target, paid for in token1 at the middle of each level;
+/// moving it down takes the token1 held below, paid for in token0. A level partly inside the
+/// move counts in proportion to the part of its price range inside it. Amounts are valued in
+/// USD with `token1_usd` when known, and broken down by DEX in `dex_sources`.
+pub fn depth_curve(
+    distributions: &[LiquidityDistribution],
+    moves: &[f64],
+    token1_usd: Option<f64>,
+) -> DepthCurve {
+    let price = weighted_price(distributions);
+    if !(price > 0.0 && price.is_finite()) {
+        return DepthCurve::default();
+    }
+    let mut moves: Vec<f64> = moves.iter().copied().filter(|m| *m > 0.0 && m.is_finite()).collect();
+    moves.sort_by(f64::total_cmp);
+    moves.dedup();
+
+    let side = |up: bool| -> Vec<DepthLevel> {
+        moves
+            .iter()
+            .map(|&change| {
+                let target = if up { price * (1.0 + change) } else { (price * (1.0 - change)).max(0.0) };
+                let (low, high) = if up { (price, target) } else { (target, price) };
+                let mut dex_sources: HashMap<String, DepthAmount> = HashMap::new();
+                for distribution in distributions {
+                    let amount = dex_sources.entry(distribution.dex.clone()).or_default();
+                    for level in &distribution.price_levels {
+                        let Some((share, mid)) = overlap(level, low, high) else {
+                            continue;
+                        };
+                        if up {
+                            let token0 = level.token0_liquidity * share;
+                            amount.token0 += token0;
+                            amount.token1 += token0 * mid;
+                        } else if mid > 0.0 {
+                            let token1 = level.token1_liquidity * share;
+                            amount.token0 += token1 / mid;
+                            amount.token1 += token1;
+                        }
+                    }
+                }
+                dex_sources.retain(|_, a| a.token0 > 0.0 || a.token1 > 0.0);
+
+                let mut total = DepthAmount::default();
+                for amount in dex_sources.values_mut() {
+                    amount.usd = token1_usd.map(|usd| amount.token1 * usd);
+                    total.token0 += amount.token0;
+                    total.token1 += amount.token1;
+                }
+                total.usd = token1_usd.map(|usd| total.token1 * usd);
+                DepthLevel {
+                    change: if up { change } else { -change },
+                    price: target,
+                    total,
+                    dex_sources,
+                }
+            })
+            .collect()
+    };
+
+    DepthCurve {
+        price,
+        asks: side(true),
+        bids: side(false),
+    }
+}
+
+/// Share of `level` inside `[low, high]` and the middle price of that part, or `None` when it
+/// lies outside or holds nothing usable.
+fn overlap(level: &PriceLiquidity, low: f64, high: f64) -> Option<(f64, f64)> {
+    let finite = [level.lower_price, level.upper_price, level.token0_liquidity, level.token1_liquidity]
+        .iter()
+        .all(|v| v.is_finite() && *v >= 0.0);
+    if !finite {
+        return None;
+    }
+    let (lower, upper) = (level.lower_price.max(low), level.upper_price.min(high));
+    if upper < lower {
+        return None;
+    }
+    let width = level.upper_price - level.lower_price;
+    let share = if width > 0.0 { (upper - lower) / width } else { 1.0 };
+    (share > 0.0).then_some((share, (lower + upper) / 2.0))
+}
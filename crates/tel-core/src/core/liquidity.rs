@@ -1,100 +1,264 @@
-use crate::models::{LiquidityDistribution, LiquidityWall, PriceLiquidity, Side};
+use super::bucketing::{spread, Grid};
+use crate::config::WallsConfig;
+use crate::models::{LiquidityDistribution, LiquidityWall, Side};
 use std::collections::HashMap;
 
-/// Identifies liquidity walls from a set of liquidity distributions
-pub fn identify_walls(
-    _distributions: &[LiquidityDistribution],
-    price_ranges: &[(f64, f64)],
-) -> (Vec<LiquidityWall>, Vec<LiquidityWall>) {
-    let mut buy_walls = Vec::new();
-    let mut sell_walls = Vec::new();
+/// Buy and sell walls of one pair, summed across DEXes.
+#[derive(Debug, Clone, Default)]
+pub struct MergedWalls {
+    /// Liquidity-weighted average of the DEXes' current prices.
+    pub price: f64,
+    /// Valued in token1.
+    pub buy_walls: Vec<LiquidityWall>,
+    /// Valued in token1 at the middle of the part of each source level inside the wall.
+    pub sell_walls_in_wall_price: Vec<LiquidityWall>,
+    /// Valued in token1 at the current price.
+    pub sell_walls_in_current_price: Vec<LiquidityWall>,
+}
 
-    // This is a simplified implementation
-    // In a real system, we would analyze the distributions to find significant
-    // concentrations of liquidity at specific price levels
+/// Merges the distributions of one pair from several DEXes onto a common price grid.
+///
+/// Every distribution must quote token0 in token1; use `storage::reverse_pair` on those that
+/// don't. The grid is laid around the liquidity-weighted current price and each price level is
+/// spread over the buckets it overlaps (see [`bucket_levels`](super::bucketing::bucket_levels)),
+/// keeping only the part within `range` of the price when set. Walls are sorted by price and
+/// record each DEX's share of their value in `dex_sources`.
+pub fn merge_walls(distributions: &[LiquidityDistribution], grid: Grid, range: Option<f64>) -> MergedWalls {
+    let price = weighted_price(distributions);
+    if !(price > 0.0 && price.is_finite()) {
+        return MergedWalls::default();
+    }
 
-    for &(price_lower, price_upper) in price_ranges {
-        let buy_wall = LiquidityWall {
-            price_lower,
-            price_upper,
-            liquidity_value: 100000.0, // Placeholder
-            dex_sources: HashMap::new(),
-        };
+    let mut buy = HashMap::new();
+    let mut sell_in_wall_price = HashMap::new();
+    let mut sell_in_current_price = HashMap::new();
+    for distribution in distributions {
+        for level in &distribution.price_levels {
+            if !(level.lower_price.is_finite() && level.upper_price.is_finite()) || level.upper_price < 0.0 {
+                continue;
+            }
+            let dex = &distribution.dex;
+            for (bucket, share) in spread(grid, level.lower_price, level.upper_price, price, range) {
+                match level.side {
+                    Side::Buy => add_to_wall(&mut buy, bucket, dex, level.token1_liquidity * share),
+                    Side::Sell => {
+                        let (low, high) = grid.bounds(bucket, price);
+                        let mid = (low.max(level.lower_price) + high.min(level.upper_price)) / 2.0;
+                        let amount = level.token0_liquidity * share;
+                        // displayed in token1 value
+                        add_to_wall(&mut sell_in_wall_price, bucket, dex, amount * mid);
+                        add_to_wall(&mut sell_in_current_price, bucket, dex, amount * price / 2.0);
+                    }
+                }
+            }
+        }
+    }
 
-        let sell_wall = LiquidityWall {
-            price_lower: price_upper,
-            price_upper: price_upper * 1.05,
-            liquidity_value: 100000.0, // Placeholder
-            dex_sources: HashMap::new(),
-        };
+    let walls = |buckets: HashMap<i64, HashMap<String, f64>>| {
+        let mut walls: Vec<(i64, LiquidityWall)> = buckets
+            .into_iter()
+            .map(|(bucket, dex_sources)| {
+                let (price_lower, price_upper) = grid.bounds(bucket, price);
+                let wall = LiquidityWall {
+                    price_lower,
+                    price_upper,
+                    liquidity_value: dex_sources.values().sum(),
+                    dex_sources,
+                    liquidity_usd: None,
+                };
+                (bucket, wall)
+            })
+            .collect();
+        walls.sort_by_key(|(bucket, _)| *bucket);
+        walls.into_iter().map(|(_, wall)| wall).collect()
+    };
 
-        buy_walls.push(buy_wall);
-        sell_walls.push(sell_wall);
+    MergedWalls {
+        price,
+        buy_walls: walls(buy),
+        sell_walls_in_wall_price: walls(sell_in_wall_price),
+        sell_walls_in_current_price: walls(sell_in_current_price),
     }
-
-    (buy_walls, sell_walls)
 }
 
-/// Groups price levels into ranges for simplified visualization
-pub fn group_price_levels(
-    price_levels: &[PriceLiquidity],
-    num_groups: usize,
-) -> Vec<PriceLiquidity> {
-    if price_levels.is_empty() || num_groups == 0 {
-        return Vec::new();
+fn add_to_wall(walls: &mut HashMap<i64, HashMap<String, f64>>, bucket: i64, dex: &str, value: f64) {
+    if value.is_finite() && value > 0.0 {
+        *walls
+            .entry(bucket)
+            .or_default()
+            .entry(dex.to_string())
+            .or_default() += value;
     }
+}
 
-    // Find min and max prices
-    let min_price = price_levels
+/// Current price of the pair, averaged over the distributions by their value in token1.
+pub fn weighted_price(distributions: &[LiquidityDistribution]) -> f64 {
+    let priced: Vec<(f64, f64)> = distributions
         .iter()
-        .map(|p| p.lower_price)
-        .fold(f64::INFINITY, f64::min);
-    let max_price = price_levels
+        .filter(|d| d.current_price > 0.0 && d.current_price.is_finite())
+        .map(|d| {
+            let value: f64 = d
+                .price_levels
+                .iter()
+                .map(|l| l.token0_liquidity * d.current_price + l.token1_liquidity)
+                .filter(|v| v.is_finite())
+                .sum();
+            (d.current_price, value)
+        })
+        .collect();
+    let total: f64 = priced.iter().map(|(_, value)| value).sum();
+    if total > 0.0 {
+        priced.iter().map(|(price, value)| price * value).sum::<f64>() / total
+    } else if priced.is_empty() {
+        0.0
+    } else {
+        priced.iter().map(|(price, _)| price).sum::<f64>() / priced.len() as f64
+    }
+}
+
+/// Identifies liquidity walls from a set of liquidity distributions
+///
+/// The distributions are merged onto `grid` (see [`merge_walls`]) and the walls
+/// of each side are detected with [`detect_walls`]. Sell walls valued at the current price
+/// cover the same bands as those valued at the wall price.
+pub fn identify_walls(
+    distributions: &[LiquidityDistribution],
+    grid: Grid,
+    range: Option<f64>,
+    config: &WallsConfig,
+) -> MergedWalls {
+    let merged = merge_walls(distributions, grid, range);
+    let sell_walls_in_wall_price = detect_walls(&merged.sell_walls_in_wall_price, config);
+    let sell_walls_in_current_price = sell_walls_in_wall_price
         .iter()
-        .map(|p| p.upper_price)
-        .fold(f64::NEG_INFINITY, f64::max);
+        .map(|wall| {
+            let dex_sources = sum_sources(merged.sell_walls_in_current_price.iter().filter(|b| {
+                let mid = (b.price_lower + b.price_upper) / 2.0;
+                mid >= wall.price_lower && mid <= wall.price_upper
+            }));
+            LiquidityWall {
+                price_lower: wall.price_lower,
+                price_upper: wall.price_upper,
+                liquidity_value: dex_sources.values().sum(),
+                dex_sources,
+                liquidity_usd: None,
+            }
+        })
+        .collect();
 
-    // Calculate the range size
-    let range_size = (max_price - min_price) / num_groups as f64;
+    MergedWalls {
+        price: merged.price,
+        buy_walls: detect_walls(&merged.buy_walls, config),
+        sell_walls_in_wall_price,
+        sell_walls_in_current_price,
+    }
+}
 
-    // Group price levels
-    let mut grouped = Vec::new();
-    for i in 0..num_groups {
-        let group_min = min_price + range_size * i as f64;
-        let group_max = group_min + range_size;
+/// Picks the walls out of price buckets sorted by price, largest first.
+///
+/// A bucket stands out when its liquidity is at least `min_z_score` standard deviations above
+/// the mean of the `window` buckets on either side; a gap in the grid counts as one empty
+/// bucket. Runs of adjacent standing-out buckets are merged into one wall, which is kept when
+/// its peak's prominence (its height above the higher of the lowest points separating it from
+/// taller buckets on either side) is at least `min_prominence` of the largest bucket. At most
+/// `max_walls` walls are returned.
+pub fn detect_walls(buckets: &[LiquidityWall], config: &WallsConfig) -> Vec<LiquidityWall> {
+    let mut series: Vec<Option<&LiquidityWall>> = Vec::with_capacity(buckets.len());
+    for bucket in buckets {
+        if let Some(Some(prev)) = series.last() {
+            let tolerance = 1e-9 * bucket.price_lower.abs().max(1.0);
+            if (bucket.price_lower - prev.price_upper).abs() > tolerance {
+                series.push(None);
+            }
+        }
+        series.push(Some(bucket));
+    }
+    let values: Vec<f64> = series
+        .iter()
+        .map(|b| b.map_or(0.0, |b| b.liquidity_value))
+        .collect();
+    let largest = values.iter().copied().fold(0.0, f64::max);
+    if largest <= 0.0 {
+        return Vec::new();
+    }
 
-        // Filter price levels in this range
-        let levels_in_range: Vec<_> = price_levels
-            .iter()
-            .filter(|p| p.lower_price >= group_min && p.upper_price < group_max)
-            .collect();
+    let stands_out: Vec<bool> = (0..values.len())
+        .map(|i| values[i] > 0.0 && z_score(&values, i, config.window) >= config.min_z_score)
+        .collect();
+    let mut walls = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        if !stands_out[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < values.len() && stands_out[i] {
+            i += 1;
+        }
+        let peak = (start..i)
+            .max_by(|a, b| values[*a].total_cmp(&values[*b]))
+            .unwrap_or(start);
+        if prominence(&values, peak) >= config.min_prominence * largest {
+            let band: Vec<&LiquidityWall> = series[start..i].iter().flatten().copied().collect();
+            let dex_sources = sum_sources(band.iter().copied());
+            walls.push(LiquidityWall {
+                price_lower: band[0].price_lower,
+                price_upper: band[band.len() - 1].price_upper,
+                liquidity_value: dex_sources.values().sum(),
+                dex_sources,
+                liquidity_usd: None,
+            });
+        }
+    }
 
-        if !levels_in_range.is_empty() {
-            // Average price in the range
-            let avg_price: f64 =
-                levels_in_range.iter().map(|p| p.lower_price).sum::<f64>() / levels_in_range.len() as f64;
+    walls.sort_by(|a, b| b.liquidity_value.total_cmp(&a.liquidity_value));
+    walls.truncate(config.max_walls);
+    walls
+}
 
-            // Sum of liquidity in the range
-            let token0_liquidity: f64 = levels_in_range.iter().map(|p| p.token0_liquidity).sum();
-            let token1_liquidity: f64 = levels_in_range.iter().map(|p| p.token1_liquidity).sum();
+/// Standard score of `values[i]` against the `window` values on either side of it.
+fn z_score(values: &[f64], i: usize, window: usize) -> f64 {
+    let neighbours: Vec<f64> = (i.saturating_sub(window)..(i + window + 1).min(values.len()))
+        .filter(|&j| j != i)
+        .map(|j| values[j])
+        .collect();
+    let n = neighbours.len().max(1) as f64;
+    let mean = neighbours.iter().sum::<f64>() / n;
+    let std = (neighbours.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    if std > 0.0 {
+        (values[i] - mean) / std
+    } else if values[i] > mean {
+        f64::INFINITY
+    } else {
+        0.0
+    }
+}
 
-            // Use the timestamp of the latest price level
-            let timestamp = levels_in_range
-                .iter()
-                .max_by_key(|p| p.timestamp)
-                .map(|p| p.timestamp)
-                .unwrap_or_else(chrono::Utc::now);
+/// Topographic prominence of `values[peak]`, with no liquidity beyond either end.
+fn prominence(values: &[f64], peak: usize) -> f64 {
+    let height = values[peak];
+    let base = |range: &mut dyn Iterator<Item = usize>| {
+        let mut lowest = height;
+        for j in range {
+            if values[j] > height {
+                return lowest;
+            }
+            lowest = lowest.min(values[j]);
+        }
+        0.0
+    };
+    let left = base(&mut (0..peak).rev());
+    let right = base(&mut (peak + 1..values.len()));
+    height - left.max(right)
+}
 
-            grouped.push(PriceLiquidity {
-                side: Side::Buy,
-                lower_price: avg_price,
-                upper_price: avg_price,
-                token0_liquidity,
-                token1_liquidity,
-                timestamp,
-            });
+fn sum_sources<'a>(walls: impl Iterator<Item = &'a LiquidityWall>) -> HashMap<String, f64> {
+    let mut sources = HashMap::new();
+    for wall in walls {
+        for (dex, value) in &wall.dex_sources {
+            *sources.entry(dex.clone()).or_default() += value;
         }
     }
-
-    grouped
+    sources
 }
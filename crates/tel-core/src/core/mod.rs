@@ -1,2 +1,8 @@
+pub mod bucketing;
+pub mod depth;
 pub mod liquidity;
 pub mod support_resistance;
+pub mod pricing;
+pub mod router;
+pub mod cross_chain;
+pub mod orderbook;
@@ -0,0 +1,89 @@
+use super::bucketing::{spread, Grid};
+use super::liquidity::weighted_price;
+use crate::models::LiquidityDistribution;
+use std::collections::BTreeMap;
+
+/// Synthetic L2 book of one pair, as `[price, size]` levels with size in the base token.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    /// Liquidity-weighted average of the DEXes' current prices.
+    pub price: f64,
+    pub tick: f64,
+    /// Highest price first.
+    pub bids: Vec<[f64; 2]>,
+    /// Lowest price first.
+    pub asks: Vec<[f64; 2]>,
+}
+
+/// Tick used when none is given: the largest power of ten within one basis point of `price`.
+pub fn default_tick(price: f64) -> f64 {
+    if !(price > 0.0 && price.is_finite()) {
+        return 1.0;
+    }
+    10f64.powf((price / 10_000.0).log10().floor())
+}
+
+/// Converts the distributions of one pair into an L2 book with levels `tick` apart.
+///
+/// Every distribution must quote the base token (token0) in the quote token (token1). Ask
+/// liquidity is the token0 held above the price, bid liquidity the token1 held below it,
+/// converted to token0 at the price it sits at. Each level is spread over the ticks its price
+/// range overlaps, within `range` of the price when set. Bids are quoted at the bottom of their
+/// tick and asks at the top, and liquidity on the wrong side of the price's tick, left by DEXes
+/// trading away from the average, is dropped, so the book never crosses. At most `depth`
+/// levels are kept per side.
+pub fn order_book(
+    distributions: &[LiquidityDistribution],
+    tick: f64,
+    range: Option<f64>,
+    depth: usize,
+) -> OrderBook {
+    let price = weighted_price(distributions);
+    if !(price > 0.0 && price.is_finite() && tick > 0.0 && tick.is_finite()) {
+        return OrderBook::default();
+    }
+
+    let grid = Grid::Linear(tick);
+    let spot = grid.index(price, price);
+    let mut bids: BTreeMap<i64, f64> = BTreeMap::new();
+    let mut asks: BTreeMap<i64, f64> = BTreeMap::new();
+    for distribution in distributions {
+        for level in &distribution.price_levels {
+            let finite = [level.lower_price, level.upper_price, level.token0_liquidity, level.token1_liquidity]
+                .iter()
+                .all(|v| v.is_finite() && *v >= 0.0);
+            if !finite {
+                continue;
+            }
+            for (k, share) in spread(grid, level.lower_price, level.upper_price, price, range) {
+                if level.token0_liquidity > 0.0 && k >= spot {
+                    *asks.entry(k).or_default() += level.token0_liquidity * share;
+                }
+                let (low, high) = grid.bounds(k, price);
+                let mid = (low.max(level.lower_price) + high.min(level.upper_price)) / 2.0;
+                if level.token1_liquidity > 0.0 && mid > 0.0 && k <= spot {
+                    *bids.entry(k).or_default() += level.token1_liquidity * share / mid;
+                }
+            }
+        }
+    }
+
+    let side = |levels: &mut dyn Iterator<Item = (&i64, &f64)>, top: bool| -> Vec<[f64; 2]> {
+        levels
+            .filter(|(_, size)| **size > 0.0)
+            .map(|(k, size)| {
+                let (low, high) = grid.bounds(*k, price);
+                [if top { high } else { low }, *size]
+            })
+            .filter(|[p, _]| *p > 0.0)
+            .take(depth)
+            .collect()
+    };
+
+    OrderBook {
+        price,
+        tick,
+        bids: side(&mut bids.iter().rev(), false),
+        asks: side(&mut asks.iter(), true),
+    }
+}
@@ -0,0 +1,224 @@
+use crate::config::PricingConfig;
+use crate::models::LiquidityDistribution;
+use crate::storage::Storage;
+use crate::Result;
+use alloy_primitives::Address;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+/// Price of a token in its chain's numeraire, with the depth backing it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceQuote {
+    pub token: Address,
+    pub chain_id: u64,
+    /// Numeraire per token.
+    pub price: f64,
+    /// Liquidity of the shallowest pool on the path, in the numeraire; a measure of how far the
+    /// price can be trusted. Infinite for the numeraire itself.
+    pub liquidity: f64,
+    /// Tokens from `token` to the numeraire.
+    pub path: Vec<Address>,
+}
+
+/// One direction of a pool: how `to` is priced once `from` is.
+struct Edge {
+    to: Address,
+    /// Price of `to` in `from`.
+    rate: f64,
+    /// Liquidity of the pool in `from`.
+    depth: f64,
+}
+
+/// Prices of every token reachable from a chain's numeraire through indexed pools.
+pub struct PriceGraph {
+    chain_id: u64,
+    quotes: HashMap<Address, PriceQuote>,
+}
+
+impl PriceGraph {
+    /// Routes a price to every token within `max_hops` pools of `numeraire`.
+    ///
+    /// Each token takes the path whose shallowest pool is deepest, with pool depth valued in
+    /// the numeraire along the way; pools shallower than `min_liquidity` are skipped.
+    /// `distributions` are the latest distributions of the chain's pools.
+    pub fn build(
+        chain_id: u64,
+        numeraire: Address,
+        distributions: &[LiquidityDistribution],
+        max_hops: usize,
+        min_liquidity: f64,
+    ) -> Self {
+        let mut edges: HashMap<Address, Vec<Edge>> = HashMap::new();
+        for d in distributions.iter().filter(|d| d.chain_id == chain_id) {
+            let price = d.current_price;
+            if !(price > 0.0 && price.is_finite()) {
+                continue;
+            }
+            let value_in_token1: f64 = d
+                .price_levels
+                .iter()
+                .map(|l| l.token0_liquidity * price + l.token1_liquidity)
+                .filter(|v| v.is_finite())
+                .sum();
+            let (token0, token1) = (d.token0.address, d.token1.address);
+            edges.entry(token1).or_default().push(Edge {
+                to: token0,
+                rate: price,
+                depth: value_in_token1,
+            });
+            edges.entry(token0).or_default().push(Edge {
+                to: token1,
+                rate: 1.0 / price,
+                depth: value_in_token1 / price,
+            });
+        }
+
+        let mut quotes = HashMap::new();
+        quotes.insert(
+            numeraire,
+            PriceQuote {
+                token: numeraire,
+                chain_id,
+                price: 1.0,
+                liquidity: f64::INFINITY,
+                path: vec![numeraire],
+            },
+        );
+        // Bellman-Ford by hop count, widening each token's path as longer routes are found.
+        let mut frontier = vec![numeraire];
+        for _ in 0..max_hops {
+            let mut improved = HashMap::new();
+            for from in &frontier {
+                let Some(edges) = edges.get(from) else {
+                    continue;
+                };
+                let via = &quotes[from];
+                for edge in edges {
+                    let depth = edge.depth * via.price;
+                    let liquidity = via.liquidity.min(depth);
+                    if depth < min_liquidity || edge.to == numeraire || via.path.contains(&edge.to) {
+                        continue;
+                    }
+                    let best = improved
+                        .get(&edge.to)
+                        .or_else(|| quotes.get(&edge.to))
+                        .map_or(f64::NEG_INFINITY, |q: &PriceQuote| q.liquidity);
+                    if liquidity > best {
+                        let mut path = vec![edge.to];
+                        path.extend(&via.path);
+                        improved.insert(
+                            edge.to,
+                            PriceQuote {
+                                token: edge.to,
+                                chain_id,
+                                price: edge.rate * via.price,
+                                liquidity,
+                                path,
+                            },
+                        );
+                    }
+                }
+            }
+            if improved.is_empty() {
+                break;
+            }
+            frontier = improved.keys().copied().collect();
+            quotes.extend(improved);
+        }
+
+        Self { chain_id, quotes }
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn quote(&self, token: Address) -> Option<&PriceQuote> {
+        self.quotes.get(&token)
+    }
+
+    /// Numeraire per `token`, if it can be routed.
+    pub fn price(&self, token: Address) -> Option<f64> {
+        self.quote(token).map(|q| q.price)
+    }
+}
+
+/// Prices tokens in USD through the pools in storage, one cached [`PriceGraph`] per chain.
+pub struct PriceOracle {
+    storage: Arc<dyn Storage>,
+    config: PricingConfig,
+    graphs: RwLock<HashMap<u64, (Instant, Arc<PriceGraph>)>>,
+}
+
+impl PriceOracle {
+    pub fn new(storage: Arc<dyn Storage>, config: PricingConfig) -> Self {
+        Self {
+            storage,
+            config,
+            graphs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Price graph of `chain_id`, rebuilt when older than `cache_secs`. Returns `None` when the
+    /// chain has no valid numeraire.
+    pub async fn graph(&self, chain_id: u64) -> Result<Option<Arc<PriceGraph>>> {
+        let ttl = Duration::from_secs(self.config.cache_secs);
+        if let Some((built, graph)) = self.graphs.read().await.get(&chain_id) {
+            if built.elapsed() < ttl {
+                return Ok(Some(graph.clone()));
+            }
+        }
+
+        let Some(numeraire) = self.numeraire(chain_id) else {
+            return Ok(None);
+        };
+        let distributions = self
+            .storage
+            .list_liquidity_distributions(Some(chain_id))
+            .await?;
+        let graph = Arc::new(PriceGraph::build(
+            chain_id,
+            numeraire,
+            &distributions,
+            self.config.max_hops,
+            self.config.min_liquidity,
+        ));
+        self.graphs
+            .write()
+            .await
+            .insert(chain_id, (Instant::now(), graph.clone()));
+        Ok(Some(graph))
+    }
+
+    /// Address of the token `chain_id` is priced in, if one is configured and valid.
+    pub fn numeraire(&self, chain_id: u64) -> Option<Address> {
+        let Some(numeraire) = self.config.numeraire(chain_id) else {
+            debug!("No pricing numeraire for chain {}", chain_id);
+            return None;
+        };
+        match Address::from_str(numeraire) {
+            Ok(address) => Some(address),
+            Err(_) => {
+                warn!("Invalid pricing numeraire {} for chain {}", numeraire, chain_id);
+                None
+            }
+        }
+    }
+
+    pub async fn quote(&self, token: Address, chain_id: u64) -> Result<Option<PriceQuote>> {
+        Ok(self
+            .graph(chain_id)
+            .await?
+            .and_then(|graph| graph.quote(token).cloned()))
+    }
+
+    /// USD (numeraire) price of `token`, if it can be routed.
+    pub async fn usd_price(&self, token: Address, chain_id: u64) -> Result<Option<f64>> {
+        Ok(self.quote(token, chain_id).await?.map(|q| q.price))
+    }
+}
@@ -0,0 +1,332 @@
+use crate::models::{LiquidityDistribution, PoolAllocation, RouteAllocation};
+use crate::storage::Storage;
+use crate::Result;
+use alloy_primitives::Address;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Each price level is cut into this many slices of constant price.
+const SLICES: usize = 32;
+/// Paths kept per quote, best spot price first.
+const MAX_ROUTES: usize = 32;
+
+/// One side of a pool: what selling a token into it returns, best rate first.
+#[derive(Debug, Clone)]
+struct Ladder {
+    /// Output per unit of input after the fee, and the output available at that rate.
+    steps: Vec<(f64, f64)>,
+    /// Step being filled and the output already taken from it.
+    step: usize,
+    used: f64,
+}
+
+impl Ladder {
+    fn new(mut steps: Vec<(f64, f64)>) -> Self {
+        steps.retain(|(rate, capacity)| {
+            rate.is_finite() && *rate > 0.0 && capacity.is_finite() && *capacity > 0.0
+        });
+        steps.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Self {
+            steps,
+            step: 0,
+            used: 0.0,
+        }
+    }
+
+    /// Rate of the next unit of input, if any liquidity is left.
+    fn marginal(&self) -> Option<f64> {
+        self.steps.get(self.step).map(|(rate, _)| *rate)
+    }
+
+    /// Output for `amount_in` and the position it leaves the ladder at, or `None` when the
+    /// ladder runs out first.
+    fn walk(&self, mut amount_in: f64) -> Option<(f64, usize, f64)> {
+        let (mut step, mut used, mut out) = (self.step, self.used, 0.0);
+        while amount_in > 0.0 {
+            let (rate, capacity) = *self.steps.get(step)?;
+            let available = capacity - used;
+            let needed = available / rate;
+            if amount_in < needed {
+                out += amount_in * rate;
+                used += amount_in * rate;
+                amount_in = 0.0;
+            } else {
+                out += available;
+                amount_in -= needed;
+                step += 1;
+                used = 0.0;
+            }
+        }
+        Some((out, step, used))
+    }
+
+    fn quote(&self, amount_in: f64) -> Option<f64> {
+        self.walk(amount_in).map(|(out, _, _)| out)
+    }
+
+    fn swap(&mut self, amount_in: f64) -> Option<f64> {
+        let (out, step, used) = self.walk(amount_in)?;
+        (self.step, self.used) = (step, used);
+        Some(out)
+    }
+}
+
+/// The latest distribution of one pool, as two ladders.
+#[derive(Debug, Clone)]
+struct Venue {
+    dex: String,
+    pool: Option<Address>,
+    token0: Address,
+    token1: Address,
+    fee: f64,
+    /// Selling token0 for token1, down the bids.
+    sell0: Ladder,
+    /// Selling token1 for token0, up the asks.
+    sell1: Ladder,
+}
+
+impl Venue {
+    fn new(distribution: &LiquidityDistribution, pool: Option<Address>, fee: f64) -> Self {
+        let keep = 1.0 - fee;
+        let (mut bids, mut asks) = (Vec::new(), Vec::new());
+        for level in &distribution.price_levels {
+            let width = level.upper_price - level.lower_price;
+            let slices = if width > 0.0 { SLICES } else { 1 };
+            for i in 0..slices {
+                let price = level.lower_price + (i as f64 + 0.5) * width / slices as f64;
+                bids.push((price * keep, level.token1_liquidity / slices as f64));
+                asks.push((keep / price, level.token0_liquidity / slices as f64));
+            }
+        }
+        Self {
+            dex: distribution.dex.clone(),
+            pool,
+            token0: distribution.token0.address,
+            token1: distribution.token1.address,
+            fee,
+            sell0: Ladder::new(bids),
+            sell1: Ladder::new(asks),
+        }
+    }
+
+    /// The ladder selling `token`, with the token received.
+    fn side(&self, token: Address) -> Option<(&Ladder, Address)> {
+        if token == self.token0 {
+            Some((&self.sell0, self.token1))
+        } else if token == self.token1 {
+            Some((&self.sell1, self.token0))
+        } else {
+            None
+        }
+    }
+
+    fn side_mut(&mut self, token: Address) -> Option<&mut Ladder> {
+        if token == self.token0 {
+            Some(&mut self.sell0)
+        } else if token == self.token1 {
+            Some(&mut self.sell1)
+        } else {
+            None
+        }
+    }
+}
+
+/// A path through the venues: each hop is a venue and the token sold into it.
+type Path = Vec<(usize, Address)>;
+
+/// Split of one swap across routes and pools.
+#[derive(Debug, Clone, Default)]
+pub struct RoutedSwap {
+    pub amount_out: f64,
+    /// token_out per token_in for the first unit through the best route, after fees.
+    pub spot_price: f64,
+    /// Input the pools could not absorb.
+    pub unfilled: f64,
+    pub routes: Vec<RouteAllocation>,
+    pub pools: Vec<PoolAllocation>,
+}
+
+/// Simulates swaps over the stored liquidity of every indexed pool.
+///
+/// Each pool is modelled by its latest distribution: liquidity spread evenly over each level's
+/// price range, taken at the level's prices less the pool's fee. Every fee tier of a pair is a
+/// venue of its own.
+pub struct QuoteRouter {
+    venues: Vec<Venue>,
+}
+
+impl QuoteRouter {
+    /// Router over `distributions`, each with the pool it stands for and its fee (0.003 = 0.3%).
+    pub fn new<'a>(
+        distributions: impl IntoIterator<Item = (&'a LiquidityDistribution, Option<Address>, f64)>,
+    ) -> Self {
+        let venues = distributions
+            .into_iter()
+            .filter(|(d, _, _)| d.token0.address != d.token1.address)
+            .map(|(d, pool, fee)| Venue::new(d, pool, fee))
+            .collect();
+        Self { venues }
+    }
+
+    /// Router over the latest distributions stored for `chain_id`, on `dex` only when set.
+    pub async fn load(storage: Arc<dyn Storage>, chain_id: u64, dex: Option<&str>) -> Result<Self> {
+        let fees: HashMap<Address, u64> = storage
+            .list_pools(Some(chain_id), dex)
+            .await?
+            .into_iter()
+            .map(|pool| (pool.address, pool.fee))
+            .collect();
+
+        // Snapshots saved before distributions were kept per pool have no pool, and no fee.
+        let distributions = storage.list_liquidity_distributions(Some(chain_id)).await?;
+        let venues = distributions
+            .iter()
+            .filter(|d| dex.is_none_or(|dex| d.dex == dex))
+            .map(|d| match d.pool.and_then(|pool| fees.get(&pool).map(|fee| (pool, *fee))) {
+                Some((pool, fee)) => (d, Some(pool), fee as f64 / 1_000_000.0),
+                None => (d, d.pool, 0.0),
+            });
+        Ok(Self::new(venues))
+    }
+
+    /// Best split of `amount_in` of `token_in` into `token_out` over paths of at most
+    /// `max_hops` pools, or `None` when no path connects the tokens.
+    ///
+    /// The input is sent in `parts` equal chunks, each along the path that returns the most for
+    /// it given what earlier chunks took from the pools, so pools fill until their marginal
+    /// prices meet. A chunk no path can absorb is left unfilled.
+    pub fn quote(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: f64,
+        max_hops: usize,
+        parts: usize,
+    ) -> Option<RoutedSwap> {
+        let mut paths = self.paths(token_in, token_out, max_hops);
+        let spot = |path: &Path| -> f64 {
+            path.iter()
+                .map(|(venue, sold)| {
+                    self.venues[*venue]
+                        .side(*sold)
+                        .and_then(|(ladder, _)| ladder.marginal())
+                        .unwrap_or(0.0)
+                })
+                .product()
+        };
+        paths.sort_by(|a, b| spot(b).total_cmp(&spot(a)));
+        paths.truncate(MAX_ROUTES);
+        let spot_price = paths.first().map(spot)?;
+        if spot_price <= 0.0 {
+            return None;
+        }
+
+        let mut venues = self.venues.clone();
+        let parts = parts.max(1);
+        let chunk = amount_in / parts as f64;
+        let mut routed = vec![(0.0, 0.0); paths.len()];
+        let mut pools: HashMap<(usize, Address), (f64, f64)> = HashMap::new();
+        let mut swap = RoutedSwap {
+            spot_price,
+            ..RoutedSwap::default()
+        };
+        for _ in 0..parts {
+            let best = paths
+                .iter()
+                .enumerate()
+                .filter_map(|(i, path)| simulate(&venues, path, chunk).map(|out| (i, out)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let Some((i, _)) = best else {
+                swap.unfilled += chunk;
+                continue;
+            };
+            let mut amount = chunk;
+            for (venue, sold) in &paths[i] {
+                let Some(out) = venues[*venue].side_mut(*sold).and_then(|l| l.swap(amount)) else {
+                    break;
+                };
+                let pool = pools.entry((*venue, *sold)).or_default();
+                pool.0 += amount;
+                pool.1 += out;
+                amount = out;
+            }
+            routed[i].0 += chunk;
+            routed[i].1 += amount;
+            swap.amount_out += amount;
+        }
+
+        swap.routes = paths
+            .iter()
+            .zip(routed)
+            .filter(|(_, (amount_in, _))| *amount_in > 0.0)
+            .map(|(path, (amount_in, amount_out))| {
+                let mut tokens = vec![token_in];
+                tokens.extend(
+                    path.iter()
+                        .filter_map(|(v, sold)| self.venues[*v].side(*sold).map(|(_, to)| to)),
+                );
+                RouteAllocation {
+                    path: tokens,
+                    dexes: path.iter().map(|(v, _)| self.venues[*v].dex.clone()).collect(),
+                    amount_in,
+                    amount_out,
+                }
+            })
+            .collect();
+        swap.routes.sort_by(|a, b| b.amount_in.total_cmp(&a.amount_in));
+        swap.pools = pools
+            .into_iter()
+            .map(|((v, sold), (amount_in, amount_out))| {
+                let venue = &self.venues[v];
+                let token_out = if sold == venue.token0 { venue.token1 } else { venue.token0 };
+                PoolAllocation {
+                    dex: venue.dex.clone(),
+                    pool: venue.pool,
+                    token_in: sold,
+                    token_out,
+                    fee: venue.fee,
+                    amount_in,
+                    amount_out,
+                }
+            })
+            .collect();
+        swap.pools.sort_by(|a, b| b.amount_in.total_cmp(&a.amount_in));
+        Some(swap)
+    }
+
+    /// Every path from `from` to `to` of at most `max_hops` venues, visiting no token twice.
+    fn paths(&self, from: Address, to: Address, max_hops: usize) -> Vec<Path> {
+        let mut paths = Vec::new();
+        let mut stack: Vec<(Address, Path, Vec<Address>)> = vec![(from, Vec::new(), vec![from])];
+        while let Some((token, path, seen)) = stack.pop() {
+            if path.len() == max_hops {
+                continue;
+            }
+            for (i, venue) in self.venues.iter().enumerate() {
+                let Some((_, next)) = venue.side(token) else {
+                    continue;
+                };
+                if seen.contains(&next) {
+                    continue;
+                }
+                let mut path = path.clone();
+                path.push((i, token));
+                if next == to {
+                    paths.push(path);
+                } else {
+                    let mut seen = seen.clone();
+                    seen.push(next);
+                    stack.push((next, path, seen));
+                }
+            }
+        }
+        paths
+    }
+}
+
+/// Output of sending `amount_in` along `path`, or `None` when a pool on it runs dry.
+fn simulate(venues: &[Venue], path: &Path, amount_in: f64) -> Option<f64> {
+    path.iter().try_fold(amount_in, |amount, (venue, sold)| {
+        venues[*venue].side(*sold)?.0.quote(amount)
+    })
+}
@@ -1,79 +1,69 @@
 use crate::models::{LevelType, PriceLiquidity, SupportResistanceLevel, Token};
-use crate::utils::calculate_support_resistance_strength;
 
 /// Identify support and resistance levels from price liquidity data
+///
+/// Each level is valued in token1 at its middle price, and in USD when `token1_usd` is known.
+/// Only local peaks, levels holding more liquidity than the levels next to them, can be support
+/// or resistance: support below `spot_price`, resistance above it, and neutral when the level
+/// contains it. The strength of a level is its liquidity relative to the largest level, so the
+/// deepest one has strength 1.0. Levels weaker than `min_strength` are dropped and the rest
+/// are ranked strongest first.
 pub fn identify_support_resistance(
     price_levels: &[PriceLiquidity],
+    spot_price: f64,
+    token1_usd: Option<f64>,
     token0: &Token,
     token1: &Token,
     min_strength: f64,
 ) -> Vec<SupportResistanceLevel> {
-    let mut levels = Vec::new();
-
-    if price_levels.is_empty() {
-        return levels;
-    }
-
-    // Calculate total liquidity for strength calculation
-    let total_liquidity: f64 = price_levels
+    let mut valued: Vec<(&PriceLiquidity, f64)> = price_levels
         .iter()
-        .map(|pl| pl.token0_liquidity + pl.token1_liquidity)
-        .sum();
+        .map(|pl| {
+            let mid = (pl.lower_price + pl.upper_price) / 2.0;
+            (pl, pl.token0_liquidity * mid + pl.token1_liquidity)
+        })
+        .filter(|(_, value)| value.is_finite() && *value > 0.0)
+        .collect();
+    valued.sort_by(|a, b| a.0.lower_price.total_cmp(&b.0.lower_price));
 
-    // Calculate price ranges
-    let prices: Vec<f64> = price_levels.iter().map(|pl| pl.lower_price).collect();
-    let min_price = prices.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-    let max_price = prices.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-    let price_range = max_price - min_price;
+    let largest = valued.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+    if largest <= 0.0 {
+        return Vec::new();
+    }
 
-    for (i, pl) in price_levels.iter().enumerate() {
-        // Skip first and last price levels
-        if i == 0 || i == price_levels.len() - 1 {
+    let mut levels = Vec::new();
+    for (i, (pl, value)) in valued.iter().enumerate() {
+        let prev = i.checked_sub(1).map_or(0.0, |j| valued[j].1);
+        let next = valued.get(i + 1).map_or(0.0, |(_, v)| *v);
+        if *value <= prev || *value < next {
             continue;
         }
 
-        let _prev_pl = &price_levels[i - 1];
-        let _next_pl = &price_levels[i + 1];
-
-        // Calculate combined liquidity at this level
-        let combined_liquidity = pl.token0_liquidity + pl.token1_liquidity;
-
-        // Calculate strength based on relative liquidity concentration
-        let strength = calculate_support_resistance_strength(
-            pl.lower_price,
-            combined_liquidity,
-            total_liquidity,
-            price_range,
-        );
+        let strength = value / largest;
+        if strength < min_strength {
+            continue;
+        }
 
-        // If strength above threshold, determine if it's support or resistance
-        if strength >= min_strength {
-            // Support: more buy-side (token0) liquidity
-            // Resistance: more sell-side (token1) liquidity
-            let level_type = if pl.token0_liquidity > pl.token1_liquidity {
-                LevelType::Support
-            } else if pl.token1_liquidity > pl.token0_liquidity {
-                LevelType::Resistance
-            } else {
-                LevelType::Neutral
-            };
+        let level_type = if pl.upper_price <= spot_price {
+            LevelType::Support
+        } else if pl.lower_price >= spot_price {
+            LevelType::Resistance
+        } else {
+            LevelType::Neutral
+        };
 
-            levels.push(SupportResistanceLevel {
-                price: pl.lower_price,
-                strength,
-                level_type,
-                token0: token0.clone(),
-                token1: token1.clone(),
-            });
-        }
+        levels.push(SupportResistanceLevel {
+            price: (pl.lower_price + pl.upper_price) / 2.0,
+            strength,
+            level_type,
+            liquidity_usd: token1_usd.map(|usd| value * usd),
+            token0: token0.clone(),
+            token1: token1.clone(),
+        });
     }
 
     // Sort by strength (descending)
-    levels.sort_by(|a, b| {
-        b.strength
-            .partial_cmp(&a.strength)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    levels.sort_by(|a, b| b.strength.total_cmp(&a.strength));
 
     levels
 }
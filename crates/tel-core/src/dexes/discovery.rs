@@ -0,0 +1,109 @@
+use crate::dexes::DexProtocol;
+use crate::error::Error;
+use crate::Result;
+use tracing::{info, warn};
+
+/// Checkpoint stream used for factory event discovery.
+pub const DISCOVERY_STREAM: &str = "discovery";
+
+/// Block span requested per `eth_getLogs` call until a provider pushes back.
+pub const DEFAULT_LOG_RANGE: u64 = 10_000;
+
+/// Walks `[from, head]` in contiguous block ranges sized for `eth_getLogs`.
+///
+/// A range the provider rejects is split in half and retried; after a success the span
+/// doubles again, up to `max_span`.
+#[derive(Debug, Clone)]
+pub struct BlockScan {
+    next: u64,
+    head: u64,
+    span: u64,
+    max_span: u64,
+}
+
+impl BlockScan {
+    pub fn new(from: u64, head: u64, max_span: u64) -> Self {
+        let max_span = max_span.max(1);
+        Self {
+            next: from,
+            head,
+            span: max_span,
+            max_span,
+        }
+    }
+
+    /// The range to query next, or `None` once `head` has been covered.
+    pub fn next_range(&self) -> Option<(u64, u64)> {
+        if self.next > self.head {
+            return None;
+        }
+        let to = self.next.saturating_add(self.span - 1).min(self.head);
+        Some((self.next, to))
+    }
+
+    /// Marks the current range as done and widens the span again.
+    pub fn advance(&mut self) {
+        if let Some((_, to)) = self.next_range() {
+            self.next = to + 1;
+            self.span = self.span.saturating_mul(2).min(self.max_span);
+        }
+    }
+
+    /// Halves the current range after a rejected query.
+    ///
+    /// Returns `false` when the range is already a single block and cannot be split.
+    pub fn split(&mut self) -> bool {
+        let Some((from, to)) = self.next_range() else {
+            return false;
+        };
+        let len = to - from + 1;
+        if len <= 1 {
+            return false;
+        }
+        self.span = len / 2;
+        true
+    }
+}
+
+/// Discovers pools created by `dex`'s factory since the last checkpoint, up to the chain head.
+///
+/// Scanning starts at `start_block` (or the DEX's deployment block) on the first run and after
+/// the stored checkpoint on later runs. The checkpoint is saved after every range, so an
+/// interrupted scan resumes where it stopped. Returns the number of pools discovered.
+pub async fn discover_pools(
+    dex: &dyn DexProtocol,
+    start_block: Option<u64>,
+    max_span: u64,
+) -> Result<usize> {
+    let storage = dex.storage();
+    let (chain_id, name) = (dex.chain_id(), dex.name());
+    let head = dex.provider().block_number().await?;
+
+    let from = match storage.get_checkpoint(chain_id, name, DISCOVERY_STREAM).await? {
+        Some(block) => block + 1,
+        None => start_block.unwrap_or_else(|| dex.deployment_block()),
+    };
+    if from > head {
+        return Ok(0);
+    }
+    info!("Discovering {} pools on chain {} from block {} to {}", name, chain_id, from, head);
+
+    let mut scan = BlockScan::new(from, head, max_span);
+    let mut discovered = 0;
+    while let Some((from, to)) = scan.next_range() {
+        match dex.discover_pools_in_range(from, to).await {
+            Ok(pools) => {
+                discovered += pools.len();
+                storage.save_checkpoint(chain_id, name, DISCOVERY_STREAM, to).await?;
+                scan.advance();
+            }
+            Err(Error::ProviderError(e)) if scan.split() => {
+                warn!("getLogs {}..{} rejected for {}, splitting range: {}", from, to, name, e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    info!("Discovered {} new {} pools on chain {}", discovered, name, chain_id);
+    Ok(discovered)
+}
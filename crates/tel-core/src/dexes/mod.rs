@@ -1,5 +1,7 @@
 pub mod balancer;
 pub mod curve;
+pub mod discovery;
+pub mod state;
 pub mod sushiswap;
 pub mod uniswap_v2;
 pub mod uniswap_v3;
@@ -9,6 +11,7 @@ use crate::error::Error;
 use crate::models::{LiquidityDistribution, Pool, Token, V3LiquidityDistribution};
 use crate::providers::EthereumProvider;
 use crate::storage::Storage;
+use state::{PoolLog, PoolState};
 use alloy_primitives::Address;
 use async_trait::async_trait;
 use std::sync::Arc; // Remove if already present to avoid duplication
@@ -37,9 +40,67 @@ pub trait DexProtocol: Send + Sync {
     /// Get all pools from full node
     async fn get_all_pools(&self) -> Result<Vec<Pool>, Error>;
 
+    /// Get the block the factory was deployed at, where pool discovery starts by default
+    fn deployment_block(&self) -> u64 {
+        0
+    }
+
+    /// Discover and save the pools created by the factory within `[from_block, to_block]`
+    ///
+    /// Only a failed log query is returned as `Error::ProviderError`, so callers can retry it
+    /// over a smaller range. A pool whose tokens cannot be loaded fails the whole range, so the
+    /// range is scanned again instead of the pool being lost.
+    async fn discover_pools_in_range(
+        &self,
+        _from_block: u64,
+        _to_block: u64,
+    ) -> Result<Vec<Pool>, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    /// Read the current state of `pool` from the chain, for incremental event updates
+    ///
+    /// DEXes that return `Error::NotImplemented` are refreshed with full snapshots instead.
+    async fn load_pool_state(&self, _pool: &Pool) -> Result<PoolState, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    /// State of `pool` right after creation, from which its history is rebuilt by replaying
+    /// every log since the creation block
+    async fn initial_pool_state(&self, _pool: &Pool) -> Result<PoolState, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    /// Read the state of `pool` as of `block`; needs an archive node for old blocks
+    async fn load_pool_state_at(&self, _pool: &Pool, _block: u64) -> Result<PoolState, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    /// Fetch the state-changing logs of `pools` within `[from_block, to_block]`, in chain order
+    ///
+    /// Only a failed log query is returned as `Error::ProviderError`, so callers can retry it
+    /// over a smaller range.
+    async fn get_pool_logs(
+        &self,
+        _pools: &[Address],
+        _from_block: u64,
+        _to_block: u64,
+    ) -> Result<Vec<PoolLog>, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    /// Compute the liquidity distribution of `pool` from a state, without touching the chain
+    fn distribution_from_state(
+        &self,
+        _pool: &Pool,
+        _state: &PoolState,
+    ) -> Result<LiquidityDistribution, Error> {
+        Err(Error::NotImplemented)
+    }
+
     /// Get all pools from local database
     async fn get_all_pools_local(&self) -> Result<Vec<Pool>, Error> {
-        let pools = self.storage().get_pools_by_dex(self.name(), self.chain_id())?;
+        let pools = self.storage().get_pools_by_dex(self.name(), self.chain_id()).await?;
         Ok(pools)
     }
 
@@ -70,8 +131,31 @@ pub trait DexProtocol: Send + Sync {
         Err(Error::DexError("Not implemented for this DEX".to_string()))
     }
 
-    async fn get_all_pools_test(&self) -> Result<Vec<Pool>, Error> {
-        Err(Error::DexError("Not implemented for this DEX".to_string()))
+    /// Get pools for the given addresses, registering any that are not stored yet
+    ///
+    /// Addresses that cannot be resolved to a pool of this DEX are skipped.
+    async fn get_pools_by_address(&self, addresses: &[Address]) -> Result<Vec<Pool>, Error> {
+        let mut pools = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            if let Ok(pool) = self.get_pool(*address).await {
+                pools.push(pool);
+            }
+        }
+        Ok(pools)
+    }
+
+    /// Get the addresses of the pools the factory holds for a token pair, in every fee tier
+    ///
+    /// Lets a pair be tracked before discovery has stored its pools.
+    async fn find_pools(&self, _token0: Address, _token1: Address) -> Result<Vec<Address>, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    /// Get the amount of each token `pool` holds, in whole tokens
+    ///
+    /// A cheap size signal for pools without a snapshot; reads both tokens' `balanceOf`.
+    async fn pool_balances(&self, pool: &Pool) -> Result<(f64, f64), Error> {
+        utils::pool_balances(self.provider(), pool).await
     }
 }
 
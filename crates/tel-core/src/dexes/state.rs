@@ -0,0 +1,201 @@
+use alloy_primitives::{Address, U256};
+use std::collections::BTreeMap;
+
+/// Checkpoint stream used for pool event polling.
+pub const EVENTS_STREAM: &str = "events";
+
+/// A pool log that changes the state a liquidity distribution is derived from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolEvent {
+    /// V2 `Sync`: the reserves after a swap, mint or burn.
+    Sync { reserve0: u128, reserve1: u128 },
+    /// V3 `Initialize`: the starting price of a new pool.
+    Initialize { sqrt_price_x96: U256, tick: i32 },
+    /// V3 `Swap`: the price, in-range liquidity and tick after the swap.
+    Swap {
+        sqrt_price_x96: U256,
+        liquidity: u128,
+        tick: i32,
+    },
+    /// V3 `Mint`: liquidity added to `[tick_lower, tick_upper)`.
+    Mint {
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    },
+    /// V3 `Burn`: liquidity removed from `[tick_lower, tick_upper)`.
+    Burn {
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    },
+}
+
+/// A decoded pool event with its position in the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolLog {
+    pub pool: Address,
+    pub block_number: u64,
+    pub log_index: u64,
+    pub event: PoolEvent,
+}
+
+/// Concentrated liquidity state of a V3 pool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct V3State {
+    pub sqrt_price_x96: U256,
+    /// Liquidity active at the current tick.
+    pub liquidity: u128,
+    pub tick: i32,
+    pub tick_spacing: i32,
+    /// Initialized ticks mapped to `(liquidity_gross, liquidity_net)`.
+    pub ticks: BTreeMap<i32, (u128, i128)>,
+    /// Inclusive range of ticks read from the chain, outside of which `ticks` is unknown;
+    /// `None` when every tick is known, as for a state replayed from the pool's creation.
+    pub tick_range: Option<(i32, i32)>,
+}
+
+/// The on-chain state a pool's liquidity distribution is computed from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolState {
+    V2 { reserve0: u128, reserve1: u128 },
+    V3(V3State),
+}
+
+impl PoolState {
+    /// Applies `event` to the state and reports whether anything changed.
+    ///
+    /// Events of the other pool type are ignored.
+    pub fn apply(&mut self, event: &PoolEvent) -> bool {
+        match (self, event) {
+            (
+                PoolState::V2 { reserve0, reserve1 },
+                PoolEvent::Sync {
+                    reserve0: r0,
+                    reserve1: r1,
+                },
+            ) => {
+                let changed = (*reserve0, *reserve1) != (*r0, *r1);
+                (*reserve0, *reserve1) = (*r0, *r1);
+                changed
+            }
+            (
+                PoolState::V3(state),
+                PoolEvent::Swap {
+                    sqrt_price_x96,
+                    liquidity,
+                    tick,
+                },
+            ) => {
+                let changed = (state.sqrt_price_x96, state.liquidity, state.tick)
+                    != (*sqrt_price_x96, *liquidity, *tick);
+                state.sqrt_price_x96 = *sqrt_price_x96;
+                state.liquidity = *liquidity;
+                state.tick = *tick;
+                changed
+            }
+            (
+                PoolState::V3(state),
+                PoolEvent::Initialize {
+                    sqrt_price_x96,
+                    tick,
+                },
+            ) => {
+                let changed = (state.sqrt_price_x96, state.tick) != (*sqrt_price_x96, *tick);
+                state.sqrt_price_x96 = *sqrt_price_x96;
+                state.tick = *tick;
+                changed
+            }
+            (
+                PoolState::V3(state),
+                PoolEvent::Mint {
+                    tick_lower,
+                    tick_upper,
+                    amount,
+                },
+            ) => state.update_position(*tick_lower, *tick_upper, *amount as i128),
+            (
+                PoolState::V3(state),
+                PoolEvent::Burn {
+                    tick_lower,
+                    tick_upper,
+                    amount,
+                },
+            ) => state.update_position(*tick_lower, *tick_upper, -(*amount as i128)),
+            _ => false,
+        }
+    }
+
+    /// Whether `event` only involves ticks this state knows.
+    ///
+    /// A V3 read only covers the ticks around the current price. An event touching a tick
+    /// outside them, or moving the price out of them, cannot be applied correctly; the pool
+    /// has to be read again instead.
+    pub fn covers(&self, event: &PoolEvent) -> bool {
+        let PoolState::V3(V3State {
+            tick_range: Some((low, high)),
+            ..
+        }) = self
+        else {
+            return true;
+        };
+        let known = |tick: &i32| (low..=high).contains(&tick);
+        match event {
+            PoolEvent::Swap { tick, .. } | PoolEvent::Initialize { tick, .. } => known(tick),
+            PoolEvent::Mint {
+                tick_lower,
+                tick_upper,
+                ..
+            }
+            | PoolEvent::Burn {
+                tick_lower,
+                tick_upper,
+                ..
+            } => known(tick_lower) && known(tick_upper),
+            PoolEvent::Sync { .. } => true,
+        }
+    }
+
+    /// Whether this event-derived state agrees with a `fresh` read from the chain.
+    ///
+    /// V3 ticks are compared over the range `fresh` was read for.
+    pub fn consistent_with(&self, fresh: &PoolState) -> bool {
+        match (self, fresh) {
+            (PoolState::V3(tracked), PoolState::V3(fresh)) => {
+                let (low, high) = fresh.tick_range.unwrap_or((i32::MIN, i32::MAX));
+                (tracked.sqrt_price_x96, tracked.liquidity, tracked.tick)
+                    == (fresh.sqrt_price_x96, fresh.liquidity, fresh.tick)
+                    && tracked
+                        .ticks
+                        .range(low..=high)
+                        .eq(fresh.ticks.range(low..=high))
+            }
+            _ => self == fresh,
+        }
+    }
+}
+
+impl V3State {
+    /// Adds `delta` liquidity to the position `[tick_lower, tick_upper)`, mirroring the pool's
+    /// tick bookkeeping. Ticks whose gross liquidity drops to zero are uninitialized.
+    fn update_position(&mut self, tick_lower: i32, tick_upper: i32, delta: i128) -> bool {
+        if delta == 0 {
+            return false;
+        }
+        self.update_tick(tick_lower, delta, false);
+        self.update_tick(tick_upper, delta, true);
+        if tick_lower <= self.tick && self.tick < tick_upper {
+            self.liquidity = self.liquidity.saturating_add_signed(delta);
+        }
+        true
+    }
+
+    fn update_tick(&mut self, tick: i32, delta: i128, upper: bool) {
+        let (gross, net) = self.ticks.entry(tick).or_insert((0, 0));
+        *gross = gross.saturating_add_signed(delta);
+        *net += if upper { -delta } else { delta };
+        if *gross == 0 {
+            self.ticks.remove(&tick);
+        }
+    }
+}
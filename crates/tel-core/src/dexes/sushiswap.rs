@@ -1,6 +1,6 @@
-use crate::dexes::DexProtocol;
+use crate::dexes::{uniswap_v2, DexProtocol};
 use crate::error::Error;
-use crate::models::{LiquidityDistribution, Pool, PriceLiquidity, Side, Token};
+use crate::models::{LiquidityDistribution, Pool, PriceLiquidity, Side};
 use crate::providers::EthereumProvider;
 use alloy_primitives::Address;
 use async_trait::async_trait;
@@ -8,6 +8,9 @@ use chrono::Utc;
 use std::sync::Arc;
 use crate::storage::Storage;
 
+/// Block the mainnet Sushiswap factory was deployed at.
+const SUSHISWAP_FACTORY_DEPLOYMENT_BLOCK: u64 = 10_794_229;
+
 // Sushiswap is a fork of Uniswap V2, so the implementation is very similar
 pub struct Sushiswap {
     provider: Arc<EthereumProvider>,
@@ -47,48 +50,29 @@ impl DexProtocol for Sushiswap {
         self.storage.clone()
     }
 
-    /// Asynchronously retrieves information about a Sushiswap pool at the specified address.
-    ///
-    /// Currently returns a placeholder `Pool` with dummy token data and static metadata.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// # use your_crate::{Sushiswap, Address};
-    /// # async fn example(sushi: Sushiswap, pool_addr: Address) {
-    /// let pool = sushi.get_pool(pool_addr).await.unwrap();
-    /// assert_eq!(pool.dex, "sushiswap");
-    /// # }
-    /// ```
-    async fn get_pool(&self, pool_address: Address) -> Result<Pool, Error> {
-        // For now, this is a simple placeholder that returns dummy data
-        let token0 = Token {
-            address: Address::ZERO,
-            symbol: "DUMMY0".to_string(),
-            name: "Dummy Token 0".to_string(),
-            decimals: 18,
-            chain_id: self.chain_id(),
-        };
+    fn deployment_block(&self) -> u64 {
+        SUSHISWAP_FACTORY_DEPLOYMENT_BLOCK
+    }
 
-        let token1 = Token {
-            address: Address::ZERO,
-            symbol: "DUMMY1".to_string(),
-            name: "Dummy Token 1".to_string(),
-            decimals: 18,
-            chain_id: self.chain_id(),
-        };
+    /// Same `PairCreated` event as Uniswap V2, so its discovery is reused.
+    async fn discover_pools_in_range(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Pool>, Error> {
+        uniswap_v2::discover_pairs(self, from_block, to_block).await
+    }
 
-        Ok(Pool {
-            address: pool_address,
-            dex: self.name().to_string(),
-            chain_id: self.chain_id(),
-            tokens: vec![token0, token1],
-            creation_block: 0,
-            creation_timestamp: Utc::now(),
-            last_updated_block: 0,
-            last_updated_timestamp: Utc::now(),
-            fee: 3000,
-        })
+    async fn find_pools(&self, token0: Address, token1: Address) -> Result<Vec<Address>, Error> {
+        uniswap_v2::find_pair(self, token0, token1).await
+    }
+
+    /// Returns the stored pool, or reads the pair from the chain like Uniswap V2 does.
+    async fn get_pool(&self, pool_address: Address) -> Result<Pool, Error> {
+        if let Some(pool) = self.storage.get_pool(pool_address, self.chain_id()).await? {
+            return Ok(pool);
+        }
+        uniswap_v2::load_pair(self, pool_address).await
     }
 
     async fn get_all_pools(&self) -> Result<Vec<Pool>, Error> {
@@ -138,6 +122,7 @@ impl DexProtocol for Sushiswap {
             chain_id: self.chain_id(),
             price_levels: vec![price_level],
             timestamp: Utc::now(),
+            pool: Some(pool_address),
         })
     }
 
@@ -2,16 +2,18 @@ use crate::dexes::DexProtocol;
 use crate::error::Error;
 use crate::models::{LiquidityDistribution, Pool, PriceLiquidity, Side, Token};
 use crate::providers::EthereumProvider;
-use crate::storage::{
-    get_pool_async, get_token_async, save_liquidity_distribution_async, save_pool_async,
-    save_token_async, Storage,
-};
-use alloy_primitives::{Address, U256};
-use alloy_sol_types::sol;
+use crate::dexes::utils;
+use crate::storage::{get_pool_async, save_pool_async, Storage};
+use crate::dexes::discovery::{discover_pools, DEFAULT_LOG_RANGE};
+use crate::dexes::state::{PoolEvent, PoolLog, PoolState};
+use alloy_primitives::Address;
+use alloy_provider::Provider;
+use alloy_rpc_types::{BlockId, Filter};
+use alloy_sol_types::{sol, SolEvent};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use tracing::warn;
 use std::sync::Arc;
-use std::str::FromStr;
 
 sol! {
     // ── Uniswap V2 Factory ───────────────────────────────────────────
@@ -19,6 +21,9 @@ sol! {
     interface IUniswapV2Factory {
         function allPairsLength() external view returns (uint256);
         function allPairs(uint256) external view returns (address);
+        function getPair(address tokenA, address tokenB) external view returns (address pair);
+
+        event PairCreated(address indexed token0, address indexed token1, address pair, uint256 pairIndex);
     }
 
     // ── Uniswap V2 Pair ──────────────────────────────────────────────
@@ -27,16 +32,14 @@ sol! {
         function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast);
         function token0() external view returns (address);
         function token1() external view returns (address);
-    }
 
-    #[sol(rpc)]
-    interface IERC20Metadata {
-        function symbol()   external view returns (string);
-        function name()     external view returns (string);
-        function decimals() external view returns (uint8);
+        event Sync(uint112 reserve0, uint112 reserve1);
     }
 }
 
+/// Block the mainnet Uniswap V2 factory was deployed at.
+const UNISWAP_V2_FACTORY_DEPLOYMENT_BLOCK: u64 = 10_000_835;
+
 pub struct UniswapV2 {
     provider: Arc<EthereumProvider>,
     factory_address: Address,
@@ -58,39 +61,7 @@ impl UniswapV2 {
     }
 
     async fn fetch_or_load_token(&self, addr: Address) -> Result<Token, Error> {
-        let token_opt = get_token_async(self.storage.clone(), addr, self.chain_id()).await?;
-
-        if let Some(tok) = token_opt {
-            return Ok(tok);
-        }
-
-        let erc20 = IERC20Metadata::new(addr, self.provider.provider());
-        let symbol = erc20
-            .symbol()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("{e}")))?;
-        let name = erc20
-            .name()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("{e}")))?;
-        let decimals = erc20
-            .decimals()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("{e}")))?;
-
-        let token = Token {
-            address: addr,
-            symbol,
-            name,
-            decimals: decimals as u8,
-            chain_id: self.chain_id(),
-        };
-
-        save_token_async(self.storage.clone(), token.clone()).await?;
-        Ok(token)
+        utils::load_token(self, addr).await
     }
 
     /// Retrieves the reserves and last update timestamp for a given pool address.
@@ -156,7 +127,56 @@ impl UniswapV2 {
             .collect()
     }
 
-    
+    /// Builds the liquidity distribution of a pair from its raw reserves.
+    fn distribution_from_reserves(
+        &self,
+        pool: &Pool,
+        reserve0: u128,
+        reserve1: u128,
+    ) -> LiquidityDistribution {
+        let token0 = &pool.tokens[0];
+        let token1 = &pool.tokens[1];
+
+        // Convert reserves to float for price calculation, avoiding precision loss by using strings.
+        let reserve0_str = reserve0.to_string();
+        let reserve1_str = reserve1.to_string();
+        let reserve0_f64 = reserve0_str.parse::<f64>().unwrap_or(0.0);
+        let reserve1_f64 = reserve1_str.parse::<f64>().unwrap_or(0.0);
+
+        let reserve0_float = reserve0_f64 / 10f64.powi(token0.decimals as i32);
+        let reserve1_float = reserve1_f64 / 10f64.powi(token1.decimals as i32);
+
+        // Calculate price (token1/token0)
+        let current_price = if reserve0_float > 0.0 {
+            reserve1_float / reserve0_float
+        } else {
+            0.0
+        };
+
+        let price_levels = Self::build_cumulative_price_levels((reserve0_float, reserve1_float));
+        let per_tick_levels: Vec<PriceLiquidity> = price_levels
+            .windows(2)
+            .map(|w| PriceLiquidity {
+                side: w[0].side,
+                lower_price: w[0].upper_price,
+                upper_price: w[1].upper_price,
+                token0_liquidity:  (w[1].token0_liquidity - w[0].token0_liquidity).abs(),
+                token1_liquidity:  (w[1].token1_liquidity - w[0].token1_liquidity).abs(),
+                timestamp:         Utc::now(),
+            })
+            .collect();
+
+        LiquidityDistribution {
+            current_price: current_price,
+            token0: token0.clone(),
+            token1: token1.clone(),
+            dex: self.name().to_string(),
+            chain_id: self.chain_id(),
+            price_levels: per_tick_levels,
+            timestamp: Utc::now(),
+            pool: Some(pool.address),
+        }
+    }
 }
 
 #[async_trait]
@@ -200,63 +220,12 @@ impl DexProtocol for UniswapV2 {
     /// assert_eq!(pool.address, Address::from_low_u64_be(0x1234));
     /// ```
     async fn get_pool(&self, pool_address: Address) -> Result<Pool, Error> {
-        let inner = self.provider.provider();
-        let pair = IUniswapV2Pair::new(pool_address, inner.clone());
-
-        let t0_addr = pair
-            .token0()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("token0(): {e}")))?;
-
-        let t1_addr = pair
-            .token1()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("token1(): {e}")))?;
-
-        let token0 = self.fetch_or_load_token(t0_addr).await?;
-        let token1 = self.fetch_or_load_token(t1_addr).await?;
-
-        let pool = Pool {
-            address: pool_address,
-            dex: self.name().into(),
-            chain_id: self.chain_id(),
-            tokens: vec![token0, token1],
-            creation_block: 0,
-            creation_timestamp: Utc::now(),
-            last_updated_block: 0,
-            last_updated_timestamp: Utc::now(),
-            fee: 3000, // 0.3% = 3000 (UniswapV2 standard)
-        };
-
-        save_pool_async(self.storage.clone(), pool.clone()).await?;
-        Ok(pool)
+        load_pair(self, pool_address).await
     }
 
-    async fn get_all_pools_test(&self) -> Result<Vec<Pool>, Error> {
-        // This is only used for test mode in the indexer
-        let pool_addresses = [
-            "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc", //USDC/ETH
-            "0xBb2b8038a1640196FbE3e38816F3e67Cba72D940", //WBTC/ETH
-            "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852", //ETH/USDT
-            "0xA478c2975Ab1Ea89e8196811F51A7B7Ade33eB11", //DAI/ETH
-            "0xd3d2E2692501A5c9Ca623199D38826e513033a17", //UNI/ETH
-            "0xd3d2E2692501A5c9Ca623199D38826e513033a17", //DAI/USDC
-            "0xebfb684dd2b01e698ca6c14f10e4f289934a54d6", //UNI/USDC
-            "0x5ac13261c181a9c3938bfe1b649e65d10f98566b", //UNI/USDT
-            "0xa43fe16908251ee70ef74718545e4fe6c5ccec9f", //PEPE/WETH
-        ];
-        let mut pools = Vec::new();
-        for addr_str in pool_addresses.iter() {
-            let addr_str = addr_str.trim();
-            if addr_str.is_empty() {
-                continue;
-            }
-            let pool_addr = match Address::from_str(addr_str) {
-                Ok(a) => a,
-                Err(_) => continue,
-            };
+    async fn get_pools_by_address(&self, addresses: &[Address]) -> Result<Vec<Pool>, Error> {
+        let mut pools = Vec::with_capacity(addresses.len());
+        for &pool_addr in addresses {
             match self.get_pool(pool_addr).await {
                 Ok(pool) => {
                     let _ = save_pool_async(self.storage.clone(), pool.clone()).await;
@@ -300,90 +269,39 @@ impl DexProtocol for UniswapV2 {
         Ok(pools)
     }
 
-    /// Retrieves up to 10 Uniswap V2 pools from the factory contract and saves them to storage.
+    /// Discovers pairs from the factory's `PairCreated` events, then returns every stored pair.
     ///
-    /// Queries the Uniswap V2 factory contract for the total number of pairs, fetches up to 10 pool addresses and their associated token addresses, constructs pool objects with token stubs, saves each pool asynchronously to storage, and returns the list of pools.
-    ///
-    /// # Returns
-    /// A vector of `Pool` objects representing the discovered Uniswap V2 pools.
+    /// Discovery resumes from the stored checkpoint, so only blocks not scanned before are queried.
     ///
     /// # Errors
-    /// Returns an error if any on-chain contract call fails or if saving a pool to storage fails.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// let pools = uniswap_v2.get_all_pools().await?;
-    /// assert!(!pools.is_empty());
-    /// ```
+    /// Returns an error if the chain head cannot be read, a log query fails even over a single
+    /// block, or storage fails.
     async fn get_all_pools(&self) -> Result<Vec<Pool>, Error> {
-        let pools = self.get_all_pools_test().await?;
-        return Ok(pools);
-
-        // 1. Alloy Provider (RootProvider<Ethereum>)
-        let inner = self.provider.provider();
+        discover_pools(self, None, DEFAULT_LOG_RANGE).await?;
+        self.get_all_pools_local().await
+    }
 
-        // 2. Uniswap-V2 Factory
-        let factory = IUniswapV2Factory::new(self.factory_address, inner.clone());
+    fn deployment_block(&self) -> u64 {
+        UNISWAP_V2_FACTORY_DEPLOYMENT_BLOCK
+    }
 
-        // 3. Total pair count (demo: max 10)
-        let total: U256 = factory
-            .allPairsLength()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("allPairsLength: {e}")))?;
-
-        let limit = std::cmp::min(total.to::<u64>(), 10) as usize;
-        let mut pools = Vec::with_capacity(limit);
-
-        // 4. Loop 0 … limit-1
-        for i in 0..limit {
-            // 4-a. pair address
-            let pair_addr: Address = factory
-                .allPairs(U256::from(i))
-                .call()
-                .await
-                .map_err(|e| Error::ProviderError(format!("allPairs({i}): {e}")))?;
-
-            // 4-b. pair contract
-            let pair = IUniswapV2Pair::new(pair_addr, inner.clone());
-
-            // 4-c. token0 / token1 address -- sequential call (no naming issue)
-            let t0_addr = pair
-                .token0()
-                .call()
-                .await
-                .map_err(|e| Error::ProviderError(format!("token0(): {e}")))?;
-
-            let t1_addr = pair
-                .token1()
-                .call()
-                .await
-                .map_err(|e| Error::ProviderError(format!("token1(): {e}")))?;
-
-            // 4-d. Fetch actual token metadata
-
-            let token0 = self.fetch_or_load_token(t0_addr).await?;
-            let token1 = self.fetch_or_load_token(t1_addr).await?;
-
-            let pool = Pool {
-                address: pair_addr,
-                dex: self.name().into(),
-                chain_id: self.chain_id(),
-                tokens: vec![token0, token1],
-                creation_block: 0,
-                creation_timestamp: Utc::now(),
-                last_updated_block: 0,
-                last_updated_timestamp: Utc::now(),
-                fee: 3000, // 0.3% = 3000 (UniswapV2 standard)
-            };
+    async fn discover_pools_in_range(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Pool>, Error> {
+        discover_pairs(self, from_block, to_block).await
+    }
 
-            // 4-e. Save to DB
-            save_pool_async(self.storage.clone(), pool.clone()).await?;
-            pools.push(pool);
-        }
+    async fn find_pools(&self, token0: Address, token1: Address) -> Result<Vec<Address>, Error> {
+        find_pair(self, token0, token1).await
+    }
 
-        Ok(pools)
+    /// Reads the reserves, a single call instead of one `balanceOf` per token.
+    async fn pool_balances(&self, pool: &Pool) -> Result<(f64, f64), Error> {
+        let (reserve0, reserve1, _) = self.get_reserves(pool.address).await?;
+        let scale = |reserve: u128, token: &Token| reserve as f64 / 10f64.powi(token.decimals as i32);
+        Ok((scale(reserve0, &pool.tokens[0]), scale(reserve1, &pool.tokens[1])))
     }
 
     /// Retrieves the current liquidity distribution and price for a given Uniswap V2 pool.
@@ -412,51 +330,7 @@ impl DexProtocol for UniswapV2 {
     ) -> Result<LiquidityDistribution, Error> {
         let pool = self.get_pool(pool_address).await?;
         let (reserve0, reserve1, _) = self.get_reserves(pool_address).await?;
-
-        let token0 = &pool.tokens[0];
-        let token1 = &pool.tokens[1];
-
-        // Convert reserves to float for price calculation, avoiding precision loss by using strings.
-        let reserve0_str = reserve0.to_string();
-        let reserve1_str = reserve1.to_string();
-        let reserve0_f64 = reserve0_str.parse::<f64>().unwrap_or(0.0);
-        let reserve1_f64 = reserve1_str.parse::<f64>().unwrap_or(0.0);
-
-        let reserve0_float = reserve0_f64 / 10f64.powi(token0.decimals as i32);
-        let reserve1_float = reserve1_f64 / 10f64.powi(token1.decimals as i32);
-
-        // Calculate price (token1/token0)
-        let current_price = if reserve0_float > 0.0 {
-            reserve1_float / reserve0_float
-        } else {
-            0.0
-        };
-
-        let price_levels = Self::build_cumulative_price_levels((reserve0_float, reserve1_float));
-        let per_tick_levels: Vec<PriceLiquidity> = price_levels
-            .windows(2)
-            .map(|w| PriceLiquidity {
-                side: w[0].side,
-                lower_price: w[0].upper_price,
-                upper_price: w[1].upper_price,
-                token0_liquidity:  (w[1].token0_liquidity - w[0].token0_liquidity).abs(),
-                token1_liquidity:  (w[1].token1_liquidity - w[0].token1_liquidity).abs(),
-                timestamp:         Utc::now(),
-            })
-            .collect();
-
-        let distribution = LiquidityDistribution {
-            current_price: current_price,
-            token0: token0.clone(),
-            token1: token1.clone(),
-            dex: self.name().to_string(),
-            chain_id: self.chain_id(),
-            price_levels: per_tick_levels,
-            timestamp: Utc::now(),
-        };
-        save_liquidity_distribution_async(self.storage.clone(), distribution.clone()).await?;
-        
-        Ok(distribution)
+        Ok(self.distribution_from_reserves(&pool, reserve0, reserve1))
     }
 
     async fn calculate_swap_impact(
@@ -468,4 +342,193 @@ impl DexProtocol for UniswapV2 {
         // Simplified placeholder implementation
         Ok(0.0)
     }
+
+    async fn load_pool_state(&self, pool: &Pool) -> Result<PoolState, Error> {
+        let (reserve0, reserve1, _) = self.get_reserves(pool.address).await?;
+        Ok(PoolState::V2 { reserve0, reserve1 })
+    }
+
+    async fn load_pool_state_at(&self, pool: &Pool, block: u64) -> Result<PoolState, Error> {
+        let reserves = IUniswapV2Pair::new(pool.address, self.provider.provider())
+            .getReserves()
+            .block(BlockId::number(block))
+            .call()
+            .await
+            .map_err(|e| Error::ProviderError(format!("getReserves at block {block}: {e}")))?;
+        Ok(PoolState::V2 {
+            reserve0: reserves.reserve0.to::<u128>(),
+            reserve1: reserves.reserve1.to::<u128>(),
+        })
+    }
+
+    async fn get_pool_logs(
+        &self,
+        pools: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<PoolLog>, Error> {
+        let filter = Filter::new()
+            .address(pools.to_vec())
+            .event_signature(IUniswapV2Pair::Sync::SIGNATURE_HASH)
+            .from_block(from_block)
+            .to_block(to_block);
+        let logs = self
+            .provider
+            .provider()
+            .get_logs(&filter)
+            .await
+            .map_err(|e| Error::ProviderError(format!("get_logs: {e}")))?;
+
+        let mut decoded = Vec::with_capacity(logs.len());
+        for log in logs {
+            let sync = match log.log_decode::<IUniswapV2Pair::Sync>() {
+                Ok(event) => event.inner.data,
+                Err(e) => {
+                    warn!("Skipping undecodable Sync log: {}", e);
+                    continue;
+                }
+            };
+            decoded.push(PoolLog {
+                pool: log.address(),
+                block_number: log.block_number.unwrap_or_default(),
+                log_index: log.log_index.unwrap_or_default(),
+                event: PoolEvent::Sync {
+                    reserve0: sync.reserve0.to::<u128>(),
+                    reserve1: sync.reserve1.to::<u128>(),
+                },
+            });
+        }
+        Ok(decoded)
+    }
+
+    fn distribution_from_state(
+        &self,
+        pool: &Pool,
+        state: &PoolState,
+    ) -> Result<LiquidityDistribution, Error> {
+        match state {
+            PoolState::V2 { reserve0, reserve1 } => {
+                Ok(self.distribution_from_reserves(pool, *reserve0, *reserve1))
+            }
+            PoolState::V3(_) => Err(Error::DexError(format!(
+                "pool {} has V3 state, expected reserves",
+                pool.address
+            ))),
+        }
+    }
+}
+
+/// Discovers and saves the pairs `dex`'s factory created within `[from_block, to_block]`.
+///
+/// Shared by the Uniswap V2 forks that emit the same `PairCreated` event, such as Sushiswap.
+/// A pair whose tokens fail to load fails the range, so it is retried rather than lost.
+pub(crate) async fn discover_pairs(
+    dex: &dyn DexProtocol,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<Pool>, Error> {
+    let filter = Filter::new()
+        .address(dex.factory_address())
+        .event_signature(IUniswapV2Factory::PairCreated::SIGNATURE_HASH)
+        .from_block(from_block)
+        .to_block(to_block);
+    let logs = dex
+        .provider()
+        .provider()
+        .get_logs(&filter)
+        .await
+        .map_err(|e| Error::ProviderError(format!("get_logs: {e}")))?;
+
+    let mut pools = Vec::with_capacity(logs.len());
+    for log in logs {
+        let event = match log.log_decode::<IUniswapV2Factory::PairCreated>() {
+            Ok(event) => event.inner.data,
+            Err(e) => {
+                warn!("Skipping undecodable PairCreated log: {}", e);
+                continue;
+            }
+        };
+        let (token0, token1) = match (
+            utils::load_token(dex, event.token0).await,
+            utils::load_token(dex, event.token1).await,
+        ) {
+            (Ok(token0), Ok(token1)) => (token0, token1),
+            (Err(e), _) | (_, Err(e)) => {
+                return Err(Error::DexError(format!(
+                    "failed to load tokens of pair {}: {}",
+                    event.pair, e
+                )))
+            }
+        };
+
+        let creation_block = log.block_number.unwrap_or_default();
+        let creation_timestamp = log
+            .block_timestamp
+            .and_then(|ts| DateTime::from_timestamp(ts as i64, 0))
+            .unwrap_or_else(Utc::now);
+        let pool = Pool {
+            address: event.pair,
+            dex: dex.name().into(),
+            chain_id: dex.chain_id(),
+            tokens: vec![token0, token1],
+            creation_block,
+            creation_timestamp,
+            last_updated_block: creation_block,
+            last_updated_timestamp: creation_timestamp,
+            fee: 3000, // 0.3% = 3000 (UniswapV2 standard)
+        };
+        save_pool_async(dex.storage(), pool.clone()).await?;
+        pools.push(pool);
+    }
+    Ok(pools)
+}
+
+/// The pair `dex`'s factory holds for two tokens, if any; shared with the Uniswap V2 forks.
+pub(crate) async fn find_pair(
+    dex: &dyn DexProtocol,
+    token0: Address,
+    token1: Address,
+) -> Result<Vec<Address>, Error> {
+    let pair = IUniswapV2Factory::new(dex.factory_address(), dex.provider().provider())
+        .getPair(token0, token1)
+        .call()
+        .await
+        .map_err(|e| Error::ProviderError(format!("getPair: {e}")))?;
+    Ok(if pair == Address::ZERO { Vec::new() } else { vec![pair] })
+}
+
+/// Reads the pair at `pool_address` from the chain and saves it; shared with the Uniswap V2 forks.
+pub(crate) async fn load_pair(dex: &dyn DexProtocol, pool_address: Address) -> Result<Pool, Error> {
+    let provider = dex.provider();
+    let pair = IUniswapV2Pair::new(pool_address, provider.provider());
+
+    let t0_addr = pair
+        .token0()
+        .call()
+        .await
+        .map_err(|e| Error::ProviderError(format!("token0(): {e}")))?;
+
+    let t1_addr = pair
+        .token1()
+        .call()
+        .await
+        .map_err(|e| Error::ProviderError(format!("token1(): {e}")))?;
+
+    let token0 = utils::load_token(dex, t0_addr).await?;
+    let token1 = utils::load_token(dex, t1_addr).await?;
+
+    let pool = Pool {
+        address: pool_address,
+        dex: dex.name().into(),
+        chain_id: dex.chain_id(),
+        tokens: vec![token0, token1],
+        creation_block: 0,
+        creation_timestamp: Utc::now(),
+        last_updated_block: 0,
+        last_updated_timestamp: Utc::now(),
+        fee: 3000, // 0.3% = 3000 (UniswapV2 standard)
+    };
+
+    save_pool_async(dex.storage(), pool.clone()).await?;
+    Ok(pool)
 }
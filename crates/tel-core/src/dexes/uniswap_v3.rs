@@ -1,3 +1,5 @@
+use crate::dexes::discovery::{discover_pools, DEFAULT_LOG_RANGE};
+use crate::dexes::state::{PoolEvent, PoolLog, PoolState, V3State};
 use crate::dexes::DexProtocol;
 use crate::error::Error;
 use crate::models::{
@@ -5,24 +7,28 @@ use crate::models::{
     V3PriceLevel, V3PriceLiquidity,
 };
 use crate::providers::EthereumProvider;
-use crate::storage::{self, get_pool_async, get_token_async, save_pool_async, save_token_async, Storage};
+use crate::dexes::utils;
+use crate::storage::{self, get_pool_async, save_pool_async, Storage};
 use crate::Result;
-use alloy_primitives::{Address, B256};
+use alloy_primitives::aliases::U24;
+use alloy_primitives::{Address, B256, U256};
 use alloy_provider::Provider;
-use alloy_rpc_types::{Filter, Log};
-use alloy_sol_types::sol;
+use alloy_rpc_types::{BlockId, Filter, Log};
+use alloy_sol_types::{sol, SolEvent};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::sync::Arc;
-use tracing::info;
+use tracing::warn;
 
 sol! {
     // ── Uniswap V3 Factory ───────────────────────────────────────────
     #[sol(rpc)]
     interface IUniswapV3Factory {
         function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool);
+
+        event PoolCreated(address indexed token0, address indexed token1, uint24 indexed fee, int24 tickSpacing, address pool);
     }
 
     // ── Uniswap V3 Pool ──────────────────────────────────────────────
@@ -42,6 +48,11 @@ sol! {
         function token1() external view returns (address);
         function fee() external view returns (uint24);
         function tickSpacing() external view returns (int24);
+
+        event Initialize(uint160 sqrtPriceX96, int24 tick);
+        event Swap(address indexed sender, address indexed recipient, int256 amount0, int256 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick);
+        event Mint(address sender, address indexed owner, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1);
+        event Burn(address indexed owner, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1);
     }
 
     // ── TickInfo struct for TickLens ────────────────────────────────
@@ -60,16 +71,13 @@ sol! {
             TickInfo[] memory populatedTicks
         );
     }
-
-    #[sol(rpc)]
-    interface IERC20Metadata {
-        function symbol()   external view returns (string);
-        function name()     external view returns (string);
-        function decimals() external view returns (uint8);
-    }
 }
 
 const UNISWAP_V3_FACTORY: &str = "0x1F98431c8aD98523631AE4a59f267346ea31F984";
+/// Block the mainnet Uniswap V3 factory was deployed at.
+const UNISWAP_V3_FACTORY_DEPLOYMENT_BLOCK: u64 = 12_369_621;
+/// Fee tiers the factory enables, in hundredths of a basis point.
+const FEE_TIERS: [u32; 4] = [100, 500, 3000, 10000];
 const POOL_CREATED_SIG: &str = "PoolCreated(address,address,uint24,int24,address)";
 const HASH_POOL_CREATED: &str =
     "0x783cca1c0412dd0d695e784568c96da2e9c22ff989357a2e8b1d9b2b4e6b7118";
@@ -104,34 +112,7 @@ impl UniswapV3 {
 
     /// Fetch token from DB or on-chain if not present
     async fn fetch_or_load_token(&self, addr: Address) -> Result<Token> {
-        if let Some(tok) = get_token_async(self.storage.clone(), addr, self.chain_id()).await? {
-            return Ok(tok);
-        }
-        let erc20 = IERC20Metadata::new(addr, self.provider.provider());
-        let symbol = erc20
-            .symbol()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("{e}")))?;
-        let name = erc20
-            .name()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("{e}")))?;
-        let decimals = erc20
-            .decimals()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("{e}")))?;
-        let token = Token {
-            address: addr,
-            symbol,
-            name,
-            decimals: decimals as u8,
-            chain_id: self.chain_id(),
-        };
-        save_token_async(self.storage.clone(), token.clone()).await?;
-        Ok(token)
+        utils::load_token(self, addr).await
     }
 
     /// Convert tick index to price, adjusting for token decimals
@@ -183,47 +164,40 @@ impl UniswapV3 {
         }
     }
 
-    /// Fetch all active ticks for a pool using TickLens
+    /// Fetch the active ticks in the bitmap word holding `current_tick` using TickLens, as of
+    /// `block`, with the inclusive tick range that word covers
     async fn get_active_ticks(
         &self,
         pool_address: Address,
-    ) -> Result<(i32, Vec<(i32, u128, i128)>)> {
+        current_tick: i32,
+        tick_spacing: i32,
+        block: BlockId,
+    ) -> Result<((i32, i32), Vec<(i32, u128, i128)>)> {
         let tick_lens_address =
             Address::from_str("0xbfd8137f7d1516D3ea5cA83523914859ec47F573").unwrap();
         let tick_lens = ITickLens::new(tick_lens_address, self.provider.provider());
-        let pool = IUniswapV3Pool::new(pool_address, self.provider.provider());
-        let slot0 = pool
-            .slot0()
+        let tick_spacing = tick_spacing.max(1);
+        let current_word = current_tick.div_euclid(tick_spacing) >> 8;
+        let word_ticks = 256 * tick_spacing;
+        let low = current_word * word_ticks;
+        let range = (low, low + word_ticks - 1);
+        let word = i16::try_from(current_word)
+            .map_err(|_| Error::DexError(format!("tick {current_tick} is out of range")))?;
+        let populated = tick_lens
+            .getPopulatedTicksInWord(pool_address, word)
+            .block(block)
             .call()
             .await
-            .map_err(|e| Error::ProviderError(format!("slot0: {e}")))?;
-        let tick_spacing: i32 = pool
-            .tickSpacing()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("tickSpacing: {e}")))?
-            .try_into()
-            .unwrap_or(1);
-        let current_tick: i32 = slot0.tick.try_into().unwrap_or(0);
-        let current_word = (current_tick / tick_spacing) >> 8;
-        let mut active_ticks = Vec::new();
-        if current_word >= i16::MIN as i32 && current_word <= i16::MAX as i32 {
-            let word_i16 = current_word as i16;
-            let call_result = tick_lens
-                .getPopulatedTicksInWord(pool_address, word_i16)
-                .call()
-                .await;
-            if let Ok(result) = call_result {
-                for tick_info in result {
-                    let tick_idx: i32 = tick_info.tick.try_into().unwrap_or(0);
-                    let liquidity_gross: u128 = tick_info.liquidityGross.try_into().unwrap_or(0);
-                    let liquidity_net: i128 = tick_info.liquidityNet.try_into().unwrap_or(0);
-                    active_ticks.push((tick_idx, liquidity_gross, liquidity_net));
-                }
-            }
+            .map_err(|e| Error::ProviderError(format!("getPopulatedTicksInWord: {e}")))?;
+        let mut active_ticks = Vec::with_capacity(populated.len());
+        for tick_info in populated {
+            let tick_idx: i32 = tick_info.tick.try_into().unwrap_or(0);
+            let liquidity_gross: u128 = tick_info.liquidityGross.try_into().unwrap_or(0);
+            let liquidity_net: i128 = tick_info.liquidityNet.try_into().unwrap_or(0);
+            active_ticks.push((tick_idx, liquidity_gross, liquidity_net));
         }
         active_ticks.sort_by_key(|(tick, _, _)| *tick);
-        Ok((current_tick, active_ticks))
+        Ok((range, active_ticks))
     }
 
     /// Build a filter for PoolCreated events
@@ -244,6 +218,176 @@ impl UniswapV3 {
             .map_err(|e| Error::ProviderError(format!("get_logs: {}", e)))
     }
 
+    /// Read the price, active liquidity and populated ticks of a pool from the chain, all as of
+    /// `block`
+    async fn read_v3_state(&self, pool_address: Address, block: BlockId) -> Result<V3State> {
+        let pool_contract = IUniswapV3Pool::new(pool_address, self.provider.provider());
+        let slot0 = pool_contract
+            .slot0()
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| Error::ProviderError(format!("slot0: {e}")))?;
+        let liquidity = pool_contract
+            .liquidity()
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| Error::ProviderError(format!("liquidity: {e}")))?;
+        let tick_spacing: i32 = pool_contract
+            .tickSpacing()
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| Error::ProviderError(format!("tickSpacing: {e}")))?
+            .try_into()
+            .unwrap_or(1);
+        let tick: i32 = slot0.tick.try_into().unwrap_or(0);
+        let (tick_range, populated) = self
+            .get_active_ticks(pool_address, tick, tick_spacing, block)
+            .await?;
+        Ok(V3State {
+            sqrt_price_x96: U256::from(slot0.sqrtPriceX96),
+            liquidity,
+            tick,
+            tick_spacing,
+            ticks: populated
+                .into_iter()
+                .map(|(tick, gross, net)| (tick, (gross, net)))
+                .collect(),
+            tick_range: Some(tick_range),
+        })
+    }
+
+    /// Compute the tick-level distribution of a pool from its state
+    fn v3_distribution(&self, token0: &Token, token1: &Token, state: &V3State) -> V3LiquidityDistribution {
+        if state.ticks.is_empty() {
+            return Self::empty_v3_dist(
+                token0,
+                token1,
+                &self.name().to_lowercase(),
+                self.chain_id(),
+            );
+        }
+        let current_tick = state.tick;
+        let tick_spacing = state.tick_spacing.max(1);
+        let liquidity = state.liquidity;
+        let sqrt_price_current = f64::from(state.sqrt_price_x96) / Q96_F64; // √P 현재값
+
+        // net liquidity 맵
+        let mut tick_liqnet: BTreeMap<i32, i128> = BTreeMap::new();
+        for (t, (_g, net)) in &state.ticks {
+            tick_liqnet.insert(*t, *net);
+        }
+        // active tick(하단) 강제 포함
+        let active_lower = (current_tick / tick_spacing) * tick_spacing;
+        tick_liqnet.entry(active_lower).or_insert(0);
+
+        // 정렬된 모든 하단 tick
+        let mut lower_ticks: Vec<i32> = tick_liqnet.keys().copied().collect();
+        lower_ticks.sort_unstable();
+
+        // ---- 누적 Liquidity로 구간별 L 계산 ---------------------------------------
+        let mut v3_levels = Vec::with_capacity(lower_ticks.len());
+        let mut L: i128 = 0; // 누적 active liquidity
+        for (idx, &lower) in lower_ticks.iter().enumerate() {
+            if let Some(net) = tick_liqnet.get(&lower) {
+                L = liquidity as i128 + *net;
+            }
+            // 마지막 lower 는 upper 가 없으므로 스킵
+            if idx + 1 == lower_ticks.len() {
+                break;
+            }
+            let upper = lower_ticks[idx + 1];
+
+            // √가격 경계
+            let sqrt_lower = 1.0001_f64.powf(lower as f64 / 2.0);
+            let sqrt_upper = 1.0001_f64.powf(upper as f64 / 2.0);
+
+            // 토큰 잠금량 계산 (Uniswap 공식)
+            let (amt0, amt1) = if lower == active_lower {
+                // ── ① 현재 활성 구간 ───────────────────────────────────────────
+                let l_f = L.max(0) as f64;
+                let amount0 =
+                    l_f * (sqrt_upper - sqrt_price_current) / (sqrt_price_current * sqrt_upper);
+                let amount1 = l_f * (sqrt_price_current - sqrt_lower);
+                (amount0, amount1)
+            } else if lower < active_lower {
+                // ── ② 현 가격보다 “왼쪽” 구간(가격 위쪽) → 전량 token1 ──────────
+                let l_f = L.max(0) as f64;
+                let amount1 = l_f * (sqrt_upper - sqrt_lower);
+                (0.0, amount1)
+            } else {
+                // ── ③ 현 가격보다 “오른쪽” 구간(가격 아래쪽) → 전량 token0 ─────────
+                let l_f = L.max(0) as f64;
+                let amount0 = l_f * (1.0 / sqrt_lower - 1.0 / sqrt_upper);
+                (amount0, 0.0)
+            };
+
+            // 사람 단위로 환산 (decimals 보정)
+            let token0_liq = amt0 / 10f64.powi(token0.decimals as i32);
+            let token1_liq = amt1 / 10f64.powi(token1.decimals as i32);
+
+            v3_levels.push(V3PriceLevel {
+                tick_idx: lower,
+                price: Self::tick_to_price(lower, token0.decimals, token1.decimals),
+                tick_price: 1.0001_f64.powi(lower),
+                token0_liquidity: token0_liq,
+                token1_liquidity: token1_liq,
+                timestamp: Utc::now(),
+            });
+        }
+
+        // 가격순 정렬
+        v3_levels.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
+
+        V3LiquidityDistribution {
+            token0: token0.clone(),
+            token1: token1.clone(),
+            dex: self.name().to_lowercase(),
+            chain_id: self.chain_id(),
+            current_tick: state.tick,
+            price_levels: v3_levels,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Flatten a tick-level distribution into generic price levels split around the current price
+    fn flatten_v3_distribution(v3_dist: &V3LiquidityDistribution, pool: Address) -> LiquidityDistribution {
+        let current_price = Self::tick_to_price(
+            v3_dist.current_tick,
+            v3_dist.token0.decimals,
+            v3_dist.token1.decimals,
+        );
+        let price_levels = v3_dist
+            .price_levels
+            .iter()
+            .map(|lvl| PriceLiquidity {
+                side: if lvl.price < current_price {
+                    Side::Buy
+                } else {
+                    Side::Sell
+                },
+                lower_price: lvl.price,
+                upper_price: lvl.price,
+                token0_liquidity: lvl.token0_liquidity,
+                token1_liquidity: lvl.token1_liquidity,
+                timestamp: lvl.timestamp,
+            })
+            .collect();
+
+        LiquidityDistribution {
+            token0: v3_dist.token0.clone(),
+            token1: v3_dist.token1.clone(),
+            current_price,
+            dex: v3_dist.dex.clone(),
+            chain_id: v3_dist.chain_id,
+            price_levels,
+            timestamp: v3_dist.timestamp,
+            pool: Some(pool),
+        }
+    }
+
     /// Return an empty LiquidityDistribution
     fn empty_dist(
         token0: &Token,
@@ -259,6 +403,7 @@ impl UniswapV3 {
             chain_id,
             price_levels: vec![],
             timestamp: Utc::now(),
+            pool: None,
         }
     }
 
@@ -308,7 +453,7 @@ impl DexProtocol for UniswapV3 {
     }
     
     async fn get_pool(&self, pool_address: Address) -> crate::Result<Pool> {
-        let pool_result = get_pool_async(self.storage.clone(), pool_address).await;
+        let pool_result = get_pool_async(self.storage.clone(), pool_address, self.chain_id()).await;
         match pool_result {
             Ok(Some(pool)) => Ok(pool),
             Ok(None) => Err(Error::DexError(format!("Pool not found: {}", pool_address))),
@@ -316,84 +461,82 @@ impl DexProtocol for UniswapV3 {
         }
     }
 
+    /// Discovers pools from the factory's `PoolCreated` events, then returns every stored pool.
+    ///
+    /// Discovery resumes from the stored checkpoint, so only blocks not scanned before are queried.
     async fn get_all_pools(&self) -> Result<Vec<Pool>> {
-        self.get_all_pools_test().await
-
-        // let provider = self.provider.provider();
-        // //let latest_block: u64 = provider.get_block_number().await.map_err(|e| Error::ProviderError(format!("get_block_number: {}", e)))?;
-        // let latest_block = 12380000; // For testing, replace with actual block number retrieval
-        // let mut from_block = 12378000;
-        // let mut all_logs = Vec::new();
-        // let mut i = 0;
-        // while from_block < latest_block {
-        //     let to_block = (from_block + 9999).min(latest_block);
-        //     info!("Fetching logs from block {} to {}", from_block, to_block);
-
-        //     let filter = self.build_pool_created_filter(from_block, to_block);
-        //     let logs = self.get_logs(filter).await?;
-        //     all_logs.extend(logs);
-        //     from_block = to_block + 1;
-        //     i += 1;
-        // }
-
-        // info!("Found a total of {} pools", all_logs.len());
-
-        // let mut pools = Vec::with_capacity(all_logs.len());
-        // let mut pools_count = 0;
-        // for log in &all_logs {
-        //     //if pools_count >= 10 { break; }
-        //     //info!("Processing log: topics={:?}, data={:?}", log.topics(), log.data());
-        //     // topics: [topic0, token0, token1, fee]
-        //     if log.topics().len() < 4 { continue; }
-        //     let token0 = Address::from_slice(&log.topics()[1].as_slice()[12..]);
-        //     let token1 = Address::from_slice(&log.topics()[2].as_slice()[12..]);
-        //     let fee_bytes = log.topics()[3].as_slice();
-        //     let fee = ((fee_bytes[29] as u32) << 16)
-        //         | ((fee_bytes[30] as u32) << 8)
-        //         | (fee_bytes[31] as u32);
-        //     // data: [tickSpacing(int24)|poolAddress]
-        //     let data_slice: &[u8] = log.data().data.as_ref();
-        //     if data_slice.len() < 64 {
-        //         continue;
-        //     }
-        //     let pool_addr = Address::from_slice(&data_slice[44..64]);
-        //     let tok0 = match self.fetch_or_load_token(token0).await {
-        //         Ok(token) => token,
-        //         Err(e) => {
-        //             tracing::error!("Failed to fetch token {}: {}", token0, e);
-        //             continue;
-        //         }
-        //     };
-        //     let tok1 = match self.fetch_or_load_token(token1).await {
-        //         Ok(token) => token,
-        //         Err(e) => {
-        //             tracing::error!("Failed to fetch token {}: {}", token1, e);
-        //             continue;
-        //         }
-        //     };
-        //     let block_number: u64 = log.block_number.unwrap_or(0);
-        //     let pool = Pool {
-        //         address: pool_addr,
-        //         dex: self.name().into(),
-        //         chain_id: self.chain_id(),
-        //         tokens: vec![tok0, tok1],
-        //         creation_block: block_number,
-        //         creation_timestamp: Utc::now(),
-        //         last_updated_block: block_number,
-        //         last_updated_timestamp: Utc::now(),
-        //         fee: fee as u64,
-        //     };
-        //     let pool_address = pool.address;
-        //     if let Err(e) = save_pool_async(self.storage.clone(), pool.clone()).await {
-        //         tracing::error!("Failed to save pool {}: {}", pool_address, e);
-        //         continue;
-        //     };
-
-        //     pools.push(pool);
-        //     info!("Pool {}: token0={}, token1={}, fee={}", pool_address, token0, token1, fee);
-        //     pools_count += 1;
-        // }
-        // Ok(pools)
+        discover_pools(self, None, DEFAULT_LOG_RANGE).await?;
+        self.get_all_pools_local().await
+    }
+
+    fn deployment_block(&self) -> u64 {
+        UNISWAP_V3_FACTORY_DEPLOYMENT_BLOCK
+    }
+
+    async fn find_pools(&self, token0: Address, token1: Address) -> Result<Vec<Address>> {
+        let factory = IUniswapV3Factory::new(self.factory_address, self.provider.provider());
+        let mut pools = Vec::new();
+        for fee in FEE_TIERS {
+            let pool = factory
+                .getPool(token0, token1, U24::from(fee))
+                .call()
+                .await
+                .map_err(|e| Error::ProviderError(format!("getPool: {e}")))?;
+            if pool != Address::ZERO {
+                pools.push(pool);
+            }
+        }
+        Ok(pools)
+    }
+
+    async fn discover_pools_in_range(&self, from_block: u64, to_block: u64) -> Result<Vec<Pool>> {
+        let logs = self
+            .get_logs(self.build_pool_created_filter(from_block, to_block))
+            .await?;
+
+        let mut pools = Vec::with_capacity(logs.len());
+        for log in logs {
+            let event = match log.log_decode::<IUniswapV3Factory::PoolCreated>() {
+                Ok(event) => event.inner.data,
+                Err(e) => {
+                    warn!("Skipping undecodable PoolCreated log: {}", e);
+                    continue;
+                }
+            };
+            // A pool whose tokens fail to load fails the range, so it is retried, not lost
+            let (token0, token1) = match (
+                self.fetch_or_load_token(event.token0).await,
+                self.fetch_or_load_token(event.token1).await,
+            ) {
+                (Ok(token0), Ok(token1)) => (token0, token1),
+                (Err(e), _) | (_, Err(e)) => {
+                    return Err(Error::DexError(format!(
+                        "failed to load tokens of pool {}: {}",
+                        event.pool, e
+                    )))
+                }
+            };
+
+            let creation_block = log.block_number.unwrap_or_default();
+            let creation_timestamp = log
+                .block_timestamp
+                .and_then(|ts| DateTime::from_timestamp(ts as i64, 0))
+                .unwrap_or_else(Utc::now);
+            let pool = Pool {
+                address: event.pool,
+                dex: self.name().into(),
+                chain_id: self.chain_id(),
+                tokens: vec![token0, token1],
+                creation_block,
+                creation_timestamp,
+                last_updated_block: creation_block,
+                last_updated_timestamp: creation_timestamp,
+                fee: event.fee.to::<u64>(),
+            };
+            save_pool_async(self.storage.clone(), pool.clone()).await?;
+            pools.push(pool);
+        }
+        Ok(pools)
     }
 
     async fn get_liquidity_distribution(
@@ -401,37 +544,7 @@ impl DexProtocol for UniswapV3 {
         pool_address: Address,
     ) -> Result<LiquidityDistribution> {
         let v3_dist = self.get_v3_liquidity_distribution(pool_address).await?;
-        let current_price = Self::tick_to_price(
-            v3_dist.current_tick,
-            v3_dist.token0.decimals,
-            v3_dist.token1.decimals,
-        );
-        let price_levels = v3_dist
-            .price_levels
-            .iter()
-            .map(|lvl| PriceLiquidity {
-                side: if lvl.price < current_price {
-                    Side::Buy
-                } else {
-                    Side::Sell
-                },
-                lower_price: lvl.price,
-                upper_price: lvl.price,
-                token0_liquidity: lvl.token0_liquidity,
-                token1_liquidity: lvl.token1_liquidity,
-                timestamp: lvl.timestamp,
-            })
-            .collect();
-
-        Ok(LiquidityDistribution {
-            token0: v3_dist.token0.clone(),
-            token1: v3_dist.token1.clone(),
-            current_price,
-            dex: v3_dist.dex.clone(),
-            chain_id: v3_dist.chain_id,
-            price_levels,
-            timestamp: v3_dist.timestamp,
-        })
+        Ok(Self::flatten_v3_distribution(&v3_dist, pool_address))
     }
 
     async fn calculate_swap_impact(
@@ -466,145 +579,121 @@ impl DexProtocol for UniswapV3 {
                 ));
             }
         };
-        let token0 = &pool.tokens[0];
-        let token1 = &pool.tokens[1];
+        let state = self.read_v3_state(pool_address, BlockId::latest()).await?;
+        Ok(self.v3_distribution(&pool.tokens[0], &pool.tokens[1], &state))
+    }
 
-        // ---- on‑chain 현재 sqrtPriceX96, tick, spacing ---------------------------
-        let pool_contract = IUniswapV3Pool::new(pool_address, self.provider.provider());
-        let slot0 = pool_contract
-            .slot0()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("slot0: {e}")))?;
-        let sqrt_price_x96: u128 = slot0.sqrtPriceX96.to(); // uint160 → u128
-        let liquidity = pool_contract
-            .liquidity()
-            .call()
-            .await
-            .map_err(|e| Error::ProviderError(format!("liquidity: {e}")))?;
-        let current_tick: i32 = slot0.tick.try_into().unwrap_or(0);
-        let tick_spacing: i32 = pool_contract
+    async fn load_pool_state(&self, pool: &Pool) -> Result<PoolState> {
+        Ok(PoolState::V3(self.read_v3_state(pool.address, BlockId::latest()).await?))
+    }
+
+    async fn load_pool_state_at(&self, pool: &Pool, block: u64) -> Result<PoolState> {
+        Ok(PoolState::V3(
+            self.read_v3_state(pool.address, BlockId::number(block)).await?,
+        ))
+    }
+
+    async fn initial_pool_state(&self, pool: &Pool) -> Result<PoolState> {
+        let tick_spacing: i32 = IUniswapV3Pool::new(pool.address, self.provider.provider())
             .tickSpacing()
             .call()
             .await
             .map_err(|e| Error::ProviderError(format!("tickSpacing: {e}")))?
             .try_into()
             .unwrap_or(1);
-        let sqrt_price_current = (sqrt_price_x96 as f64) / Q96_F64; // √P 현재값
-
-        // ---- 모든 populated ticks -------------------------------------------------
-        let (_ignored, populated) = self.get_active_ticks(pool_address).await?;
-        if populated.is_empty() {
-            return Ok(Self::empty_v3_dist(
-                token0,
-                token1,
-                &self.name().to_lowercase(),
-                self.chain_id(),
-            ));
-        }
-
-        // net liquidity 맵
-        let mut tick_liqnet: BTreeMap<i32, i128> = BTreeMap::new();
-        for (t, _g, net) in &populated {
-            tick_liqnet.insert(*t, *net);
-        }
-        // active tick(하단) 강제 포함
-        let active_lower = (current_tick / tick_spacing) * tick_spacing;
-        tick_liqnet.entry(active_lower).or_insert(0);
-
-        // 정렬된 모든 하단 tick
-        let mut lower_ticks: Vec<i32> = tick_liqnet.keys().copied().collect();
-        lower_ticks.sort_unstable();
-
-        // ---- 누적 Liquidity로 구간별 L 계산 ---------------------------------------
-        let mut v3_levels = Vec::with_capacity(lower_ticks.len());
-        let mut L: i128 = 0; // 누적 active liquidity
-        for (idx, &lower) in lower_ticks.iter().enumerate() {
-            if let Some(net) = tick_liqnet.get(&lower) {
-                L = liquidity as i128 + *net;
-            }
-            // 마지막 lower 는 upper 가 없으므로 스킵
-            if idx + 1 == lower_ticks.len() {
-                break;
-            }
-            let upper = lower_ticks[idx + 1];
-
-            // √가격 경계
-            let sqrt_lower = 1.0001_f64.powf(lower as f64 / 2.0);
-            let sqrt_upper = 1.0001_f64.powf(upper as f64 / 2.0);
+        Ok(PoolState::V3(V3State {
+            sqrt_price_x96: U256::ZERO,
+            liquidity: 0,
+            tick: 0,
+            tick_spacing,
+            ticks: BTreeMap::new(),
+            tick_range: None,
+        }))
+    }
 
-            // 토큰 잠금량 계산 (Uniswap 공식)
-            let (amt0, amt1) = if lower == active_lower {
-                // ── ① 현재 활성 구간 ───────────────────────────────────────────
-                let l_f = L.max(0) as f64;
-                let amount0 =
-                    l_f * (sqrt_upper - sqrt_price_current) / (sqrt_price_current * sqrt_upper);
-                let amount1 = l_f * (sqrt_price_current - sqrt_lower);
-                (amount0, amount1)
-            } else if lower < active_lower {
-                // ── ② 현 가격보다 “왼쪽” 구간(가격 위쪽) → 전량 token1 ──────────
-                let l_f = L.max(0) as f64;
-                let amount1 = l_f * (sqrt_upper - sqrt_lower);
-                (0.0, amount1)
-            } else {
-                // ── ③ 현 가격보다 “오른쪽” 구간(가격 아래쪽) → 전량 token0 ─────────
-                let l_f = L.max(0) as f64;
-                let amount0 = l_f * (1.0 / sqrt_lower - 1.0 / sqrt_upper);
-                (amount0, 0.0)
+    async fn get_pool_logs(
+        &self,
+        pools: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<PoolLog>> {
+        let filter = Filter::new()
+            .address(pools.to_vec())
+            .event_signature(vec![
+                IUniswapV3Pool::Initialize::SIGNATURE_HASH,
+                IUniswapV3Pool::Swap::SIGNATURE_HASH,
+                IUniswapV3Pool::Mint::SIGNATURE_HASH,
+                IUniswapV3Pool::Burn::SIGNATURE_HASH,
+            ])
+            .from_block(from_block)
+            .to_block(to_block);
+        let logs = self.get_logs(filter).await?;
+
+        let mut decoded = Vec::with_capacity(logs.len());
+        for log in logs {
+            let event = match log.topic0() {
+                Some(&IUniswapV3Pool::Initialize::SIGNATURE_HASH) => log
+                    .log_decode::<IUniswapV3Pool::Initialize>()
+                    .map(|e| PoolEvent::Initialize {
+                        sqrt_price_x96: U256::from(e.inner.data.sqrtPriceX96),
+                        tick: e.inner.data.tick.try_into().unwrap_or(0),
+                    }),
+                Some(&IUniswapV3Pool::Swap::SIGNATURE_HASH) => log
+                    .log_decode::<IUniswapV3Pool::Swap>()
+                    .map(|e| PoolEvent::Swap {
+                        sqrt_price_x96: U256::from(e.inner.data.sqrtPriceX96),
+                        liquidity: e.inner.data.liquidity,
+                        tick: e.inner.data.tick.try_into().unwrap_or(0),
+                    }),
+                Some(&IUniswapV3Pool::Mint::SIGNATURE_HASH) => log
+                    .log_decode::<IUniswapV3Pool::Mint>()
+                    .map(|e| PoolEvent::Mint {
+                        tick_lower: e.inner.data.tickLower.try_into().unwrap_or(0),
+                        tick_upper: e.inner.data.tickUpper.try_into().unwrap_or(0),
+                        amount: e.inner.data.amount,
+                    }),
+                Some(&IUniswapV3Pool::Burn::SIGNATURE_HASH) => log
+                    .log_decode::<IUniswapV3Pool::Burn>()
+                    .map(|e| PoolEvent::Burn {
+                        tick_lower: e.inner.data.tickLower.try_into().unwrap_or(0),
+                        tick_upper: e.inner.data.tickUpper.try_into().unwrap_or(0),
+                        amount: e.inner.data.amount,
+                    }),
+                _ => continue,
             };
-
-            // 사람 단위로 환산 (decimals 보정)
-            let token0_liq = amt0 / 10f64.powi(token0.decimals as i32);
-            let token1_liq = amt1 / 10f64.powi(token1.decimals as i32);
-
-            v3_levels.push(V3PriceLevel {
-                tick_idx: lower,
-                price: Self::tick_to_price(lower, token0.decimals, token1.decimals),
-                tick_price: 1.0001_f64.powi(lower),
-                token0_liquidity: token0_liq,
-                token1_liquidity: token1_liq,
-                timestamp: Utc::now(),
-            });
+            match event {
+                Ok(event) => decoded.push(PoolLog {
+                    pool: log.address(),
+                    block_number: log.block_number.unwrap_or_default(),
+                    log_index: log.log_index.unwrap_or_default(),
+                    event,
+                }),
+                Err(e) => warn!("Skipping undecodable pool log: {}", e),
+            }
         }
+        Ok(decoded)
+    }
 
-        // 가격순 정렬
-        v3_levels.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap());
-
-        Ok(V3LiquidityDistribution {
-            token0: token0.clone(),
-            token1: token1.clone(),
-            dex: self.name().to_lowercase(),
-            chain_id: self.chain_id(),
-            current_tick,
-            price_levels: v3_levels,
-            timestamp: Utc::now(),
-        })
+    fn distribution_from_state(
+        &self,
+        pool: &Pool,
+        state: &PoolState,
+    ) -> Result<LiquidityDistribution> {
+        match state {
+            PoolState::V3(state) => Ok(Self::flatten_v3_distribution(
+                &self.v3_distribution(&pool.tokens[0], &pool.tokens[1], state),
+                pool.address,
+            )),
+            PoolState::V2 { .. } => Err(Error::DexError(format!(
+                "pool {} has V2 reserves, expected tick state",
+                pool.address
+            ))),
+        }
     }
 
-    async fn get_all_pools_test(&self) -> Result<Vec<Pool>> {
-        // This is only used for test mode in the indexer
-        let pool_addresses = [
-            "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640",
-            "0xCBCdF9626bC03E24f779434178A73a0B4bad62eD",
-            "0x99ac8cA7087fA4A2A1FB6357269965A2014ABc35",
-            "0xe8f7c89C5eFa061e340f2d2F206EC78FD8f7e124",
-            "0x5777d92f208679DB4b9778590Fa3CAB3aC9e2168",
-            "0x4e68Ccd3E89f51C3074ca5072bbAC773960dFa36",
-            "0xC5c134A1f112efA96003f8559Dba6fAC0BA77692",
-            "0x1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801",
-            "0x9Db9e0e53058C89e5B94e29621a205198648425B",
-            "0x8ad599c3A0ff1De082011EFDDc58f1908eb6e6D8",
-        ];
-        let mut pools = Vec::new();
-        for addr_str in pool_addresses.iter() {
-            let addr_str = addr_str.trim();
-            if addr_str.is_empty() {
-                continue;
-            }
-            let pool_addr = match Address::from_str(addr_str) {
-                Ok(a) => a,
-                Err(_) => continue,
-            };
+    async fn get_pools_by_address(&self, addresses: &[Address]) -> Result<Vec<Pool>> {
+        let mut pools = Vec::with_capacity(addresses.len());
+        for &pool_addr in addresses {
             match self.get_pool(pool_addr).await {
                 Ok(pool) => {
                     let _ = save_pool_async(self.storage.clone(), pool.clone()).await;
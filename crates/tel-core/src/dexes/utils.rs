@@ -1,5 +1,6 @@
+use crate::dexes::DexProtocol;
 use crate::error::Error;
-use crate::models::Token;
+use crate::models::{Pool, Token};
 use crate::providers::EthereumProvider;
 use alloy_primitives::Address;
 use alloy_sol_types::sol;
@@ -12,10 +13,15 @@ sol! {
         function name() external view returns (string);
         function symbol() external view returns (string);
         function decimals() external view returns (uint8);
+        function balanceOf(address owner) external view returns (uint256);
     }
 }
 
 /// Shared implementation of get_token for all DEX protocols
+///
+/// Tokens that revert or return nothing for `name`, `symbol` or `decimals`, such as those with a
+/// bytes32 symbol, get placeholder values since asking again would not help. A failed RPC request
+/// is returned as `Error::ProviderError` so the caller can retry.
 pub async fn get_token(
     provider: Arc<EthereumProvider>,
     token_address: Address,
@@ -24,23 +30,13 @@ pub async fn get_token(
     // Create contract instance
     let contract = IERC20::new(token_address, provider.provider());
 
-    // Get name with fallback
-    let name = match contract.name().call().await {
-        Ok(name) => name,
-        Err(_) => format!("Token-{}", token_address),
-    };
-
-    // Get symbol with fallback
-    let symbol = match contract.symbol().call().await {
-        Ok(symbol) => symbol,
-        Err(_) => format!("TKN-{}", &token_address.to_string()[..6]),
-    };
-
-    // Get decimals with fallback
-    let decimals = match contract.decimals().call().await {
-        Ok(decimals) => decimals,
-        Err(_) => 18u8,
-    };
+    let name = or_placeholder(contract.name().call().await, || {
+        format!("Token-{}", token_address)
+    })?;
+    let symbol = or_placeholder(contract.symbol().call().await, || {
+        format!("TKN-{}", &token_address.to_string()[..6])
+    })?;
+    let decimals = or_placeholder(contract.decimals().call().await, || 18u8)?;
 
     Ok(Token {
         address: token_address,
@@ -50,3 +46,44 @@ pub async fn get_token(
         chain_id,
     })
 }
+
+/// The stored token at `address` on `dex`'s chain, read from the chain and saved if missing.
+pub async fn load_token(dex: &dyn DexProtocol, address: Address) -> Result<Token, Error> {
+    let storage = dex.storage();
+    if let Some(token) = storage.get_token(address, dex.chain_id()).await? {
+        return Ok(token);
+    }
+    let token = get_token(dex.provider(), address, dex.chain_id()).await?;
+    storage.save_token(&token).await?;
+    Ok(token)
+}
+
+/// The result of a metadata call, or `placeholder` when the token contract could not answer it.
+fn or_placeholder<T>(
+    result: Result<T, alloy_contract::Error>,
+    placeholder: impl FnOnce() -> T,
+) -> Result<T, Error> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(alloy_contract::Error::TransportError(e))
+            if !e.as_error_resp().is_some_and(|resp| resp.message.contains("revert")) =>
+        {
+            Err(Error::ProviderError(format!("{e}")))
+        }
+        Err(_) => Ok(placeholder()),
+    }
+}
+
+/// Shared implementation of pool_balances: how much of each of its tokens `pool` holds.
+pub async fn pool_balances(provider: Arc<EthereumProvider>, pool: &Pool) -> Result<(f64, f64), Error> {
+    let mut balances = [0.0; 2];
+    for (balance, token) in balances.iter_mut().zip(&pool.tokens) {
+        let raw = IERC20::new(token.address, provider.provider())
+            .balanceOf(pool.address)
+            .call()
+            .await
+            .map_err(|e| Error::ProviderError(format!("balanceOf: {e}")))?;
+        *balance = f64::from(raw) / 10f64.powi(token.decimals as i32);
+    }
+    Ok((balances[0], balances[1]))
+}
@@ -1,4 +1,4 @@
-use alloy_primitives::Address;
+use alloy_primitives::{Address, B256};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -76,6 +76,10 @@ pub struct LiquidityDistribution {
     pub chain_id: u64,
     pub price_levels: Vec<PriceLiquidity>,
     pub timestamp: DateTime<Utc>,
+    /// Pool the distribution was read from; `None` for merged books and for snapshots saved
+    /// before distributions were kept per pool.
+    #[serde(default)]
+    pub pool: Option<Address>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,13 +97,16 @@ pub struct V3LiquidityDistribution {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SupportResistanceLevel {
     pub price: f64,
+    /// Liquidity at the level relative to the deepest level, in (0, 1].
     pub strength: f64,
     pub level_type: LevelType,
+    /// Liquidity at the level in USD, when a USD price of the pair is known.
+    pub liquidity_usd: Option<f64>,
     pub token0: Token,
     pub token1: Token,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LevelType {
     Support,
     Resistance,
@@ -149,6 +156,231 @@ pub struct LiquidityWall {
     pub price_upper: f64,
     pub liquidity_value: f64,
     pub dex_sources: HashMap<String, f64>,
+    /// `liquidity_value` in USD, when token1 can be priced.
+    #[serde(default)]
+    pub liquidity_usd: Option<f64>,
+}
+
+/// Market depth of a pair: the liquidity met by moving its price by each of a set of amounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketDepthResponse {
+    pub token0: Token,
+    pub token1: Token,
+    pub price: f64,
+    /// Moving the price up: token0 bought for token1
+    pub asks: Vec<DepthLevel>,
+    /// Moving the price down: token0 sold for token1
+    pub bids: Vec<DepthLevel>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Cumulative liquidity between the current price and `price`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepthLevel {
+    /// Price move as a fraction of the current price, negative for bids
+    pub change: f64,
+    pub price: f64,
+    pub total: DepthAmount,
+    pub dex_sources: HashMap<String, DepthAmount>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DepthAmount {
+    pub token0: f64,
+    pub token1: f64,
+    /// `token1` in USD, when token1 can be priced.
+    pub usd: Option<f64>,
+}
+
+/// Best execution of a swap across the indexed pools, split so marginal prices are equal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteResponse {
+    pub token_in: Token,
+    pub token_out: Token,
+    pub amount_in: f64,
+    pub amount_out: f64,
+    /// token_out per token_in for the first unit through the best route, after fees
+    pub spot_price: f64,
+    /// token_out per token_in over the whole filled amount
+    pub effective_price: f64,
+    /// Shortfall of `effective_price` from `spot_price`, as a fraction of it
+    pub slippage: f64,
+    /// Input the pools could not absorb
+    pub unfilled: f64,
+    pub routes: Vec<RouteAllocation>,
+    pub pools: Vec<PoolAllocation>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Part of a swap sent along one path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteAllocation {
+    /// Tokens from token_in to token_out
+    pub path: Vec<Address>,
+    /// DEX of each hop
+    pub dexes: Vec<String>,
+    pub amount_in: f64,
+    pub amount_out: f64,
+}
+
+/// Part of a swap sent through one pool, summed over every route using it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolAllocation {
+    pub dex: String,
+    /// Pool whose fee was applied, if the pair's pool is stored
+    pub pool: Option<Address>,
+    pub token_in: Address,
+    pub token_out: Address,
+    /// Fee as a fraction of the input
+    pub fee: f64,
+    pub amount_in: f64,
+    pub amount_out: f64,
+}
+
+/// AMM liquidity of a pair as a CEX-style L2 order book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBookResponse {
+    pub base: Token,
+    pub quote: Token,
+    /// Current price in quote per base
+    pub price: f64,
+    /// Spacing of the price levels, in quote
+    pub tick: f64,
+    /// `[price, size]` with size in base, highest price first
+    pub bids: Vec<[f64; 2]>,
+    /// `[price, size]` with size in base, lowest price first
+    pub asks: Vec<[f64; 2]>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Liquidity of one canonical asset across every chain it is indexed on, in USD
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossChainLiquidityResponse {
+    pub asset: String,
+    /// USD price averaged over the chains by liquidity
+    pub price: f64,
+    /// Spread between the highest and lowest token prices, as a fraction of `price`
+    pub max_divergence: f64,
+    pub chains: Vec<ChainPrice>,
+    /// Buckets around `price`, sorted by price
+    pub price_levels: Vec<CrossChainLevel>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Price and depth of one token of a canonical asset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainPrice {
+    pub chain_id: u64,
+    pub token: Token,
+    /// USD price on this chain
+    pub price: f64,
+    pub liquidity_usd: f64,
+    /// Difference of `price` from the cross-chain price, as a fraction of it
+    pub divergence: f64,
+    /// Whether `divergence` exceeds the configured threshold
+    pub divergent: bool,
+}
+
+/// Price bucket of a cross-chain book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossChainLevel {
+    pub side: Side,
+    pub lower_price: f64,
+    pub upper_price: f64,
+    /// Asks, in the asset
+    pub asset_liquidity: f64,
+    /// Bids, in USD
+    pub usd_liquidity: f64,
+    /// USD value of the bucket held on each chain
+    pub chain_sources: HashMap<u64, f64>,
+}
+
+/// Size and time span of the stored snapshot history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotStats {
+    pub count: u64,
+    /// Snapshots whose block is deep enough to be safe from reorgs
+    pub finalized: u64,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+/// A block the indexer synced to, kept to detect reorgs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRef {
+    pub number: u64,
+    pub hash: B256,
+    pub parent_hash: B256,
+}
+
+/// What a reorg rollback removed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RollbackStats {
+    pub snapshots: u64,
+    pub blocks: u64,
+}
+
+/// One indexer cycle, as recorded in `indexer_runs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexerRun {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// Pools tracked this cycle, whether followed through events or snapshotted
+    pub pools: u64,
+    /// Pools snapshotted in full
+    pub snapshotted: u64,
+    pub failed: u64,
+}
+
+/// Outcome of one attempt to refresh a pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolAttempt {
+    pub chain_id: u64,
+    pub dex: String,
+    pub pool: Address,
+    pub at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Refresh health of a pool, folded from its attempts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolStatus {
+    pub chain_id: u64,
+    pub dex: String,
+    pub pool: Address,
+    pub last_attempt: DateTime<Utc>,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    /// Consecutive failures since the last success
+    pub error_count: u64,
+    /// Duration of the last attempt
+    pub duration_ms: u64,
+}
+
+/// Refresh health of a pool, with its staleness at the time it was read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolHealth {
+    #[serde(flatten)]
+    pub status: PoolStatus,
+    /// Seconds since the last successful refresh; `None` if it never succeeded
+    pub stale_secs: Option<i64>,
+}
+
+/// Recent indexer runs and the health of the pools they refreshed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerStatus {
+    pub last_run: Option<IndexerRun>,
+    pub runs: Vec<IndexerRun>,
+    pub pools: Vec<PoolHealth>,
+}
+
+/// Result of one compaction pass over the snapshot history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompactionStats {
+    pub examined: u64,
+    pub deleted: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
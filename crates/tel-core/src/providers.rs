@@ -1,7 +1,10 @@
 use crate::config::RpcConfig;
 use crate::error::Error;
+use crate::models::BlockRef;
 use alloy_network::Ethereum;
-use alloy_provider::RootProvider;
+use alloy_provider::{Provider, RootProvider};
+use alloy_rpc_types::BlockNumberOrTag;
+use chrono::{DateTime, Utc};
 use reqwest::Url;
 use std::sync::Arc;
 
@@ -9,6 +12,7 @@ use std::sync::Arc;
 pub struct EthereumProvider {
     provider: Arc<RootProvider<Ethereum>>,
     chain_id: u64,
+    max_concurrency: Option<usize>,
 }
 
 impl EthereumProvider {
@@ -22,7 +26,11 @@ impl EthereumProvider {
         // Create the provider with the URL
         let provider = Arc::new(RootProvider::<Ethereum>::new_http(url));
 
-        Ok(Self { provider, chain_id })
+        Ok(Self {
+            provider,
+            chain_id,
+            max_concurrency: config.max_concurrency,
+        })
     }
 
     /// Get the provider instance
@@ -34,6 +42,57 @@ impl EthereumProvider {
     pub fn chain_id(&self) -> u64 {
         self.chain_id
     }
+
+    /// Get the configured limit on parallel requests, if any
+    pub fn max_concurrency(&self) -> Option<usize> {
+        self.max_concurrency
+    }
+
+    /// Get the timestamp of a block
+    pub async fn block_timestamp(&self, block: u64) -> Result<DateTime<Utc>, Error> {
+        let header = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Number(block))
+            .await
+            .map_err(|e| Error::ProviderError(format!("get_block_by_number: {e}")))?
+            .ok_or_else(|| Error::ProviderError(format!("block {block} not found")))?
+            .header;
+        DateTime::from_timestamp(header.timestamp as i64, 0)
+            .ok_or_else(|| Error::ProviderError(format!("block {block} has an invalid timestamp")))
+    }
+
+    /// Get the latest block with its hash and parent hash
+    pub async fn head_block(&self) -> Result<BlockRef, Error> {
+        self.block_ref(BlockNumberOrTag::Latest)
+            .await?
+            .ok_or_else(|| Error::ProviderError("latest block not found".to_string()))
+    }
+
+    /// Get the canonical block at `number`, if the node has it
+    pub async fn block_at(&self, number: u64) -> Result<Option<BlockRef>, Error> {
+        self.block_ref(BlockNumberOrTag::Number(number)).await
+    }
+
+    async fn block_ref(&self, tag: BlockNumberOrTag) -> Result<Option<BlockRef>, Error> {
+        let block = self
+            .provider
+            .get_block_by_number(tag)
+            .await
+            .map_err(|e| Error::ProviderError(format!("get_block_by_number: {e}")))?;
+        Ok(block.map(|b| BlockRef {
+            number: b.header.number,
+            hash: b.header.hash,
+            parent_hash: b.header.parent_hash,
+        }))
+    }
+
+    /// Get the latest block number
+    pub async fn block_number(&self) -> Result<u64, Error> {
+        self.provider
+            .get_block_number()
+            .await
+            .map_err(|e| Error::ProviderError(format!("get_block_number: {e}")))
+    }
 }
 
 /// ProviderManager handles multiple providers for different chains
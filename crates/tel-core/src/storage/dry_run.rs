@@ -0,0 +1,247 @@
+use crate::config::RetentionConfig;
+use crate::models::{
+    BlockRef, CompactionStats, IndexerRun, LiquidityDistribution, Pool, PoolAttempt, PoolStatus,
+    RollbackStats, SnapshotStats, Token,
+};
+use crate::storage::Storage;
+use crate::Result;
+use alloy_primitives::Address;
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::debug;
+
+/// `Storage` wrapper that serves reads from `inner` and drops every write.
+///
+/// Used by `--dry-run`: the indexer does all of its RPC work, but nothing is persisted, so
+/// reads never observe the writes of the same run.
+pub struct DryRunStorage {
+    inner: Arc<dyn Storage>,
+    skipped: AtomicU64,
+}
+
+impl DryRunStorage {
+    pub fn new(inner: Arc<dyn Storage>) -> Self {
+        Self {
+            inner,
+            skipped: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of writes dropped so far.
+    pub fn skipped_writes(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    fn skip(&self, what: &str) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+        debug!("dry run: skipped {}", what);
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for DryRunStorage {
+    async fn save_token(&self, token: &Token) -> Result<()> {
+        self.skip(&format!("token {}", token.address));
+        Ok(())
+    }
+
+    async fn get_token(&self, address: Address, chain_id: u64) -> Result<Option<Token>> {
+        self.inner.get_token(address, chain_id).await
+    }
+
+    async fn list_tokens(&self, chain_id: Option<u64>) -> Result<Vec<Token>> {
+        self.inner.list_tokens(chain_id).await
+    }
+
+    async fn save_pool(&self, pool: &Pool) -> Result<()> {
+        self.skip(&format!("pool {}", pool.address));
+        Ok(())
+    }
+
+    async fn get_pool(&self, address: Address, chain_id: u64) -> Result<Option<Pool>> {
+        self.inner.get_pool(address, chain_id).await
+    }
+
+    async fn get_pools_by_dex(&self, dex: &str, chain_id: u64) -> Result<Vec<Pool>> {
+        self.inner.get_pools_by_dex(dex, chain_id).await
+    }
+
+    async fn list_pools(&self, chain_id: Option<u64>, dex: Option<&str>) -> Result<Vec<Pool>> {
+        self.inner.list_pools(chain_id, dex).await
+    }
+
+    async fn get_pools_by_dex_paginated(
+        &self,
+        dex: &str,
+        chain_id: u64,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Pool>> {
+        self.inner
+            .get_pools_by_dex_paginated(dex, chain_id, limit, offset)
+            .await
+    }
+
+    async fn get_all_pools_paginated(
+        &self,
+        chain_id: u64,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Pool>> {
+        self.inner
+            .get_all_pools_paginated(chain_id, limit, offset)
+            .await
+    }
+
+    async fn get_pools_by_token(
+        &self,
+        token0: Address,
+        token1: Address,
+        chain_id: u64,
+        dex: Option<&str>,
+        fee: Option<u64>,
+    ) -> Result<Vec<Pool>> {
+        self.inner
+            .get_pools_by_token(token0, token1, chain_id, dex, fee)
+            .await
+    }
+
+    async fn get_pools_containing_token(
+        &self,
+        token: Address,
+        chain_id: u64,
+        dex: Option<&str>,
+    ) -> Result<Vec<Pool>> {
+        self.inner
+            .get_pools_containing_token(token, chain_id, dex)
+            .await
+    }
+
+    async fn save_liquidity_distribution(&self, distribution: &LiquidityDistribution) -> Result<()> {
+        self.skip(&format!(
+            "distribution {}/{} on {}",
+            distribution.token0.symbol, distribution.token1.symbol, distribution.dex
+        ));
+        Ok(())
+    }
+
+    async fn get_liquidity_distributions(
+        &self,
+        token0: Address,
+        token1: Address,
+        dex: &str,
+        chain_id: u64,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        self.inner
+            .get_liquidity_distributions(token0, token1, dex, chain_id)
+            .await
+    }
+
+    async fn list_liquidity_distributions(
+        &self,
+        chain_id: Option<u64>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        self.inner.list_liquidity_distributions(chain_id).await
+    }
+
+    async fn get_liquidity_history(
+        &self,
+        token0: Address,
+        token1: Address,
+        dex: &str,
+        chain_id: u64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        self.inner
+            .get_liquidity_history(token0, token1, dex, chain_id, from, to)
+            .await
+    }
+
+    async fn list_liquidity_history(
+        &self,
+        chain_id: Option<u64>,
+        dex: Option<&str>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        self.inner
+            .list_liquidity_history(chain_id, dex, from, to)
+            .await
+    }
+
+    async fn compact_snapshots(
+        &self,
+        _policy: &RetentionConfig,
+        _now: DateTime<Utc>,
+    ) -> Result<CompactionStats> {
+        self.skip("snapshot compaction");
+        Ok(CompactionStats::default())
+    }
+
+    async fn get_snapshot_stats(&self) -> Result<SnapshotStats> {
+        self.inner.get_snapshot_stats().await
+    }
+
+    async fn get_checkpoint(&self, chain_id: u64, dex: &str, stream: &str) -> Result<Option<u64>> {
+        self.inner.get_checkpoint(chain_id, dex, stream).await
+    }
+
+    async fn save_checkpoint(&self, chain_id: u64, dex: &str, stream: &str, block: u64) -> Result<()> {
+        self.skip(&format!(
+            "{} checkpoint of {} on chain {} at block {}",
+            stream, dex, chain_id, block
+        ));
+        Ok(())
+    }
+
+    async fn save_liquidity_distribution_at(
+        &self,
+        distribution: &LiquidityDistribution,
+        block: &BlockRef,
+    ) -> Result<()> {
+        self.skip(&format!(
+            "distribution {}/{} on {} at block {}",
+            distribution.token0.symbol, distribution.token1.symbol, distribution.dex, block.number
+        ));
+        Ok(())
+    }
+
+    async fn save_block(&self, chain_id: u64, block: &BlockRef) -> Result<()> {
+        self.skip(&format!("block {} of chain {}", block.number, chain_id));
+        Ok(())
+    }
+
+    async fn get_recent_blocks(&self, chain_id: u64, limit: usize) -> Result<Vec<BlockRef>> {
+        self.inner.get_recent_blocks(chain_id, limit).await
+    }
+
+    async fn rollback_to(&self, chain_id: u64, fork_block: u64) -> Result<RollbackStats> {
+        self.skip(&format!("rollback of chain {} to block {}", chain_id, fork_block));
+        Ok(RollbackStats::default())
+    }
+
+    async fn finalize_blocks(&self, chain_id: u64, block: u64) -> Result<u64> {
+        self.skip(&format!("finalizing chain {} up to block {}", chain_id, block));
+        Ok(0)
+    }
+
+    async fn save_indexer_run(&self, _run: &IndexerRun) -> Result<()> {
+        self.skip("indexer run");
+        Ok(())
+    }
+
+    async fn get_indexer_runs(&self, limit: usize) -> Result<Vec<IndexerRun>> {
+        self.inner.get_indexer_runs(limit).await
+    }
+
+    async fn record_pool_attempt(&self, attempt: &PoolAttempt) -> Result<()> {
+        self.skip(&format!("status of pool {}", attempt.pool));
+        Ok(())
+    }
+
+    async fn list_pool_status(&self, chain_id: Option<u64>, dex: Option<&str>) -> Result<Vec<PoolStatus>> {
+        self.inner.list_pool_status(chain_id, dex).await
+    }
+}
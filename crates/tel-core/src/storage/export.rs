@@ -0,0 +1,464 @@
+use crate::error::Error;
+use crate::models::{LiquidityDistribution, Pool, Side, Token};
+use crate::storage::Storage;
+use crate::Result;
+use alloy_primitives::Address;
+use chrono::{DateTime, SecondsFormat, Utc};
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// File format written by `export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Parquet,
+    Csv,
+    JsonLines,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "parquet" => Ok(ExportFormat::Parquet),
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" | "jsonlines" | "ndjson" => Ok(ExportFormat::JsonLines),
+            other => Err(Error::ConfigError(format!(
+                "unknown export format `{other}`, expected parquet, csv or jsonl"
+            ))),
+        }
+    }
+}
+
+/// Selects what `export` writes. Unset fields do not filter.
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub chain_id: Option<u64>,
+    pub dex: Option<String>,
+    /// Matches pools and distributions of this pair in either token order.
+    pub pair: Option<(Address, Address)>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ExportFilter {
+    fn matches_pair(&self, a: Address, b: Address) -> bool {
+        self.pair
+            .is_none_or(|(t0, t1)| (a == t0 && b == t1) || (a == t1 && b == t0))
+    }
+}
+
+/// Row counts and files produced by one `export` call.
+#[derive(Debug, Clone, Default)]
+pub struct ExportSummary {
+    pub tokens: usize,
+    pub pools: usize,
+    pub price_levels: usize,
+    pub files: Vec<PathBuf>,
+}
+
+/// Writes tokens, pools and flattened price levels matching `filter` to `out_dir`.
+///
+/// Produces `tokens.<ext>`, `pools.<ext>` and `price_levels.<ext>`, one row per price level
+/// of every stored snapshot in the time range. Without a DEX or pair filter every token of
+/// the selected chain is exported; otherwise only the tokens the exported pools and
+/// snapshots refer to.
+pub async fn export(
+    storage: Arc<dyn Storage>,
+    filter: &ExportFilter,
+    format: ExportFormat,
+    out_dir: &Path,
+) -> Result<ExportSummary> {
+    let from = filter.from.unwrap_or(DateTime::<Utc>::UNIX_EPOCH);
+    let to = filter.to.unwrap_or_else(Utc::now);
+    let dex = filter.dex.as_deref();
+
+    let pools: Vec<Pool> = storage
+        .list_pools(filter.chain_id, dex)
+        .await?
+        .into_iter()
+        .filter(|p| {
+            p.tokens.len() >= 2 && filter.matches_pair(p.tokens[0].address, p.tokens[1].address)
+        })
+        .collect();
+    let distributions: Vec<LiquidityDistribution> = storage
+        .list_liquidity_history(filter.chain_id, dex, from, to)
+        .await?
+        .into_iter()
+        .filter(|d| filter.matches_pair(d.token0.address, d.token1.address))
+        .collect();
+
+    let tokens: Vec<Token> = if filter.dex.is_none() && filter.pair.is_none() {
+        storage.list_tokens(filter.chain_id).await?
+    } else {
+        let mut seen = HashSet::new();
+        pools
+            .iter()
+            .flat_map(|p| p.tokens.iter())
+            .chain(distributions.iter().flat_map(|d| [&d.token0, &d.token1]))
+            .filter(|t| seen.insert((t.address, t.chain_id)))
+            .cloned()
+            .collect()
+    };
+
+    let tables = [
+        token_table(&tokens),
+        pool_table(&pools),
+        price_level_table(&distributions),
+    ];
+    let out_dir = out_dir.to_path_buf();
+    let summary = ExportSummary {
+        tokens: tables[0].rows(),
+        pools: tables[1].rows(),
+        price_levels: tables[2].rows(),
+        files: Vec::new(),
+    };
+
+    tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&out_dir)?;
+        let mut files = Vec::with_capacity(tables.len());
+        for table in &tables {
+            let path = out_dir.join(format!("{}.{}", table.name, format.extension()));
+            match format {
+                ExportFormat::Parquet => table.write_parquet(&path)?,
+                ExportFormat::Csv => table.write_csv(&path)?,
+                ExportFormat::JsonLines => table.write_json_lines(&path)?,
+            }
+            files.push(path);
+        }
+        Ok(ExportSummary { files, ..summary })
+    })
+    .await
+    .map_err(|e| Error::Unknown(format!("export task: {e}")))?
+}
+
+/// One column of an export table.
+enum Column {
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+    Text(Vec<String>),
+    Timestamp(Vec<DateTime<Utc>>),
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match self {
+            Column::Int(v) => v.len(),
+            Column::Float(v) => v.len(),
+            Column::Text(v) => v.len(),
+            Column::Timestamp(v) => v.len(),
+        }
+    }
+
+    fn parquet_type(&self, name: &str) -> String {
+        match self {
+            Column::Int(_) => format!("REQUIRED INT64 {name};"),
+            Column::Float(_) => format!("REQUIRED DOUBLE {name};"),
+            Column::Text(_) => format!("REQUIRED BYTE_ARRAY {name} (UTF8);"),
+            Column::Timestamp(_) => format!("REQUIRED INT64 {name} (TIMESTAMP(MILLIS,true));"),
+        }
+    }
+
+    fn json_value(&self, row: usize) -> serde_json::Value {
+        match self {
+            Column::Int(v) => v[row].into(),
+            Column::Float(v) => v[row].into(),
+            Column::Text(v) => v[row].clone().into(),
+            Column::Timestamp(v) => format_timestamp(&v[row]).into(),
+        }
+    }
+
+    fn text_value(&self, row: usize) -> String {
+        match self {
+            Column::Int(v) => v[row].to_string(),
+            Column::Float(v) => v[row].to_string(),
+            Column::Text(v) => v[row].clone(),
+            Column::Timestamp(v) => format_timestamp(&v[row]),
+        }
+    }
+}
+
+fn format_timestamp(ts: &DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// A named set of equally long columns, written the same way in every format.
+struct Table {
+    name: &'static str,
+    columns: Vec<(&'static str, Column)>,
+}
+
+impl Table {
+    fn rows(&self) -> usize {
+        self.columns.first().map_or(0, |(_, c)| c.len())
+    }
+
+    fn write_csv(&self, path: &Path) -> Result<()> {
+        let serialize_err =
+            |e: csv::Error| Error::SerializationError(format!("csv {}: {e}", self.name));
+        let mut writer = csv::Writer::from_path(path).map_err(serialize_err)?;
+        writer
+            .write_record(self.columns.iter().map(|(name, _)| *name))
+            .map_err(serialize_err)?;
+        for row in 0..self.rows() {
+            writer
+                .write_record(self.columns.iter().map(|(_, c)| c.text_value(row)))
+                .map_err(serialize_err)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn write_json_lines(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for row in 0..self.rows() {
+            let object: serde_json::Map<String, serde_json::Value> = self
+                .columns
+                .iter()
+                .map(|(name, c)| (name.to_string(), c.json_value(row)))
+                .collect();
+            serde_json::to_writer(&mut writer, &object)
+                .map_err(|e| Error::SerializationError(format!("jsonl {}: {e}", self.name)))?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn write_parquet(&self, path: &Path) -> Result<()> {
+        let parquet_err = |e: parquet::errors::ParquetError| {
+            Error::SerializationError(format!("parquet {}: {e}", self.name))
+        };
+
+        let fields: String = self
+            .columns
+            .iter()
+            .map(|(name, c)| c.parquet_type(name))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let schema = Arc::new(
+            parse_message_type(&format!("message {} {{ {fields} }}", self.name))
+                .map_err(parquet_err)?,
+        );
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = SerializedFileWriter::new(File::create(path)?, schema, Arc::new(props))
+            .map_err(parquet_err)?;
+
+        let mut row_group = writer.next_row_group().map_err(parquet_err)?;
+        for (_, column) in &self.columns {
+            let Some(mut column_writer) = row_group.next_column().map_err(parquet_err)? else {
+                break;
+            };
+            match column {
+                Column::Int(v) => {
+                    column_writer
+                        .typed::<Int64Type>()
+                        .write_batch(v, None, None)
+                        .map_err(parquet_err)?;
+                }
+                Column::Float(v) => {
+                    column_writer
+                        .typed::<DoubleType>()
+                        .write_batch(v, None, None)
+                        .map_err(parquet_err)?;
+                }
+                Column::Text(v) => {
+                    let values: Vec<ByteArray> =
+                        v.iter().map(|s| ByteArray::from(s.as_str())).collect();
+                    column_writer
+                        .typed::<ByteArrayType>()
+                        .write_batch(&values, None, None)
+                        .map_err(parquet_err)?;
+                }
+                Column::Timestamp(v) => {
+                    let millis: Vec<i64> = v.iter().map(|ts| ts.timestamp_millis()).collect();
+                    column_writer
+                        .typed::<Int64Type>()
+                        .write_batch(&millis, None, None)
+                        .map_err(parquet_err)?;
+                }
+            }
+            column_writer.close().map_err(parquet_err)?;
+        }
+        row_group.close().map_err(parquet_err)?;
+        writer.close().map_err(parquet_err)?;
+        Ok(())
+    }
+}
+
+fn token_table(tokens: &[Token]) -> Table {
+    Table {
+        name: "tokens",
+        columns: vec![
+            (
+                "chain_id",
+                Column::Int(tokens.iter().map(|t| t.chain_id as i64).collect()),
+            ),
+            (
+                "address",
+                Column::Text(tokens.iter().map(|t| t.address.to_string()).collect()),
+            ),
+            (
+                "symbol",
+                Column::Text(tokens.iter().map(|t| t.symbol.clone()).collect()),
+            ),
+            (
+                "name",
+                Column::Text(tokens.iter().map(|t| t.name.clone()).collect()),
+            ),
+            (
+                "decimals",
+                Column::Int(tokens.iter().map(|t| t.decimals as i64).collect()),
+            ),
+        ],
+    }
+}
+
+fn pool_table(pools: &[Pool]) -> Table {
+    Table {
+        name: "pools",
+        columns: vec![
+            (
+                "chain_id",
+                Column::Int(pools.iter().map(|p| p.chain_id as i64).collect()),
+            ),
+            (
+                "dex",
+                Column::Text(pools.iter().map(|p| p.dex.clone()).collect()),
+            ),
+            (
+                "address",
+                Column::Text(pools.iter().map(|p| p.address.to_string()).collect()),
+            ),
+            (
+                "token0",
+                Column::Text(
+                    pools
+                        .iter()
+                        .map(|p| p.tokens[0].address.to_string())
+                        .collect(),
+                ),
+            ),
+            (
+                "token1",
+                Column::Text(
+                    pools
+                        .iter()
+                        .map(|p| p.tokens[1].address.to_string())
+                        .collect(),
+                ),
+            ),
+            (
+                "fee",
+                Column::Int(pools.iter().map(|p| p.fee as i64).collect()),
+            ),
+        ],
+    }
+}
+
+/// Flattens snapshots into one row per price level, keeping the snapshot context on each row.
+fn price_level_table(distributions: &[LiquidityDistribution]) -> Table {
+    let rows: Vec<_> = distributions
+        .iter()
+        .flat_map(|d| d.price_levels.iter().map(move |level| (d, level)))
+        .collect();
+    let side = |side: Side| match side {
+        Side::Buy => "buy".to_string(),
+        Side::Sell => "sell".to_string(),
+    };
+
+    Table {
+        name: "price_levels",
+        columns: vec![
+            (
+                "timestamp",
+                Column::Timestamp(rows.iter().map(|(d, _)| d.timestamp).collect()),
+            ),
+            (
+                "chain_id",
+                Column::Int(rows.iter().map(|(d, _)| d.chain_id as i64).collect()),
+            ),
+            (
+                "dex",
+                Column::Text(rows.iter().map(|(d, _)| d.dex.clone()).collect()),
+            ),
+            (
+                "pool",
+                Column::Text(
+                    rows.iter()
+                        .map(|(d, _)| d.pool.map(|p| p.to_string()).unwrap_or_default())
+                        .collect(),
+                ),
+            ),
+            (
+                "token0",
+                Column::Text(
+                    rows.iter()
+                        .map(|(d, _)| d.token0.address.to_string())
+                        .collect(),
+                ),
+            ),
+            (
+                "token1",
+                Column::Text(
+                    rows.iter()
+                        .map(|(d, _)| d.token1.address.to_string())
+                        .collect(),
+                ),
+            ),
+            (
+                "token0_symbol",
+                Column::Text(rows.iter().map(|(d, _)| d.token0.symbol.clone()).collect()),
+            ),
+            (
+                "token1_symbol",
+                Column::Text(rows.iter().map(|(d, _)| d.token1.symbol.clone()).collect()),
+            ),
+            (
+                "current_price",
+                Column::Float(rows.iter().map(|(d, _)| d.current_price).collect()),
+            ),
+            (
+                "side",
+                Column::Text(rows.iter().map(|(_, l)| side(l.side)).collect()),
+            ),
+            (
+                "lower_price",
+                Column::Float(rows.iter().map(|(_, l)| l.lower_price).collect()),
+            ),
+            (
+                "upper_price",
+                Column::Float(rows.iter().map(|(_, l)| l.upper_price).collect()),
+            ),
+            (
+                "token0_liquidity",
+                Column::Float(rows.iter().map(|(_, l)| l.token0_liquidity).collect()),
+            ),
+            (
+                "token1_liquidity",
+                Column::Float(rows.iter().map(|(_, l)| l.token1_liquidity).collect()),
+            ),
+        ],
+    }
+}
@@ -0,0 +1,551 @@
+use crate::config::RetentionConfig;
+use crate::models::{
+    BlockRef, CompactionStats, IndexerRun, LiquidityDistribution, Pool, PoolAttempt, PoolStatus,
+    RollbackStats, SnapshotStats, Token,
+};
+use crate::storage::{retention, Storage, MAX_INDEXER_RUNS};
+use crate::Result;
+use alloy_primitives::Address;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::RwLock;
+
+/// DEXes returned by `get_all_pools_paginated`, mirroring the SQLite query.
+const LISTED_DEXES: [&str; 3] = ["uniswap_v3", "uniswap_v2", "sushiswap"];
+
+/// Pair, DEX, chain and pool of a distribution; the pool is `None` for unattributed snapshots.
+type DistributionKey = (Address, Address, String, u64, Option<Address>);
+
+struct HistoryEntry {
+    id: i64,
+    distribution: LiquidityDistribution,
+    block: Option<BlockRef>,
+    finalized: bool,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    tokens: HashMap<(Address, u64), Token>,
+    // Kept in insertion order so pagination behaves like SQLite's rowid ordering.
+    pools: Vec<Pool>,
+    distributions: HashMap<DistributionKey, LiquidityDistribution>,
+    // Every saved snapshot, tagged with an id like the SQLite history table.
+    history: Vec<HistoryEntry>,
+    next_history_id: i64,
+    checkpoints: HashMap<(u64, String, String), u64>,
+    blocks: HashMap<u64, BTreeMap<u64, BlockRef>>,
+    // Oldest first, capped at MAX_INDEXER_RUNS.
+    runs: VecDeque<IndexerRun>,
+    pool_status: HashMap<(u64, Address), PoolStatus>,
+}
+
+impl MemoryState {
+    fn save_distribution(&mut self, distribution: &LiquidityDistribution, block: Option<BlockRef>) {
+        self.next_history_id += 1;
+        self.history.push(HistoryEntry {
+            id: self.next_history_id,
+            distribution: distribution.clone(),
+            block,
+            finalized: false,
+        });
+
+        let key = distribution_key(distribution);
+        match self.distributions.get(&key) {
+            Some(existing) if existing.timestamp > distribution.timestamp => {}
+            _ => {
+                self.distributions.insert(key, distribution.clone());
+            }
+        }
+        // A pool's snapshot replaces the one its pair saved before distributions were per pool.
+        if distribution.pool.is_some() {
+            let (token0, token1, dex, chain_id, _) = distribution_key(distribution);
+            self.distributions.remove(&(token0, token1, dex, chain_id, None));
+        }
+    }
+}
+
+fn distribution_key(distribution: &LiquidityDistribution) -> DistributionKey {
+    (
+        distribution.token0.address,
+        distribution.token1.address,
+        distribution.dex.clone(),
+        distribution.chain_id,
+        distribution.pool,
+    )
+}
+
+/// Whether `distribution` belongs to the pair `token0`/`token1` on `dex` and `chain_id`.
+fn same_pair(
+    distribution: &LiquidityDistribution,
+    token0: Address,
+    token1: Address,
+    dex: &str,
+    chain_id: u64,
+) -> bool {
+    distribution.token0.address == token0
+        && distribution.token1.address == token1
+        && distribution.dex == dex
+        && distribution.chain_id == chain_id
+}
+
+/// In-memory `Storage` implementation.
+///
+/// Nothing is persisted; intended for tests and for wiring components together
+/// without touching a SQLite file.
+#[derive(Default)]
+pub struct MemoryStorage {
+    state: RwLock<MemoryState>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for MemoryStorage {
+    async fn save_token(&self, token: &Token) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        state
+            .tokens
+            .insert((token.address, token.chain_id), token.clone());
+        Ok(())
+    }
+
+    async fn get_token(&self, address: Address, chain_id: u64) -> Result<Option<Token>> {
+        let state = self.state.read().unwrap();
+        Ok(state.tokens.get(&(address, chain_id)).cloned())
+    }
+
+    async fn list_tokens(&self, chain_id: Option<u64>) -> Result<Vec<Token>> {
+        let state = self.state.read().unwrap();
+        let mut tokens: Vec<Token> = state
+            .tokens
+            .values()
+            .filter(|t| chain_id.is_none_or(|c| t.chain_id == c))
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|t| (t.chain_id, t.address));
+        Ok(tokens)
+    }
+
+    async fn save_pool(&self, pool: &Pool) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        for token in &pool.tokens {
+            state
+                .tokens
+                .insert((token.address, token.chain_id), token.clone());
+        }
+        // Updated in place like an upsert keeps its rowid, and without dropping a known
+        // creation block.
+        let key = (pool.address, pool.chain_id);
+        match state.pools.iter_mut().find(|p| (p.address, p.chain_id) == key) {
+            Some(known) => {
+                let creation_block = known.creation_block;
+                *known = pool.clone();
+                if known.creation_block == 0 {
+                    known.creation_block = creation_block;
+                }
+            }
+            None => state.pools.push(pool.clone()),
+        }
+        Ok(())
+    }
+
+    async fn get_pool(&self, address: Address, chain_id: u64) -> Result<Option<Pool>> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .pools
+            .iter()
+            .find(|p| p.address == address && p.chain_id == chain_id)
+            .cloned())
+    }
+
+    async fn get_pools_by_dex(&self, dex: &str, chain_id: u64) -> Result<Vec<Pool>> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .pools
+            .iter()
+            .filter(|p| p.dex == dex && p.chain_id == chain_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_pools(&self, chain_id: Option<u64>, dex: Option<&str>) -> Result<Vec<Pool>> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .pools
+            .iter()
+            .filter(|p| chain_id.is_none_or(|c| p.chain_id == c) && dex.is_none_or(|d| p.dex == d))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_pools_by_dex_paginated(
+        &self,
+        dex: &str,
+        chain_id: u64,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Pool>> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .pools
+            .iter()
+            .filter(|p| p.dex == dex && p.chain_id == chain_id)
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_all_pools_paginated(
+        &self,
+        chain_id: u64,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Pool>> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .pools
+            .iter()
+            .rev()
+            .filter(|p| p.chain_id == chain_id && LISTED_DEXES.contains(&p.dex.as_str()))
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_pools_by_token(
+        &self,
+        token0: Address,
+        token1: Address,
+        chain_id: u64,
+        dex: Option<&str>,
+        fee: Option<u64>,
+    ) -> Result<Vec<Pool>> {
+        let state = self.state.read().unwrap();
+        let mut pools: Vec<Pool> = state
+            .pools
+            .iter()
+            .filter(|p| {
+                p.chain_id == chain_id
+                    && p.tokens.len() >= 2
+                    && ((p.tokens[0].address == token0 && p.tokens[1].address == token1)
+                        || (p.tokens[0].address == token1 && p.tokens[1].address == token0))
+                    && dex.is_none_or(|d| p.dex == d)
+                    && fee.is_none_or(|f| p.fee == f)
+            })
+            .cloned()
+            .collect();
+        pools.sort_by(|a, b| a.dex.cmp(&b.dex).then(a.fee.cmp(&b.fee)));
+        Ok(pools)
+    }
+
+    async fn get_pools_containing_token(
+        &self,
+        token: Address,
+        chain_id: u64,
+        dex: Option<&str>,
+    ) -> Result<Vec<Pool>> {
+        let state = self.state.read().unwrap();
+        let mut pools: Vec<Pool> = state
+            .pools
+            .iter()
+            .filter(|p| {
+                p.chain_id == chain_id
+                    && p.tokens.iter().any(|t| t.address == token)
+                    && dex.is_none_or(|d| p.dex == d)
+            })
+            .cloned()
+            .collect();
+        pools.sort_by(|a, b| a.dex.cmp(&b.dex).then(a.fee.cmp(&b.fee)));
+        Ok(pools)
+    }
+
+    async fn save_liquidity_distribution(
+        &self,
+        distribution: &LiquidityDistribution,
+    ) -> Result<()> {
+        self.state.write().unwrap().save_distribution(distribution, None);
+        Ok(())
+    }
+
+    async fn get_liquidity_distributions(
+        &self,
+        token0: Address,
+        token1: Address,
+        dex: &str,
+        chain_id: u64,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        let state = self.state.read().unwrap();
+        let mut distributions: Vec<LiquidityDistribution> = state
+            .distributions
+            .values()
+            .filter(|d| same_pair(d, token0, token1, dex, chain_id))
+            .cloned()
+            .collect();
+        distributions.sort_by_key(|d| d.pool);
+        Ok(distributions)
+    }
+
+    async fn get_liquidity_history(
+        &self,
+        token0: Address,
+        token1: Address,
+        dex: &str,
+        chain_id: u64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        let state = self.state.read().unwrap();
+        let mut history: Vec<LiquidityDistribution> = state
+            .history
+            .iter()
+            .map(|e| &e.distribution)
+            .filter(|d| same_pair(d, token0, token1, dex, chain_id) && d.timestamp >= from && d.timestamp <= to)
+            .cloned()
+            .collect();
+        history.sort_by_key(|d| d.timestamp);
+        Ok(history)
+    }
+
+    async fn list_liquidity_distributions(
+        &self,
+        chain_id: Option<u64>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .distributions
+            .values()
+            .filter(|d| chain_id.is_none_or(|c| d.chain_id == c))
+            .cloned()
+            .collect())
+    }
+
+    async fn list_liquidity_history(
+        &self,
+        chain_id: Option<u64>,
+        dex: Option<&str>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        let state = self.state.read().unwrap();
+        let mut history: Vec<LiquidityDistribution> = state
+            .history
+            .iter()
+            .map(|e| &e.distribution)
+            .filter(|d| {
+                chain_id.is_none_or(|c| d.chain_id == c)
+                    && dex.is_none_or(|x| d.dex == x)
+                    && d.timestamp >= from
+                    && d.timestamp <= to
+            })
+            .cloned()
+            .collect();
+        history.sort_by_key(|d| d.timestamp);
+        Ok(history)
+    }
+
+    async fn compact_snapshots(
+        &self,
+        policy: &RetentionConfig,
+        now: DateTime<Utc>,
+    ) -> Result<CompactionStats> {
+        let mut state = self.state.write().unwrap();
+        let snapshots: Vec<(i64, DistributionKey, i64)> = state
+            .history
+            .iter()
+            .map(|e| {
+                let d = &e.distribution;
+                (e.id, distribution_key(d), d.timestamp.timestamp())
+            })
+            .collect();
+        let expired: HashSet<i64> =
+            retention::expired_snapshots(&snapshots, policy, now.timestamp())
+                .into_iter()
+                .collect();
+        state.history.retain(|e| !expired.contains(&e.id));
+        Ok(CompactionStats {
+            examined: snapshots.len() as u64,
+            deleted: expired.len() as u64,
+        })
+    }
+
+    async fn get_snapshot_stats(&self) -> Result<SnapshotStats> {
+        let state = self.state.read().unwrap();
+        let timestamps = state.history.iter().map(|e| e.distribution.timestamp);
+        Ok(SnapshotStats {
+            count: state.history.len() as u64,
+            finalized: state.history.iter().filter(|e| e.finalized).count() as u64,
+            oldest: timestamps.clone().min(),
+            newest: timestamps.max(),
+        })
+    }
+
+    async fn get_checkpoint(&self, chain_id: u64, dex: &str, stream: &str) -> Result<Option<u64>> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .checkpoints
+            .get(&(chain_id, dex.to_string(), stream.to_string()))
+            .copied())
+    }
+
+    async fn save_checkpoint(&self, chain_id: u64, dex: &str, stream: &str, block: u64) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        state
+            .checkpoints
+            .insert((chain_id, dex.to_string(), stream.to_string()), block);
+        Ok(())
+    }
+    async fn save_liquidity_distribution_at(
+        &self,
+        distribution: &LiquidityDistribution,
+        block: &BlockRef,
+    ) -> Result<()> {
+        self.state
+            .write()
+            .unwrap()
+            .save_distribution(distribution, Some(*block));
+        Ok(())
+    }
+
+    async fn save_block(&self, chain_id: u64, block: &BlockRef) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        state
+            .blocks
+            .entry(chain_id)
+            .or_default()
+            .insert(block.number, *block);
+        Ok(())
+    }
+
+    async fn get_recent_blocks(&self, chain_id: u64, limit: usize) -> Result<Vec<BlockRef>> {
+        let state = self.state.read().unwrap();
+        Ok(state
+            .blocks
+            .get(&chain_id)
+            .map(|blocks| blocks.values().rev().take(limit).copied().collect())
+            .unwrap_or_default())
+    }
+
+    async fn rollback_to(&self, chain_id: u64, fork_block: u64) -> Result<RollbackStats> {
+        let mut state = self.state.write().unwrap();
+        let orphaned = |e: &HistoryEntry| {
+            e.distribution.chain_id == chain_id && e.block.is_some_and(|b| b.number > fork_block)
+        };
+
+        let affected: HashSet<DistributionKey> = state
+            .history
+            .iter()
+            .filter(|e| orphaned(e))
+            .map(|e| distribution_key(&e.distribution))
+            .collect();
+        let before = state.history.len();
+        state.history.retain(|e| !orphaned(e));
+        let snapshots = (before - state.history.len()) as u64;
+
+        for key in affected {
+            let newest = state
+                .history
+                .iter()
+                .filter(|e| distribution_key(&e.distribution) == key)
+                .max_by_key(|e| (e.distribution.timestamp, e.id))
+                .map(|e| e.distribution.clone());
+            match newest {
+                Some(distribution) => state.distributions.insert(key, distribution),
+                None => state.distributions.remove(&key),
+            };
+        }
+
+        let blocks = match state.blocks.get_mut(&chain_id) {
+            Some(blocks) => blocks.split_off(&(fork_block + 1)).len() as u64,
+            None => 0,
+        };
+        for ((chain, _, _), block) in state.checkpoints.iter_mut() {
+            if *chain == chain_id && *block > fork_block {
+                *block = fork_block;
+            }
+        }
+        Ok(RollbackStats { snapshots, blocks })
+    }
+
+    async fn finalize_blocks(&self, chain_id: u64, block: u64) -> Result<u64> {
+        let mut state = self.state.write().unwrap();
+        let mut finalized = 0;
+        for entry in state.history.iter_mut() {
+            let final_now = entry.distribution.chain_id == chain_id
+                && !entry.finalized
+                && entry.block.is_some_and(|b| b.number <= block);
+            if final_now {
+                entry.finalized = true;
+                finalized += 1;
+            }
+        }
+        if let Some(blocks) = state.blocks.get_mut(&chain_id) {
+            *blocks = blocks.split_off(&block);
+        }
+        Ok(finalized)
+    }
+    async fn save_indexer_run(&self, run: &IndexerRun) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        state.runs.push_back(run.clone());
+        while state.runs.len() > MAX_INDEXER_RUNS {
+            state.runs.pop_front();
+        }
+        Ok(())
+    }
+
+    async fn get_indexer_runs(&self, limit: usize) -> Result<Vec<IndexerRun>> {
+        let state = self.state.read().unwrap();
+        Ok(state.runs.iter().rev().take(limit).cloned().collect())
+    }
+
+    async fn record_pool_attempt(&self, attempt: &PoolAttempt) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        let status = state
+            .pool_status
+            .entry((attempt.chain_id, attempt.pool))
+            .or_insert_with(|| PoolStatus {
+                chain_id: attempt.chain_id,
+                dex: attempt.dex.clone(),
+                pool: attempt.pool,
+                last_attempt: attempt.at,
+                last_success: None,
+                last_error: None,
+                last_error_at: None,
+                error_count: 0,
+                duration_ms: 0,
+            });
+        status.dex = attempt.dex.clone();
+        status.last_attempt = attempt.at;
+        status.duration_ms = attempt.duration_ms;
+        match &attempt.error {
+            None => {
+                status.last_success = Some(attempt.at);
+                status.error_count = 0;
+            }
+            Some(error) => {
+                status.last_error = Some(error.clone());
+                status.last_error_at = Some(attempt.at);
+                status.error_count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn list_pool_status(&self, chain_id: Option<u64>, dex: Option<&str>) -> Result<Vec<PoolStatus>> {
+        let state = self.state.read().unwrap();
+        let mut statuses: Vec<PoolStatus> = state
+            .pool_status
+            .values()
+            .filter(|s| chain_id.is_none_or(|c| s.chain_id == c))
+            .filter(|s| dex.is_none_or(|d| s.dex == d))
+            .cloned()
+            .collect();
+        statuses.sort_by(|a, b| (a.chain_id, &a.dex, a.pool).cmp(&(b.chain_id, &b.dex, b.pool)));
+        Ok(statuses)
+    }
+}
@@ -0,0 +1,508 @@
+use crate::error::Error;
+use crate::config::{CanonicalAsset, RetentionConfig};
+use crate::core::bucketing::{bucket_levels, Grid};
+use crate::core::cross_chain::{merge_chains, CrossChainBook};
+use crate::core::liquidity::weighted_price;
+use crate::core::pricing::PriceOracle;
+use crate::models::{
+    BlockRef, CompactionStats, IndexerRun, IndexerStatus, LiquidityDistribution, Pool,
+    PoolAttempt, PoolHealth, PoolStatus, PriceLiquidity, RollbackStats, Side, SnapshotStats, Token,
+};
+use crate::Result;
+use alloy_primitives::Address;
+use chrono::{DateTime, Utc};
+use tracing::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub mod dry_run;
+pub mod export;
+pub mod memory;
+pub mod retention;
+pub mod sqlite;
+
+pub use dry_run::DryRunStorage;
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
+
+/// Indexer runs kept in `indexer_runs`; older ones are dropped as new ones are saved.
+pub const MAX_INDEXER_RUNS: usize = 10_000;
+
+#[async_trait::async_trait]
+pub trait Storage: Send + Sync {
+    // Token operations
+    async fn save_token(&self, token: &Token) -> Result<()>;
+    async fn get_token(&self, address: Address, chain_id: u64) -> Result<Option<Token>>;
+    async fn list_tokens(&self, chain_id: Option<u64>) -> Result<Vec<Token>>;
+
+    // Pool operations
+    async fn save_pool(&self, pool: &Pool) -> Result<()>;
+    /// The pool at `address` on `chain_id`; CREATE2 factories reuse addresses across chains.
+    async fn get_pool(&self, address: Address, chain_id: u64) -> Result<Option<Pool>>;
+    async fn get_pools_by_dex(&self, dex: &str, chain_id: u64) -> Result<Vec<Pool>>;
+    async fn list_pools(&self, chain_id: Option<u64>, dex: Option<&str>) -> Result<Vec<Pool>>;
+    async fn get_pools_by_dex_paginated(&self, dex: &str, chain_id: u64, limit: u64, offset: u64) -> Result<Vec<Pool>>;
+    async fn get_all_pools_paginated(&self, chain_id: u64, limit: u64, offset: u64) -> Result<Vec<Pool>>;
+    async fn get_pools_by_token(
+        &self,
+        token0: Address,
+        token1: Address,
+        chain_id: u64,
+        dex: Option<&str>,
+        fee: Option<u64>,
+    ) -> Result<Vec<Pool>>;
+    async fn get_pools_containing_token(
+        &self,
+        token: Address,
+        chain_id: u64,
+        dex: Option<&str>,
+    ) -> Result<Vec<Pool>>;
+
+    // Liquidity distribution operations
+    /// Saves a snapshot of one pool. A pool's snapshot replaces one its pair saved before
+    /// distributions were kept per pool.
+    async fn save_liquidity_distribution(&self, distribution: &LiquidityDistribution) -> Result<()>;
+    /// The latest distribution of every pool of a pair on a DEX, one per fee tier.
+    async fn get_liquidity_distributions(
+        &self,
+        token0: Address,
+        token1: Address,
+        dex: &str,
+        chain_id: u64,
+    ) -> Result<Vec<LiquidityDistribution>>;
+    /// The latest distribution of every pool, optionally narrowed to a chain.
+    async fn list_liquidity_distributions(&self, chain_id: Option<u64>) -> Result<Vec<LiquidityDistribution>>;
+
+    // Snapshot history operations
+    /// Every stored snapshot of a pair/DEX/chain within `[from, to]`, across its pools, oldest
+    /// first.
+    async fn get_liquidity_history(
+        &self,
+        token0: Address,
+        token1: Address,
+        dex: &str,
+        chain_id: u64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LiquidityDistribution>>;
+    /// Every stored snapshot within `[from, to]` across pairs, optionally narrowed to a chain and
+    /// DEX, oldest first.
+    async fn list_liquidity_history(
+        &self,
+        chain_id: Option<u64>,
+        dex: Option<&str>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LiquidityDistribution>>;
+    /// Deletes history snapshots that `policy` no longer keeps, as of `now`.
+    async fn compact_snapshots(&self, policy: &RetentionConfig, now: DateTime<Utc>) -> Result<CompactionStats>;
+    async fn get_snapshot_stats(&self) -> Result<SnapshotStats>;
+
+    // Sync checkpoint operations
+    /// Last block fully processed by `stream` (e.g. pool discovery) for a DEX on a chain.
+    async fn get_checkpoint(&self, chain_id: u64, dex: &str, stream: &str) -> Result<Option<u64>>;
+    async fn save_checkpoint(&self, chain_id: u64, dex: &str, stream: &str, block: u64) -> Result<()>;
+
+    // Chain tracking operations
+    /// Saves a snapshot taken at `block`, so that a reorg past it can roll it back.
+    async fn save_liquidity_distribution_at(
+        &self,
+        distribution: &LiquidityDistribution,
+        block: &BlockRef,
+    ) -> Result<()>;
+    async fn save_block(&self, chain_id: u64, block: &BlockRef) -> Result<()>;
+    /// The `limit` newest recorded blocks of a chain, newest first.
+    async fn get_recent_blocks(&self, chain_id: u64, limit: usize) -> Result<Vec<BlockRef>>;
+    /// Forgets everything recorded on a chain after `fork_block`: blocks, the snapshots taken
+    /// at them, and checkpoints past it. Latest distributions fall back to the newest
+    /// remaining snapshot of their series.
+    async fn rollback_to(&self, chain_id: u64, fork_block: u64) -> Result<RollbackStats>;
+    /// Marks snapshots at or below `block` final and drops older block hashes. Returns the
+    /// number of snapshots newly finalized.
+    async fn finalize_blocks(&self, chain_id: u64, block: u64) -> Result<u64>;
+
+    // Indexer status operations
+    /// Records a finished indexer cycle, keeping the newest `MAX_INDEXER_RUNS`.
+    async fn save_indexer_run(&self, run: &IndexerRun) -> Result<()>;
+    /// The `limit` newest indexer cycles, newest first.
+    async fn get_indexer_runs(&self, limit: usize) -> Result<Vec<IndexerRun>>;
+    /// Folds a refresh attempt into its pool's status: a success resets the error count, a
+    /// failure increments it and keeps the message.
+    async fn record_pool_attempt(&self, attempt: &PoolAttempt) -> Result<()>;
+    /// Status of every pool the indexer attempted, ordered by chain, DEX and address.
+    async fn list_pool_status(&self, chain_id: Option<u64>, dex: Option<&str>) -> Result<Vec<PoolStatus>>;
+
+    /// Makes every completed write durable before shutdown. Stores without buffered writes
+    /// have nothing to do.
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The `runs` newest indexer runs and the health of every pool the indexer attempted, as of
+/// `now`.
+///
+/// With `stale_secs` set, only pools whose last success is at least that old, or that never
+/// succeeded, are listed.
+pub async fn indexer_status(
+    storage: Arc<dyn Storage>,
+    chain_id: Option<u64>,
+    dex: Option<&str>,
+    stale_secs: Option<i64>,
+    runs: usize,
+    now: DateTime<Utc>,
+) -> Result<IndexerStatus> {
+    let mut recent = storage.get_indexer_runs(runs.max(1)).await?;
+    let last_run = recent.first().cloned();
+    recent.truncate(runs);
+    let pools = storage
+        .list_pool_status(chain_id, dex)
+        .await?
+        .into_iter()
+        .map(|status| PoolHealth {
+            stale_secs: status.last_success.map(|at| (now - at).num_seconds()),
+            status,
+        })
+        .filter(|health| match (stale_secs, health.stale_secs) {
+            (Some(min), Some(stale)) => stale >= min,
+            _ => true,
+        })
+        .collect();
+
+    Ok(IndexerStatus {
+        last_run,
+        runs: recent,
+        pools,
+    })
+}
+
+pub async fn reverse_pair(
+    distribution: LiquidityDistribution,
+) -> Result<LiquidityDistribution> {
+    let LiquidityDistribution {
+        token0,
+        token1,
+        dex,
+        chain_id,
+        timestamp,
+        current_price,
+        price_levels,
+        pool,
+    } = distribution;
+
+    let reversed_levels: Vec<PriceLiquidity> = price_levels
+        .into_iter()
+        .map(|pl| PriceLiquidity {
+            // 한 쪽 토큰의 매수 유동성은 반대 쪽에서 보면 매도 유동성
+            side: match pl.side {
+                Side::Buy => Side::Sell,
+                Side::Sell => Side::Buy,
+            },
+            // 가격 구간도 상하한을 뒤집어서 역수로
+            lower_price: 1.0 / pl.upper_price,
+            upper_price: 1.0 / pl.lower_price,
+            // 토큰 유동성도 서로 스왑
+            token0_liquidity: pl.token1_liquidity,
+            token1_liquidity: pl.token0_liquidity,
+            timestamp: pl.timestamp,
+        })
+        .collect();
+
+    let reversed_price = 1.0 / current_price;
+
+    let reversed_distribution = LiquidityDistribution {
+        token0: token1,
+        token1: token0,
+        dex,
+        chain_id,
+        timestamp,
+        current_price: reversed_price,
+        price_levels: reversed_levels,
+        pool,
+    };
+
+    Ok(reversed_distribution)
+}
+
+/// Latest distribution of every pool of `token0`/`token1` on `dex`, quoted as token0 in token1.
+///
+/// Pools stored with their tokens the other way round are inverted with [`reverse_pair`].
+pub async fn get_pair_distributions(
+    storage: Arc<dyn Storage>,
+    token0: Address,
+    token1: Address,
+    dex: &str,
+    chain_id: u64,
+) -> Result<Vec<LiquidityDistribution>> {
+    let mut distributions = storage
+        .get_liquidity_distributions(token0, token1, dex, chain_id)
+        .await?;
+    for distribution in storage
+        .get_liquidity_distributions(token1, token0, dex, chain_id)
+        .await?
+    {
+        distributions.push(reverse_pair(distribution).await?);
+    }
+    Ok(distributions)
+}
+
+pub async fn reverse_current_price_only(
+    distribution: LiquidityDistribution,
+) -> Result<LiquidityDistribution> {
+    Ok(LiquidityDistribution {
+        current_price: 1.0 / distribution.current_price,
+        ..distribution
+    })
+}
+
+
+pub async fn get_current_price(
+    storage: Arc<dyn Storage>,
+    token0: Address,
+    token1: Address,
+    dex : &str,
+    chain_id: u64,
+    )  -> Result<f64> {
+    // Averaged over the pair's pools; 0.0 if there are none
+    let distributions = get_pair_distributions(storage, token0, token1, dex, chain_id).await?;
+    Ok(weighted_price(&distributions))
+}
+
+/// Estimates a pool's TVL in USD from its latest stored snapshot, or its pair's if the pool has
+/// none of its own yet.
+///
+/// The snapshot's liquidity is valued in token1 at the snapshot price and converted to USD
+/// through whichever token `prices` can route. Returns `None` when the pool has no snapshot or
+/// neither token can be priced.
+pub async fn estimate_pool_tvl_usd(
+    storage: Arc<dyn Storage>,
+    prices: &PriceOracle,
+    pool: &Pool,
+) -> Result<Option<f64>> {
+    let [token0, token1] = [&pool.tokens[0], &pool.tokens[1]].map(|t| t.address);
+    let Some(distribution) = storage
+        .get_liquidity_distributions(token0, token1, &pool.dex, pool.chain_id)
+        .await?
+        .into_iter()
+        .find(|d| d.pool.is_none_or(|p| p == pool.address))
+    else {
+        return Ok(None);
+    };
+    distribution_tvl_usd(prices, &distribution).await
+}
+
+/// Values the liquidity of `distribution` in USD, like [`estimate_pool_tvl_usd`] does with a
+/// stored snapshot. Returns `None` when neither token can be priced.
+pub async fn distribution_tvl_usd(
+    prices: &PriceOracle,
+    distribution: &LiquidityDistribution,
+) -> Result<Option<f64>> {
+    let (token0, token1) = (distribution.token0.address, distribution.token1.address);
+    let price = distribution.current_price;
+    let value_in_token1: f64 = distribution
+        .price_levels
+        .iter()
+        .map(|l| l.token0_liquidity * price + l.token1_liquidity)
+        .sum();
+
+    if let Some(usd) = prices.usd_price(token1, distribution.chain_id).await? {
+        return Ok(Some(value_in_token1 * usd));
+    }
+    if price > 0.0 {
+        if let Some(usd) = prices.usd_price(token0, distribution.chain_id).await? {
+            return Ok(Some(value_in_token1 / price * usd));
+        }
+    }
+    Ok(None)
+}
+
+/// Liquidity of `token` across every stored pool containing it on `dexes`, merged into one book
+/// quoted in the chain's numeraire.
+///
+/// Each pool is oriented with `token` as token0, whichever position it holds in the pool. Its
+/// prices and bids are converted to the numeraire at the counter token's routed price; asks stay
+/// in `token`. Pools whose counter token cannot be priced are left out. The merged levels are
+/// bucketed on `grid` around the token's routed price, within `range` of it when set.
+pub async fn aggregate_token_liquidity(
+    storage: Arc<dyn Storage>,
+    prices: &PriceOracle,
+    token: Address,
+    chain_id: u64,
+    dexes: &[String],
+    grid: Grid,
+    range: Option<f64>,
+) -> Result<LiquidityDistribution> {
+    let mut book = token_numeraire_book(storage, prices, token, chain_id, dexes).await?;
+    book.price_levels = bucket_levels(&book.price_levels, grid, book.current_price, range);
+    Ok(book)
+}
+
+/// Liquidity of a canonical asset across every chain, merged into one USD book.
+///
+/// Each of the asset's tokens is aggregated over its chain's pools on `dexes[chain_id]` as in
+/// [`aggregate_token_liquidity`], then the books are merged with [`merge_chains`]. This relies
+/// on every chain's numeraire being a USD stablecoin. Tokens that are not stored, cannot be
+/// priced or have no liquidity are left out, and tokens more than `threshold` from the
+/// cross-chain price are flagged.
+pub async fn aggregate_asset_liquidity(
+    storage: Arc<dyn Storage>,
+    prices: &PriceOracle,
+    asset: &CanonicalAsset,
+    dexes: &HashMap<u64, Vec<String>>,
+    grid: Grid,
+    range: Option<f64>,
+    threshold: f64,
+) -> Result<CrossChainBook> {
+    let mut books = Vec::new();
+    for token in &asset.tokens {
+        let Ok(address) = Address::from_str(&token.token) else {
+            warn!("Invalid address {} for asset {} on chain {}", token.token, asset.id, token.chain_id);
+            continue;
+        };
+        let Some(dexes) = dexes.get(&token.chain_id) else {
+            continue;
+        };
+        match token_numeraire_book(storage.clone(), prices, address, token.chain_id, dexes).await {
+            Ok(book) => books.push(book),
+            Err(Error::DexError(e) | Error::InvalidAddress(e) | Error::ConfigError(e)) => {
+                info!("Leaving {} on chain {} out of {}: {}", address, token.chain_id, asset.id, e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if books.is_empty() {
+        return Err(Error::DexError(format!("No priced liquidity found for asset {}", asset.id)));
+    }
+    Ok(merge_chains(&books, grid, range, threshold))
+}
+
+/// Unbucketed book of `token` in its chain's numeraire; see [`aggregate_token_liquidity`].
+async fn token_numeraire_book(
+    storage: Arc<dyn Storage>,
+    prices: &PriceOracle,
+    token: Address,
+    chain_id: u64,
+    dexes: &[String],
+) -> Result<LiquidityDistribution> {
+    let base = storage
+        .get_token(token, chain_id)
+        .await?
+        .ok_or(Error::InvalidAddress(token.to_string()))?;
+    let numeraire = prices
+        .numeraire(chain_id)
+        .ok_or_else(|| Error::ConfigError(format!("No pricing numeraire for chain {}", chain_id)))?;
+    let quote = storage
+        .get_token(numeraire, chain_id)
+        .await?
+        .ok_or(Error::InvalidAddress(numeraire.to_string()))?;
+    let spot = prices
+        .usd_price(token, chain_id)
+        .await?
+        .ok_or_else(|| Error::DexError(format!("No route to price token {}", token)))?;
+
+    let mut levels = Vec::new();
+    let mut latest = None;
+    // Each pair is read once, with the latest distribution of every pool (fee tier) in it.
+    let mut seen = HashSet::new();
+    for pool in storage.get_pools_containing_token(token, chain_id, None).await? {
+        if !dexes.contains(&pool.dex) {
+            continue;
+        }
+        let Some(other) = pool.tokens.iter().map(|t| t.address).find(|&a| a != token) else {
+            continue;
+        };
+        if !seen.insert((pool.dex.clone(), other)) {
+            continue;
+        }
+        let distributions = get_pair_distributions(storage.clone(), token, other, &pool.dex, chain_id).await?;
+        if distributions.is_empty() {
+            continue;
+        }
+        let Some(other_usd) = prices.usd_price(other, chain_id).await? else {
+            info!("Skipping {} pair {}/{}: no price for {}", pool.dex, token, other, other);
+            continue;
+        };
+        for dist in distributions {
+            latest = latest.max(Some(dist.timestamp));
+            for mut price_level in dist.price_levels {
+                price_level.lower_price *= other_usd;
+                price_level.upper_price *= other_usd;
+                price_level.token1_liquidity *= other_usd;
+                let valid = [price_level.lower_price, price_level.token0_liquidity, price_level.token1_liquidity]
+                    .iter()
+                    .all(|v| v.is_finite() && *v >= 0.0);
+                if valid {
+                    levels.push(price_level);
+                }
+            }
+        }
+    }
+    if levels.is_empty() {
+        return Err(Error::DexError(format!("No priced liquidity found for token {}", token)));
+    }
+
+    Ok(LiquidityDistribution {
+        token0: base,
+        token1: quote,
+        current_price: spot,
+        dex: "aggregated".to_string(),
+        chain_id,
+        price_levels: levels,
+        timestamp: latest.unwrap_or_else(Utc::now),
+        pool: None,
+    })
+}
+
+pub async fn save_token_async(storage: Arc<dyn Storage>, token: Token) -> Result<()> {
+    storage.save_token(&token).await
+}
+
+pub async fn get_token_async(
+    storage: Arc<dyn Storage>,
+    address: Address,
+    chain_id: u64,
+) -> Result<Option<Token>> {
+    storage.get_token(address, chain_id).await
+}
+
+/// Saves a pool to the storage asynchronously.
+///
+/// # Examples
+///
+/// ```
+/// let storage = Arc::new(SqliteStorage::new(":memory:").unwrap());
+/// let pool = Pool::default();
+/// save_pool_async(storage, pool).await.unwrap();
+/// ```
+pub async fn save_pool_async(storage: Arc<dyn Storage>, pool: Pool) -> Result<()> {
+    storage.save_pool(&pool).await
+}
+
+/// Retrieves a pool by its address and chain asynchronously.
+///
+/// # Examples
+///
+/// ```
+/// let pool = get_pool_async(storage.clone(), pool_address, 1).await?;
+/// assert!(pool.is_some());
+/// ```
+pub async fn get_pool_async(
+    storage: Arc<dyn Storage>,
+    address: Address,
+    chain_id: u64,
+) -> Result<Option<Pool>> {
+    storage.get_pool(address, chain_id).await
+}
+
+pub async fn save_liquidity_distribution_async(
+    storage: Arc<dyn Storage>,
+    distribution: LiquidityDistribution,
+) -> Result<()> {
+    storage.save_liquidity_distribution(&distribution).await
+}
+
+
+
+
+
@@ -0,0 +1,60 @@
+use crate::config::RetentionConfig;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+const SECS_PER_HOUR: i64 = 3_600;
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Resolution a snapshot is kept at, based on its age.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Tier {
+    Hourly,
+    Daily,
+}
+
+/// Picks the snapshots that the retention policy no longer needs.
+///
+/// `snapshots` holds `(id, series, timestamp)` triples, where `series` identifies one
+/// pair/DEX/chain history and `timestamp` is in unix seconds. Snapshots newer than
+/// `raw_hours` are always kept; older ones are grouped per series into hourly (or, past
+/// `hourly_days`, daily) buckets and only the latest snapshot of each bucket survives.
+///
+/// Returns the ids to delete.
+pub fn expired_snapshots<K>(
+    snapshots: &[(i64, K, i64)],
+    policy: &RetentionConfig,
+    now: i64,
+) -> Vec<i64>
+where
+    K: Hash + Eq + Clone,
+{
+    let raw_cutoff = now - (policy.raw_hours as i64) * SECS_PER_HOUR;
+    let hourly_cutoff = now - (policy.hourly_days as i64) * SECS_PER_DAY;
+
+    // Latest (timestamp, id) per bucket.
+    let mut keep: HashMap<(K, Tier, i64), (i64, i64)> = HashMap::new();
+    for (id, series, ts) in snapshots {
+        if *ts >= raw_cutoff {
+            continue;
+        }
+        let bucket = if *ts >= hourly_cutoff {
+            (series.clone(), Tier::Hourly, ts.div_euclid(SECS_PER_HOUR))
+        } else {
+            (series.clone(), Tier::Daily, ts.div_euclid(SECS_PER_DAY))
+        };
+        keep.entry(bucket)
+            .and_modify(|best| {
+                if (*ts, *id) > *best {
+                    *best = (*ts, *id);
+                }
+            })
+            .or_insert((*ts, *id));
+    }
+
+    let survivors: HashSet<i64> = keep.values().map(|(_, id)| *id).collect();
+    snapshots
+        .iter()
+        .filter(|(id, _, ts)| *ts < raw_cutoff && !survivors.contains(id))
+        .map(|(id, _, _)| *id)
+        .collect()
+}
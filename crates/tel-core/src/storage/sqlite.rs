@@ -0,0 +1,1356 @@
+use crate::config::RetentionConfig;
+use crate::error::Error;
+use crate::models::{
+    BlockRef, CompactionStats, IndexerRun, LiquidityDistribution, Pool, PoolAttempt, PoolStatus,
+    RollbackStats, SnapshotStats, Token,
+};
+use crate::storage::{retention, Storage, MAX_INDEXER_RUNS};
+use crate::Result;
+use alloy_primitives::{Address, B256};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, TransactionBehavior};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Number of read-only connections opened next to the writer.
+const READER_POOL_SIZE: usize = 4;
+
+/// How long a connection waits on a locked database before giving up.
+const BUSY_TIMEOUT_MS: u64 = 5_000;
+
+/// Columns of `tokens`. The same address can hold different tokens on different chains.
+const TOKENS_COLUMNS: &str = "(
+    address TEXT NOT NULL,
+    chain_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    decimals INTEGER NOT NULL,
+    PRIMARY KEY (address, chain_id)
+)";
+
+/// Columns of `pools`, keyed like `tokens`.
+const POOLS_COLUMNS: &str = "(
+    address TEXT NOT NULL,
+    chain_id INTEGER NOT NULL,
+    dex TEXT NOT NULL,
+    token0_address TEXT NOT NULL,
+    token1_address TEXT NOT NULL,
+    fee INTEGER,
+    creation_block INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (address, chain_id),
+    FOREIGN KEY (token0_address, chain_id) REFERENCES tokens (address, chain_id),
+    FOREIGN KEY (token1_address, chain_id) REFERENCES tokens (address, chain_id)
+)";
+
+/// Columns of `liquidity_distributions`, one row per pool. `pool_address` is empty for
+/// snapshots saved before distributions were kept per pool.
+const DISTRIBUTIONS_COLUMNS: &str = "(
+    token0_address TEXT NOT NULL,
+    token1_address TEXT NOT NULL,
+    dex TEXT NOT NULL,
+    chain_id INTEGER NOT NULL,
+    pool_address TEXT NOT NULL DEFAULT '',
+    data TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (token0_address, token1_address, dex, chain_id, pool_address),
+    FOREIGN KEY (token0_address, chain_id) REFERENCES tokens (address, chain_id),
+    FOREIGN KEY (token1_address, chain_id) REFERENCES tokens (address, chain_id)
+)";
+
+/// SQLite-backed `Storage`.
+///
+/// All queries run on Tokio's blocking thread pool. Writes go through a single
+/// WAL-mode writer connection, while reads are spread over a small pool of
+/// read-only connections, so the API can keep serving while the indexer writes.
+/// In-memory databases cannot be shared between connections and fall back to
+/// the writer for reads.
+pub struct SqliteStorage {
+    writer: Arc<Mutex<Connection>>,
+    readers: Vec<Arc<Mutex<Connection>>>,
+    next_reader: AtomicUsize,
+}
+
+impl SqliteStorage {
+    pub fn new(database_path: &str) -> Result<Self> {
+        Self::with_readers(database_path, READER_POOL_SIZE)
+    }
+
+    /// Opens the database with `reader_count` read-only connections.
+    pub fn with_readers(database_path: &str, reader_count: usize) -> Result<Self> {
+        let writer = Connection::open(database_path)?;
+        writer.busy_timeout(std::time::Duration::from_millis(BUSY_TIMEOUT_MS))?;
+
+        let in_memory = database_path == ":memory:" || database_path.contains("mode=memory");
+        if !in_memory {
+            writer.pragma_update(None, "journal_mode", "WAL")?;
+            writer.pragma_update(None, "synchronous", "NORMAL")?;
+        }
+        Self::init_schema(&writer)?;
+
+        let mut readers = Vec::new();
+        if !in_memory {
+            for _ in 0..reader_count {
+                let reader = Connection::open_with_flags(
+                    database_path,
+                    OpenFlags::SQLITE_OPEN_READ_ONLY
+                        | OpenFlags::SQLITE_OPEN_URI
+                        | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                )?;
+                reader.busy_timeout(std::time::Duration::from_millis(BUSY_TIMEOUT_MS))?;
+                readers.push(Arc::new(Mutex::new(reader)));
+            }
+        }
+
+        Ok(Self {
+            writer: Arc::new(Mutex::new(writer)),
+            readers,
+            next_reader: AtomicUsize::new(0),
+        })
+    }
+
+    fn init_schema(conn: &Connection) -> Result<()> {
+        conn.execute(&format!("CREATE TABLE IF NOT EXISTS tokens {TOKENS_COLUMNS}"), [])?;
+        conn.execute(&format!("CREATE TABLE IF NOT EXISTS pools {POOLS_COLUMNS}"), [])?;
+        // Databases created when tokens and pools were keyed by address alone.
+        Self::ensure_primary_key(
+            conn,
+            "tokens",
+            "chain_id",
+            TOKENS_COLUMNS,
+            "address, chain_id, name, symbol, decimals",
+        )?;
+        Self::ensure_primary_key(
+            conn,
+            "pools",
+            "chain_id",
+            POOLS_COLUMNS,
+            "address, chain_id, dex, token0_address, token1_address, fee",
+        )?;
+        Self::ensure_column(conn, "pools", "creation_block", "INTEGER NOT NULL DEFAULT 0")?;
+
+        conn.execute(
+            &format!("CREATE TABLE IF NOT EXISTS liquidity_distributions {DISTRIBUTIONS_COLUMNS}"),
+            [],
+        )?;
+        // Databases created when distributions were kept per pair and DEX.
+        Self::ensure_primary_key(
+            conn,
+            "liquidity_distributions",
+            "pool_address",
+            DISTRIBUTIONS_COLUMNS,
+            "token0_address, token1_address, dex, chain_id, data, timestamp",
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS liquidity_distribution_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                token0_address TEXT NOT NULL,
+                token1_address TEXT NOT NULL,
+                dex TEXT NOT NULL,
+                chain_id INTEGER NOT NULL,
+                pool_address TEXT NOT NULL DEFAULT '',
+                data TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                block_number INTEGER,
+                block_hash TEXT,
+                finalized INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+        // Databases created before snapshots were tagged with their block.
+        Self::ensure_column(conn, "liquidity_distribution_history", "block_number", "INTEGER")?;
+        Self::ensure_column(conn, "liquidity_distribution_history", "block_hash", "TEXT")?;
+        Self::ensure_column(
+            conn,
+            "liquidity_distribution_history",
+            "finalized",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        Self::ensure_column(
+            conn,
+            "liquidity_distribution_history",
+            "pool_address",
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_distribution_history_block
+             ON liquidity_distribution_history (chain_id, block_number)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_distribution_history_series
+             ON liquidity_distribution_history (token0_address, token1_address, dex, chain_id, timestamp)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_checkpoints (
+                chain_id INTEGER NOT NULL,
+                dex TEXT NOT NULL,
+                stream TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (chain_id, dex, stream)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS chain_blocks (
+                chain_id INTEGER NOT NULL,
+                number INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                parent_hash TEXT NOT NULL,
+                PRIMARY KEY (chain_id, number)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS indexer_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                pools INTEGER NOT NULL,
+                snapshotted INTEGER NOT NULL,
+                failed INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS pool_status (
+                chain_id INTEGER NOT NULL,
+                pool_address TEXT NOT NULL,
+                dex TEXT NOT NULL,
+                last_attempt INTEGER NOT NULL,
+                last_success INTEGER,
+                last_error TEXT,
+                last_error_at INTEGER,
+                error_count INTEGER NOT NULL DEFAULT 0,
+                duration_ms INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (chain_id, pool_address)
+            )",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    /// Rebuilds `table` with `columns` if its primary key does not include `key`, copying
+    /// `copied` over. Rows that only differed by `key` had already overwritten each other.
+    fn ensure_primary_key(
+        conn: &Connection,
+        table: &str,
+        key: &str,
+        columns: &str,
+        copied: &str,
+    ) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let keyed = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, i64>(5)?)))?
+            .collect::<rusqlite::Result<Vec<(String, i64)>>>()?
+            .iter()
+            .any(|(name, pk)| name == key && *pk > 0);
+        if !keyed {
+            conn.execute_batch(&format!(
+                "BEGIN;
+                 CREATE TABLE {table}_rekeyed {columns};
+                 INSERT OR REPLACE INTO {table}_rekeyed ({copied}) SELECT {copied} FROM {table};
+                 DROP TABLE {table};
+                 ALTER TABLE {table}_rekeyed RENAME TO {table};
+                 COMMIT;"
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Adds `column` to `table` unless an earlier schema already has it.
+    fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<Vec<String>>>()?
+            .iter()
+            .any(|name| name == column);
+        if !exists {
+            conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"), [])?;
+        }
+        Ok(())
+    }
+
+    /// Upserts the latest distribution of a pool and appends it to the history, in one
+    /// transaction. A pool's snapshot replaces its pair's unattributed one.
+    fn insert_distribution(
+        conn: &mut Connection,
+        distribution: &LiquidityDistribution,
+        block: Option<&BlockRef>,
+    ) -> Result<()> {
+        let data = serde_json::to_string(distribution)
+            .map_err(|e| Error::DatabaseError(format!("serialize distribution: {e}")))?;
+        let token0 = distribution.token0.address.to_string();
+        let token1 = distribution.token1.address.to_string();
+        let dex = &distribution.dex;
+        let chain_id = distribution.chain_id;
+        let pool = distribution.pool.map(|p| p.to_string()).unwrap_or_default();
+        let timestamp = distribution.timestamp.timestamp();
+
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| Error::DatabaseError(format!("tx start: {e}")))?;
+
+        tx.execute(
+            "INSERT INTO liquidity_distributions
+             (token0_address, token1_address, dex, chain_id, pool_address, data, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (token0_address, token1_address, dex, chain_id, pool_address) DO UPDATE
+             SET data = excluded.data, timestamp = excluded.timestamp
+             WHERE excluded.timestamp >= liquidity_distributions.timestamp",
+            params![token0, token1, dex, chain_id, pool, data, timestamp],
+        )
+        .map_err(|e| Error::DatabaseError(format!("save_liquidity_distribution: {e}")))?;
+
+        if !pool.is_empty() {
+            tx.execute(
+                "DELETE FROM liquidity_distributions
+                 WHERE token0_address = ?1 AND token1_address = ?2 AND dex = ?3 AND chain_id = ?4
+                   AND pool_address = ''",
+                params![token0, token1, dex, chain_id],
+            )
+            .map_err(|e| Error::DatabaseError(format!("save_liquidity_distribution: {e}")))?;
+        }
+
+        tx.execute(
+            "INSERT INTO liquidity_distribution_history
+             (token0_address, token1_address, dex, chain_id, pool_address, data, timestamp,
+              block_number, block_hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                token0,
+                token1,
+                dex,
+                chain_id,
+                pool,
+                data,
+                timestamp,
+                block.map(|b| b.number),
+                block.map(|b| b.hash.to_string())
+            ],
+        )
+        .map_err(|e| Error::DatabaseError(format!("save_liquidity_distribution history: {e}")))?;
+
+        tx.commit()
+            .map_err(|e| Error::DatabaseError(format!("commit: {e}")))?;
+        Ok(())
+    }
+
+    /// Runs `f` on a reader connection inside the blocking thread pool.
+    async fn read<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = if self.readers.is_empty() {
+            self.writer.clone()
+        } else {
+            let idx = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+            self.readers[idx].clone()
+        };
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            f(&conn)
+        })
+        .await
+        .map_err(|e| Error::DatabaseError(format!("blocking read task: {e}")))?
+    }
+
+    /// Runs `f` on the writer connection inside the blocking thread pool.
+    async fn write<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = self.writer.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap();
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| Error::DatabaseError(format!("blocking write task: {e}")))?
+    }
+
+    /// Runs the pools/tokens JOIN with the given filter and builds `Pool`s from the rows.
+    ///
+    /// Pools whose token metadata is missing are skipped.
+    fn query_joined_pools(
+        conn: &Connection,
+        filter: &str,
+        params: &[&dyn rusqlite::ToSql],
+        context: &str,
+    ) -> Result<Vec<Pool>> {
+        let sql = format!(
+            "SELECT p.address, p.chain_id, p.dex, p.token0_address, p.token1_address, p.fee,
+                    p.creation_block, t0.symbol, t0.name, t0.decimals,
+                    t1.symbol, t1.name, t1.decimals
+             FROM pools p
+             LEFT JOIN tokens t0 ON p.token0_address = t0.address AND p.chain_id = t0.chain_id
+             LEFT JOIN tokens t1 ON p.token1_address = t1.address AND p.chain_id = t1.chain_id
+             {filter}"
+        );
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| Error::DatabaseError(format!("prepare {context}: {e}")))?;
+        let mut rows = stmt
+            .query(params)
+            .map_err(|e| Error::DatabaseError(format!("query {context}: {e}")))?;
+
+        let mut pools = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| Error::DatabaseError(format!("row {context}: {e}")))?
+        {
+            let chain_id: u64 = row.get(1)?;
+            let token = |address: String, offset: usize| -> Result<Option<Token>> {
+                let symbol: Option<String> = row.get(offset)?;
+                let name: Option<String> = row.get(offset + 1)?;
+                let decimals: Option<u8> = row.get(offset + 2)?;
+                let (Some(symbol), Some(name), Some(decimals)) = (symbol, name, decimals) else {
+                    return Ok(None);
+                };
+                Ok(Some(Token {
+                    address: Address::from_str(&address)
+                        .map_err(|e| Error::DatabaseError(format!("parse token address: {e}")))?,
+                    symbol,
+                    name,
+                    decimals,
+                    chain_id,
+                }))
+            };
+            let (Some(token0), Some(token1)) = (token(row.get(3)?, 7)?, token(row.get(4)?, 10)?)
+            else {
+                continue;
+            };
+
+            let address: String = row.get(0)?;
+            let fee: u32 = row.get(5)?;
+            let default_dt = DateTime::<Utc>::UNIX_EPOCH;
+            pools.push(Pool {
+                address: Address::from_str(&address)
+                    .map_err(|e| Error::DatabaseError(format!("parse pool address: {e}")))?,
+                dex: row.get(2)?,
+                chain_id,
+                tokens: vec![token0, token1],
+                creation_block: row.get(6)?,
+                creation_timestamp: default_dt,
+                last_updated_block: 0,
+                last_updated_timestamp: default_dt,
+                fee: fee.into(),
+            });
+        }
+
+        Ok(pools)
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for SqliteStorage {
+    async fn save_token(&self, token: &Token) -> Result<()> {
+        let token = token.clone();
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO tokens
+                 (address, chain_id, name, symbol, decimals)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    token.address.to_string(),
+                    token.chain_id,
+                    token.name,
+                    token.symbol,
+                    token.decimals as u32
+                ],
+            )
+            .map_err(|e| Error::DatabaseError(format!("save_token: {e}")))?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Retrieves a token by its address and chain ID.
+    ///
+    /// Returns `Ok(None)` if the token is not stored for that chain.
+    async fn get_token(&self, address: Address, chain_id: u64) -> Result<Option<Token>> {
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT address, chain_id, name, symbol, decimals
+                     FROM tokens WHERE address = ?1 AND chain_id = ?2",
+                )
+                .map_err(|e| Error::DatabaseError(format!("prepare get_token: {e}")))?;
+
+            let token_opt = match stmt.query_row(params![address.to_string(), chain_id], |row| {
+                let addr: String = row.get(0)?;
+                Ok(Token {
+                    address: Address::from_str(&addr)
+                        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?,
+                    chain_id: row.get(1)?,
+                    name: row.get(2)?,
+                    symbol: row.get(3)?,
+                    decimals: row.get(4)?,
+                })
+            }) {
+                Ok(token) => Some(token),
+                Err(rusqlite::Error::QueryReturnedNoRows) => None,
+                Err(e) => return Err(Error::DatabaseError(format!("query_row get_token: {e}"))),
+            };
+            Ok(token_opt)
+        })
+        .await
+    }
+
+    async fn list_tokens(&self, chain_id: Option<u64>) -> Result<Vec<Token>> {
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT address, chain_id, name, symbol, decimals
+                     FROM tokens WHERE ?1 IS NULL OR chain_id = ?1
+                     ORDER BY chain_id, address",
+                )
+                .map_err(|e| Error::DatabaseError(format!("prepare list_tokens: {e}")))?;
+            let tokens = stmt
+                .query_map(params![chain_id], |row| {
+                    let addr: String = row.get(0)?;
+                    Ok(Token {
+                        address: Address::from_str(&addr)
+                            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?,
+                        chain_id: row.get(1)?,
+                        name: row.get(2)?,
+                        symbol: row.get(3)?,
+                        decimals: row.get(4)?,
+                    })
+                })
+                .map_err(|e| Error::DatabaseError(format!("query list_tokens: {e}")))?
+                .collect::<rusqlite::Result<Vec<Token>>>()?;
+            Ok(tokens)
+        })
+        .await
+    }
+
+    /// Saves a pool and its associated tokens to the SQLite database within a transaction.
+    ///
+    /// Inserts or updates both tokens and the pool record atomically. If any operation fails, the transaction is rolled back.
+    async fn save_pool(&self, pool: &Pool) -> Result<()> {
+        let pool = pool.clone();
+        self.write(move |conn| {
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .map_err(|e| Error::DatabaseError(format!("tx start: {e}")))?;
+
+            for t in &pool.tokens {
+                tx.execute(
+                    "INSERT OR REPLACE INTO tokens
+                     (address, chain_id, name, symbol, decimals)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        t.address.to_string(),
+                        t.chain_id,
+                        t.name,
+                        t.symbol,
+                        t.decimals as u32
+                    ],
+                )
+                .map_err(|e| Error::DatabaseError(format!("save_token: {e}")))?;
+            }
+
+            // A pool saved again without its creation block keeps the one already known.
+            tx.execute(
+                "INSERT INTO pools
+                 (address, chain_id, dex, token0_address, token1_address, fee, creation_block)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT (address, chain_id) DO UPDATE
+                 SET dex = excluded.dex,
+                     token0_address = excluded.token0_address,
+                     token1_address = excluded.token1_address,
+                     fee = excluded.fee,
+                     creation_block = CASE excluded.creation_block
+                         WHEN 0 THEN pools.creation_block
+                         ELSE excluded.creation_block
+                     END",
+                params![
+                    pool.address.to_string(),
+                    pool.chain_id,
+                    &pool.dex,
+                    pool.tokens[0].address.to_string(),
+                    pool.tokens[1].address.to_string(),
+                    pool.fee as u32,
+                    pool.creation_block
+                ],
+            )
+            .map_err(|e| Error::DatabaseError(format!("save_pool: {e}")))?;
+
+            tx.commit()
+                .map_err(|e| Error::DatabaseError(format!("commit: {e}")))?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Retrieves a pool by its address and chain.
+    ///
+    /// Returns `Ok(None)` if the pool, or the metadata of one of its tokens, is not stored.
+    async fn get_pool(&self, address: Address, chain_id: u64) -> Result<Option<Pool>> {
+        self.read(move |conn| {
+            let pools = Self::query_joined_pools(
+                conn,
+                "WHERE p.address = ?1 AND p.chain_id = ?2",
+                params![address.to_string(), chain_id],
+                "get_pool",
+            )?;
+            Ok(pools.into_iter().next())
+        })
+        .await
+    }
+
+    /// Retrieves all pools for the specified DEX and chain ID.
+    async fn get_pools_by_dex(&self, dex: &str, chain_id: u64) -> Result<Vec<Pool>> {
+        let dex = dex.to_string();
+        self.read(move |conn| {
+            Self::query_joined_pools(
+                conn,
+                "WHERE p.dex = ?1 AND p.chain_id = ?2 ORDER BY p.rowid",
+                params![dex, chain_id],
+                "get_pools_by_dex",
+            )
+        })
+        .await
+    }
+
+    async fn list_pools(&self, chain_id: Option<u64>, dex: Option<&str>) -> Result<Vec<Pool>> {
+        let dex = dex.map(str::to_string);
+        self.read(move |conn| {
+            Self::query_joined_pools(
+                conn,
+                "WHERE (?1 IS NULL OR p.chain_id = ?1) AND (?2 IS NULL OR p.dex = ?2)
+                 ORDER BY p.rowid",
+                params![chain_id, dex],
+                "list_pools",
+            )
+        })
+        .await
+    }
+
+    async fn get_pools_by_dex_paginated(
+        &self,
+        dex: &str,
+        chain_id: u64,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Pool>> {
+        let dex = dex.to_string();
+        self.read(move |conn| {
+            Self::query_joined_pools(
+                conn,
+                "WHERE p.dex = ?1 AND p.chain_id = ?2
+                 ORDER BY p.rowid
+                 LIMIT ?3 OFFSET ?4",
+                params![dex, chain_id, limit, offset],
+                "get_pools_by_dex_paginated",
+            )
+        })
+        .await
+    }
+
+    async fn get_all_pools_paginated(
+        &self,
+        chain_id: u64,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Pool>> {
+        self.read(move |conn| {
+            Self::query_joined_pools(
+                conn,
+                "WHERE p.chain_id = ?1 AND p.dex IN ('uniswap_v3', 'uniswap_v2', 'sushiswap')
+                 ORDER BY p.rowid DESC
+                 LIMIT ?2 OFFSET ?3",
+                params![chain_id, limit, offset],
+                "get_all_pools_paginated",
+            )
+        })
+        .await
+    }
+
+    /// Retrieves every pool trading the given pair, in either token order.
+    ///
+    /// A pair usually lives in several pools (one per V3 fee tier plus V2 forks), so all of them
+    /// are returned, ordered by DEX and fee. `dex` and `fee` narrow the result when set.
+    async fn get_pools_by_token(
+        &self,
+        token0: Address,
+        token1: Address,
+        chain_id: u64,
+        dex: Option<&str>,
+        fee: Option<u64>,
+    ) -> Result<Vec<Pool>> {
+        let dex = dex.map(str::to_string);
+        self.read(move |conn| {
+            Self::query_joined_pools(
+                conn,
+                "WHERE p.chain_id = ?1
+                   AND ((p.token0_address = ?2 AND p.token1_address = ?3)
+                     OR (p.token0_address = ?3 AND p.token1_address = ?2))
+                   AND (?4 IS NULL OR p.dex = ?4)
+                   AND (?5 IS NULL OR p.fee = ?5)
+                 ORDER BY p.dex, p.fee, p.rowid",
+                params![chain_id, token0.to_string(), token1.to_string(), dex, fee],
+                "get_pools_by_token",
+            )
+        })
+        .await
+    }
+
+    /// Retrieves every pool that has `token` on either side, optionally restricted to one DEX.
+    async fn get_pools_containing_token(
+        &self,
+        token: Address,
+        chain_id: u64,
+        dex: Option<&str>,
+    ) -> Result<Vec<Pool>> {
+        let dex = dex.map(str::to_string);
+        self.read(move |conn| {
+            Self::query_joined_pools(
+                conn,
+                "WHERE p.chain_id = ?1
+                   AND (p.token0_address = ?2 OR p.token1_address = ?2)
+                   AND (?3 IS NULL OR p.dex = ?3)
+                 ORDER BY p.dex, p.fee, p.rowid",
+                params![chain_id, token.to_string(), dex],
+                "get_pools_containing_token",
+            )
+        })
+        .await
+    }
+
+    /// Saves a liquidity distribution record to the storage.
+    ///
+    /// Every record is appended to the snapshot history. The latest-snapshot table ignores a
+    /// record older than the one already stored for the same pool, so
+    /// `get_liquidity_distributions` always returns each pool's latest snapshot.
+    async fn save_liquidity_distribution(
+        &self,
+        distribution: &LiquidityDistribution,
+    ) -> Result<()> {
+        let distribution = distribution.clone();
+        self.write(move |conn| Self::insert_distribution(conn, &distribution, None))
+            .await
+    }
+
+    /// Retrieves the latest liquidity distribution of each pool of a token pair, DEX, and chain ID.
+    async fn get_liquidity_distributions(
+        &self,
+        token0: Address,
+        token1: Address,
+        dex: &str,
+        chain_id: u64,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        let dex = dex.to_string();
+        let rows = self
+            .read(move |conn| {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT data
+                         FROM liquidity_distributions
+                         WHERE token0_address = ?
+                           AND token1_address = ?
+                           AND dex            = ?
+                           AND chain_id       = ?",
+                    )
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("prepare get_liquidity_distributions: {e}"))
+                    })?;
+                let rows = stmt
+                    .query_map(
+                        params![token0.to_string(), token1.to_string(), dex, chain_id],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("query get_liquidity_distributions: {e}"))
+                    })?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        let mut distributions = rows
+            .iter()
+            .map(|json_str| {
+                serde_json::from_str(json_str)
+                    .map_err(|e| Error::DatabaseError(format!("JSON parse error: {e}")))
+            })
+            .collect::<Result<Vec<LiquidityDistribution>>>()?;
+        // Addresses are stored checksummed, so order by their bytes rather than the text.
+        distributions.sort_by_key(|d| d.pool);
+        Ok(distributions)
+    }
+
+    async fn get_liquidity_history(
+        &self,
+        token0: Address,
+        token1: Address,
+        dex: &str,
+        chain_id: u64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        let dex = dex.to_string();
+        let rows = self
+            .read(move |conn| {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT data
+                         FROM liquidity_distribution_history
+                         WHERE token0_address = ?1
+                           AND token1_address = ?2
+                           AND dex            = ?3
+                           AND chain_id       = ?4
+                           AND timestamp BETWEEN ?5 AND ?6
+                         ORDER BY timestamp, id",
+                    )
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("prepare get_liquidity_history: {e}"))
+                    })?;
+                let rows = stmt
+                    .query_map(
+                        params![
+                            token0.to_string(),
+                            token1.to_string(),
+                            dex,
+                            chain_id,
+                            from.timestamp(),
+                            to.timestamp()
+                        ],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .map_err(|e| Error::DatabaseError(format!("query get_liquidity_history: {e}")))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        rows.iter()
+            .map(|json_str| {
+                serde_json::from_str(json_str)
+                    .map_err(|e| Error::DatabaseError(format!("JSON parse error: {e}")))
+            })
+            .collect()
+    }
+
+    async fn list_liquidity_distributions(
+        &self,
+        chain_id: Option<u64>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        let rows = self
+            .read(move |conn| {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT data
+                         FROM liquidity_distributions
+                         WHERE (?1 IS NULL OR chain_id = ?1)",
+                    )
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("prepare list_liquidity_distributions: {e}"))
+                    })?;
+                let rows = stmt
+                    .query_map(params![chain_id], |row| row.get::<_, String>(0))
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("query list_liquidity_distributions: {e}"))
+                    })?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        rows.iter()
+            .map(|json_str| {
+                serde_json::from_str(json_str)
+                    .map_err(|e| Error::DatabaseError(format!("JSON parse error: {e}")))
+            })
+            .collect()
+    }
+
+    async fn list_liquidity_history(
+        &self,
+        chain_id: Option<u64>,
+        dex: Option<&str>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<LiquidityDistribution>> {
+        let dex = dex.map(str::to_string);
+        let rows = self
+            .read(move |conn| {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT data
+                         FROM liquidity_distribution_history
+                         WHERE (?1 IS NULL OR chain_id = ?1)
+                           AND (?2 IS NULL OR dex = ?2)
+                           AND timestamp BETWEEN ?3 AND ?4
+                         ORDER BY timestamp, id",
+                    )
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("prepare list_liquidity_history: {e}"))
+                    })?;
+                let rows = stmt
+                    .query_map(
+                        params![chain_id, dex, from.timestamp(), to.timestamp()],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("query list_liquidity_history: {e}"))
+                    })?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        rows.iter()
+            .map(|json_str| {
+                serde_json::from_str(json_str)
+                    .map_err(|e| Error::DatabaseError(format!("JSON parse error: {e}")))
+            })
+            .collect()
+    }
+
+    /// Applies the retention policy to the snapshot history in a single transaction.
+    async fn compact_snapshots(
+        &self,
+        policy: &RetentionConfig,
+        now: DateTime<Utc>,
+    ) -> Result<CompactionStats> {
+        let policy = policy.clone();
+        self.write(move |conn| {
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .map_err(|e| Error::DatabaseError(format!("tx start: {e}")))?;
+
+            let mut stmt = tx
+                .prepare(
+                    "SELECT id, token0_address, token1_address, dex, chain_id, pool_address, timestamp
+                     FROM liquidity_distribution_history",
+                )
+                .map_err(|e| Error::DatabaseError(format!("prepare compact_snapshots: {e}")))?;
+            let snapshots = stmt
+                .query_map([], |row| {
+                    let series: (String, String, String, u64, String) =
+                        (row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?);
+                    Ok((row.get::<_, i64>(0)?, series, row.get::<_, i64>(6)?))
+                })
+                .map_err(|e| Error::DatabaseError(format!("query compact_snapshots: {e}")))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            drop(stmt);
+
+            let expired = retention::expired_snapshots(&snapshots, &policy, now.timestamp());
+            {
+                let mut delete = tx
+                    .prepare("DELETE FROM liquidity_distribution_history WHERE id = ?1")
+                    .map_err(|e| Error::DatabaseError(format!("prepare delete snapshot: {e}")))?;
+                for id in &expired {
+                    delete
+                        .execute(params![id])
+                        .map_err(|e| Error::DatabaseError(format!("delete snapshot: {e}")))?;
+                }
+            }
+
+            tx.commit()
+                .map_err(|e| Error::DatabaseError(format!("commit: {e}")))?;
+            Ok(CompactionStats {
+                examined: snapshots.len() as u64,
+                deleted: expired.len() as u64,
+            })
+        })
+        .await
+    }
+
+    async fn get_snapshot_stats(&self) -> Result<SnapshotStats> {
+        self.read(|conn| {
+            let (count, finalized, oldest, newest): (u64, u64, Option<i64>, Option<i64>) = conn
+                .query_row(
+                    "SELECT COUNT(*), COALESCE(SUM(finalized), 0), MIN(timestamp), MAX(timestamp)
+                     FROM liquidity_distribution_history",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .map_err(|e| Error::DatabaseError(format!("get_snapshot_stats: {e}")))?;
+            Ok(SnapshotStats {
+                count,
+                finalized,
+                oldest: oldest.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+                newest: newest.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+            })
+        })
+        .await
+    }
+
+    async fn get_checkpoint(&self, chain_id: u64, dex: &str, stream: &str) -> Result<Option<u64>> {
+        let dex = dex.to_string();
+        let stream = stream.to_string();
+        self.read(move |conn| {
+            match conn.query_row(
+                "SELECT block_number FROM sync_checkpoints
+                 WHERE chain_id = ?1 AND dex = ?2 AND stream = ?3",
+                params![chain_id, dex, stream],
+                |row| row.get::<_, u64>(0),
+            ) {
+                Ok(block) => Ok(Some(block)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(Error::DatabaseError(format!("get_checkpoint: {e}"))),
+            }
+        })
+        .await
+    }
+
+    async fn save_checkpoint(&self, chain_id: u64, dex: &str, stream: &str, block: u64) -> Result<()> {
+        let dex = dex.to_string();
+        let stream = stream.to_string();
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO sync_checkpoints
+                 (chain_id, dex, stream, block_number, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![chain_id, dex, stream, block, Utc::now().timestamp()],
+            )
+            .map_err(|e| Error::DatabaseError(format!("save_checkpoint: {e}")))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn save_liquidity_distribution_at(
+        &self,
+        distribution: &LiquidityDistribution,
+        block: &BlockRef,
+    ) -> Result<()> {
+        let distribution = distribution.clone();
+        let block = *block;
+        self.write(move |conn| Self::insert_distribution(conn, &distribution, Some(&block)))
+            .await
+    }
+
+    async fn save_block(&self, chain_id: u64, block: &BlockRef) -> Result<()> {
+        let block = *block;
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO chain_blocks (chain_id, number, hash, parent_hash)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    chain_id,
+                    block.number,
+                    block.hash.to_string(),
+                    block.parent_hash.to_string()
+                ],
+            )
+            .map_err(|e| Error::DatabaseError(format!("save_block: {e}")))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_recent_blocks(&self, chain_id: u64, limit: usize) -> Result<Vec<BlockRef>> {
+        let rows = self
+            .read(move |conn| {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT number, hash, parent_hash FROM chain_blocks
+                         WHERE chain_id = ?1
+                         ORDER BY number DESC
+                         LIMIT ?2",
+                    )
+                    .map_err(|e| Error::DatabaseError(format!("prepare get_recent_blocks: {e}")))?;
+                let rows = stmt
+                    .query_map(params![chain_id, limit as i64], |row| {
+                        Ok((
+                            row.get::<_, u64>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    })
+                    .map_err(|e| Error::DatabaseError(format!("query get_recent_blocks: {e}")))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        rows.into_iter()
+            .map(|(number, hash, parent_hash)| {
+                let parse = |h: &str| {
+                    B256::from_str(h)
+                        .map_err(|e| Error::DatabaseError(format!("invalid block hash {h}: {e}")))
+                };
+                Ok(BlockRef {
+                    number,
+                    hash: parse(&hash)?,
+                    parent_hash: parse(&parent_hash)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn rollback_to(&self, chain_id: u64, fork_block: u64) -> Result<RollbackStats> {
+        self.write(move |conn| {
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .map_err(|e| Error::DatabaseError(format!("tx start: {e}")))?;
+
+            let mut stmt = tx
+                .prepare(
+                    "SELECT DISTINCT token0_address, token1_address, dex, pool_address
+                     FROM liquidity_distribution_history
+                     WHERE chain_id = ?1 AND block_number > ?2",
+                )
+                .map_err(|e| Error::DatabaseError(format!("prepare rollback_to: {e}")))?;
+            let affected = stmt
+                .query_map(params![chain_id, fork_block], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })
+                .map_err(|e| Error::DatabaseError(format!("query rollback_to: {e}")))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            drop(stmt);
+
+            let snapshots = tx
+                .execute(
+                    "DELETE FROM liquidity_distribution_history
+                     WHERE chain_id = ?1 AND block_number > ?2",
+                    params![chain_id, fork_block],
+                )
+                .map_err(|e| Error::DatabaseError(format!("rollback snapshots: {e}")))?;
+
+            for (token0, token1, dex, pool) in &affected {
+                let newest: Option<(String, i64)> = tx
+                    .query_row(
+                        "SELECT data, timestamp FROM liquidity_distribution_history
+                         WHERE token0_address = ?1 AND token1_address = ?2
+                           AND dex = ?3 AND chain_id = ?4 AND pool_address = ?5
+                         ORDER BY timestamp DESC, id DESC
+                         LIMIT 1",
+                        params![token0, token1, dex, chain_id, pool],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()
+                    .map_err(|e| Error::DatabaseError(format!("rollback latest lookup: {e}")))?;
+                match newest {
+                    Some((data, timestamp)) => tx.execute(
+                        "INSERT OR REPLACE INTO liquidity_distributions
+                         (token0_address, token1_address, dex, chain_id, pool_address, data, timestamp)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![token0, token1, dex, chain_id, pool, data, timestamp],
+                    ),
+                    None => tx.execute(
+                        "DELETE FROM liquidity_distributions
+                         WHERE token0_address = ?1 AND token1_address = ?2
+                           AND dex = ?3 AND chain_id = ?4 AND pool_address = ?5",
+                        params![token0, token1, dex, chain_id, pool],
+                    ),
+                }
+                .map_err(|e| Error::DatabaseError(format!("rollback latest: {e}")))?;
+            }
+
+            let blocks = tx
+                .execute(
+                    "DELETE FROM chain_blocks WHERE chain_id = ?1 AND number > ?2",
+                    params![chain_id, fork_block],
+                )
+                .map_err(|e| Error::DatabaseError(format!("rollback blocks: {e}")))?;
+            tx.execute(
+                "UPDATE sync_checkpoints SET block_number = ?2, updated_at = ?3
+                 WHERE chain_id = ?1 AND block_number > ?2",
+                params![chain_id, fork_block, Utc::now().timestamp()],
+            )
+            .map_err(|e| Error::DatabaseError(format!("rollback checkpoints: {e}")))?;
+
+            tx.commit()
+                .map_err(|e| Error::DatabaseError(format!("commit: {e}")))?;
+            Ok(RollbackStats {
+                snapshots: snapshots as u64,
+                blocks: blocks as u64,
+            })
+        })
+        .await
+    }
+
+    async fn finalize_blocks(&self, chain_id: u64, block: u64) -> Result<u64> {
+        self.write(move |conn| {
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .map_err(|e| Error::DatabaseError(format!("tx start: {e}")))?;
+            let finalized = tx
+                .execute(
+                    "UPDATE liquidity_distribution_history SET finalized = 1
+                     WHERE chain_id = ?1 AND block_number <= ?2 AND finalized = 0",
+                    params![chain_id, block],
+                )
+                .map_err(|e| Error::DatabaseError(format!("finalize snapshots: {e}")))?;
+            tx.execute(
+                "DELETE FROM chain_blocks WHERE chain_id = ?1 AND number < ?2",
+                params![chain_id, block],
+            )
+            .map_err(|e| Error::DatabaseError(format!("prune blocks: {e}")))?;
+            tx.commit()
+                .map_err(|e| Error::DatabaseError(format!("commit: {e}")))?;
+            Ok(finalized as u64)
+        })
+        .await
+    }
+    async fn save_indexer_run(&self, run: &IndexerRun) -> Result<()> {
+        let run = run.clone();
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO indexer_runs (started_at, duration_ms, pools, snapshotted, failed)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    run.started_at.timestamp(),
+                    run.duration_ms,
+                    run.pools,
+                    run.snapshotted,
+                    run.failed
+                ],
+            )
+            .map_err(|e| Error::DatabaseError(format!("save_indexer_run: {e}")))?;
+            conn.execute(
+                "DELETE FROM indexer_runs WHERE id <= last_insert_rowid() - ?1",
+                params![MAX_INDEXER_RUNS as i64],
+            )
+            .map_err(|e| Error::DatabaseError(format!("prune indexer_runs: {e}")))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_indexer_runs(&self, limit: usize) -> Result<Vec<IndexerRun>> {
+        self.read(move |conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT started_at, duration_ms, pools, snapshotted, failed
+                     FROM indexer_runs
+                     ORDER BY id DESC
+                     LIMIT ?1",
+                )
+                .map_err(|e| Error::DatabaseError(format!("prepare get_indexer_runs: {e}")))?;
+            let runs = stmt
+                .query_map(params![limit as i64], |row| {
+                    Ok(IndexerRun {
+                        started_at: DateTime::from_timestamp(row.get(0)?, 0).unwrap_or_default(),
+                        duration_ms: row.get(1)?,
+                        pools: row.get(2)?,
+                        snapshotted: row.get(3)?,
+                        failed: row.get(4)?,
+                    })
+                })
+                .map_err(|e| Error::DatabaseError(format!("query get_indexer_runs: {e}")))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(runs)
+        })
+        .await
+    }
+
+    async fn record_pool_attempt(&self, attempt: &PoolAttempt) -> Result<()> {
+        let attempt = attempt.clone();
+        self.write(move |conn| {
+            let pool = attempt.pool.to_string();
+            let at = attempt.at.timestamp();
+            match &attempt.error {
+                None => conn.execute(
+                    "INSERT INTO pool_status
+                     (chain_id, pool_address, dex, last_attempt, last_success, error_count, duration_ms)
+                     VALUES (?1, ?2, ?3, ?4, ?4, 0, ?5)
+                     ON CONFLICT (chain_id, pool_address) DO UPDATE
+                     SET dex = excluded.dex, last_attempt = excluded.last_attempt,
+                         last_success = excluded.last_success, error_count = 0,
+                         duration_ms = excluded.duration_ms",
+                    params![attempt.chain_id, pool, attempt.dex, at, attempt.duration_ms],
+                ),
+                Some(error) => conn.execute(
+                    "INSERT INTO pool_status
+                     (chain_id, pool_address, dex, last_attempt, last_error, last_error_at,
+                      error_count, duration_ms)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?4, 1, ?6)
+                     ON CONFLICT (chain_id, pool_address) DO UPDATE
+                     SET dex = excluded.dex, last_attempt = excluded.last_attempt,
+                         last_error = excluded.last_error, last_error_at = excluded.last_error_at,
+                         error_count = pool_status.error_count + 1,
+                         duration_ms = excluded.duration_ms",
+                    params![attempt.chain_id, pool, attempt.dex, at, error, attempt.duration_ms],
+                ),
+            }
+            .map_err(|e| Error::DatabaseError(format!("record_pool_attempt: {e}")))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_pool_status(&self, chain_id: Option<u64>, dex: Option<&str>) -> Result<Vec<PoolStatus>> {
+        let dex = dex.map(str::to_string);
+        let rows = self
+            .read(move |conn| {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT chain_id, dex, pool_address, last_attempt, last_success,
+                                last_error, last_error_at, error_count, duration_ms
+                         FROM pool_status
+                         WHERE (?1 IS NULL OR chain_id = ?1) AND (?2 IS NULL OR dex = ?2)
+                         ORDER BY chain_id, dex, LOWER(pool_address)",
+                    )
+                    .map_err(|e| Error::DatabaseError(format!("prepare list_pool_status: {e}")))?;
+                let rows = stmt
+                    .query_map(params![chain_id, dex], |row| {
+                        Ok((
+                            row.get::<_, u64>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                            row.get::<_, i64>(3)?,
+                            row.get::<_, Option<i64>>(4)?,
+                            row.get::<_, Option<String>>(5)?,
+                            row.get::<_, Option<i64>>(6)?,
+                            row.get::<_, u64>(7)?,
+                            row.get::<_, u64>(8)?,
+                        ))
+                    })
+                    .map_err(|e| Error::DatabaseError(format!("query list_pool_status: {e}")))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        rows.into_iter()
+            .map(
+                |(chain_id, dex, pool, last_attempt, last_success, last_error, last_error_at, error_count, duration_ms)| {
+                    Ok(PoolStatus {
+                        chain_id,
+                        dex,
+                        pool: Address::from_str(&pool)
+                            .map_err(|_| Error::InvalidAddress(pool.clone()))?,
+                        last_attempt: DateTime::from_timestamp(last_attempt, 0).unwrap_or_default(),
+                        last_success: last_success.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+                        last_error,
+                        last_error_at: last_error_at.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+                        error_count,
+                        duration_ms,
+                    })
+                },
+            )
+            .collect()
+    }
+    async fn flush(&self) -> Result<()> {
+        self.write(|conn| {
+            // Moves the WAL into the main database file, so it is complete on its own
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+                .map_err(|e| Error::DatabaseError(format!("flush: {e}")))
+        })
+        .await
+    }
+}
@@ -1,7 +1,7 @@
 use alloy_primitives::Address;
 use crate::{Error, Result};
 use std::str::FromStr;
-use crate::models::{LiquidityDistribution, PriceLiquidity, Token};
+use crate::models::{LiquidityDistribution, Token};
 use chrono::Utc;
 
 /// Parse a string into an Address.
@@ -30,18 +30,6 @@ pub fn calculate_price_impact(reserve_in: f64, reserve_out: f64, amount_in: f64)
     ((price_before - price_after) / price_before) * 100.0
 }
 
-/// Calculate support/resistance strength from liquidity concentration
-pub fn calculate_support_resistance_strength(
-    _price_level: f64,
-    liquidity: f64,
-    total_liquidity: f64,
-    price_range: f64,
-) -> f64 {
-    // Simple calculation: (liquidity / total_liquidity) * (1.0 / price_range)
-    // Higher values indicate stronger support/resistance
-    (liquidity / total_liquidity) * (1.0 / price_range) * 100.0
-}
-
 /// Format large numbers with K, M, B, T suffixes
 pub fn format_large_number(num: f64) -> String {
     if num >= 1_000_000_000_000.0 {
@@ -61,6 +49,8 @@ pub fn format_large_number(num: f64) -> String {
 ///
 /// This function assumes that both distributions are for the same token pair and chain.
 /// It returns `None` if the distributions are inconsistent.
+/// Books of one asset on different chains are merged with
+/// [`merge_chains`](crate::core::cross_chain::merge_chains) instead.
 ///
 /// # Arguments
 ///
@@ -107,40 +97,37 @@ pub fn merge_two_liquidity_distributions(
         chain_id: dist1.chain_id,
         price_levels: all_price_levels,
         timestamp: Utc::now(), // Set new timestamp
+        pool: None,
     })
 }
 
-/// Buckets price levels into uniform intervals
-pub fn bucket_price_levels(price_levels: Vec<PriceLiquidity>, current_price: f64, bucket_size: f64) -> Vec<PriceLiquidity> {
-    use std::collections::HashMap;
-    
-    let mut buckets: HashMap<i32, PriceLiquidity> = HashMap::new();
-    
-    for level in price_levels {
-        let mid_price = (level.lower_price + level.upper_price) / 2.0;
-        let bucket_index = ((mid_price / current_price - 1.0) / bucket_size).round() as i32;
-        
-        let bucket_center = current_price * (1.0 + bucket_index as f64 * bucket_size);
-        let bucket_lower = bucket_center * (1.0 - bucket_size / 2.0);
-        let bucket_upper = bucket_center * (1.0 + bucket_size / 2.0);
-        
-        buckets.entry(bucket_index)
-            .and_modify(|existing| {
-                existing.token0_liquidity += level.token0_liquidity;
-                existing.token1_liquidity += level.token1_liquidity;
-            })
-            .or_insert(PriceLiquidity {
-                side: level.side,
-                lower_price: bucket_lower,
-                upper_price: bucket_upper,
-                token0_liquidity: level.token0_liquidity,
-                token1_liquidity: level.token1_liquidity,
-                timestamp: level.timestamp,
-            });
-    }
-    
-    buckets.into_values().collect()
-}
+/// Resolves once the process receives SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
 
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-       
\ No newline at end of file
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("Shutdown signal received");
+}
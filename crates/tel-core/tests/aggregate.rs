@@ -0,0 +1,123 @@
+//! Token-centric aggregation of every pool containing a token into one USD book.
+
+use alloy_primitives::Address;
+use chrono::Utc;
+use std::sync::Arc;
+use tel_core::config::{Numeraire, PricingConfig};
+use tel_core::core::bucketing::Grid;
+use tel_core::core::pricing::PriceOracle;
+use tel_core::models::{LiquidityDistribution, Pool, PriceLiquidity, Side};
+use tel_core::storage::{aggregate_token_liquidity, MemoryStorage, Storage};
+
+mod common;
+use common::{level, token};
+
+const USDC: u8 = 0xa;
+const WETH: u8 = 0xb;
+const TKN: u8 = 0xc;
+
+async fn add_pool(
+    storage: &Arc<dyn Storage>,
+    address: u8,
+    dex: &str,
+    fee: u64,
+    (token0, token1): (u8, u8),
+    current_price: f64,
+    price_levels: Vec<PriceLiquidity>,
+) {
+    let pool = Pool {
+        address: Address::repeat_byte(address),
+        dex: dex.to_string(),
+        chain_id: 1,
+        tokens: vec![token(token0), token(token1)],
+        creation_block: 0,
+        creation_timestamp: Utc::now(),
+        last_updated_block: 0,
+        last_updated_timestamp: Utc::now(),
+        fee,
+    };
+    storage.save_pool(&pool).await.unwrap();
+    let distribution = LiquidityDistribution {
+        token0: token(token0),
+        token1: token(token1),
+        current_price,
+        dex: dex.to_string(),
+        chain_id: 1,
+        price_levels,
+        timestamp: Utc::now(),
+        pool: Some(pool.address),
+    };
+    storage.save_liquidity_distribution(&distribution).await.unwrap();
+}
+
+#[tokio::test]
+async fn every_pool_of_the_token_is_merged_in_usd() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    add_pool(
+        &storage,
+        1,
+        "uniswap_v3",
+        500,
+        (WETH, USDC),
+        2000.0,
+        vec![level(Side::Buy, 1990.0, 2000.0, 0.0, 1e6)],
+    )
+    .await;
+    // TKN is token1 here: 100 TKN per WETH, so 20 USD per TKN.
+    add_pool(
+        &storage,
+        2,
+        "uniswap_v2",
+        3000,
+        (WETH, TKN),
+        100.0,
+        vec![
+            level(Side::Buy, 99.0, 100.0, 0.0, 500.0),
+            level(Side::Sell, 100.0, 101.0, 2.0, 0.0),
+        ],
+    )
+    .await;
+    let direct = vec![
+        level(Side::Buy, 19.8, 20.0, 0.0, 1000.0),
+        level(Side::Sell, 20.0, 20.2, 100.0, 0.0),
+    ];
+    add_pool(&storage, 3, "uniswap_v3", 3000, (TKN, USDC), 20.0, direct.clone()).await;
+    // Another fee tier of the same pair has its own snapshot and counts too.
+    add_pool(&storage, 4, "uniswap_v3", 10000, (TKN, USDC), 20.0, direct.clone()).await;
+    add_pool(&storage, 5, "sushiswap", 3000, (TKN, USDC), 20.0, direct).await;
+
+    let prices = PriceOracle::new(
+        storage.clone(),
+        PricingConfig {
+            numeraires: vec![Numeraire {
+                chain_id: 1,
+                token: Address::repeat_byte(USDC).to_string(),
+            }],
+            min_liquidity: 1000.0,
+            ..PricingConfig::default()
+        },
+    );
+    let dexes = ["uniswap_v2".to_string(), "uniswap_v3".to_string()];
+    let grid = Grid::Percent(0.01);
+    let book = aggregate_token_liquidity(storage, &prices, token(TKN).address, 1, &dexes, grid, None)
+        .await
+        .unwrap();
+
+    assert_eq!(book.token0.address, token(TKN).address);
+    assert_eq!(book.token1.address, token(USDC).address);
+    assert_eq!(book.dex, "aggregated");
+    assert!((book.current_price - 20.0).abs() < 1e-9);
+
+    let bids: f64 = book.price_levels.iter().map(|l| l.token1_liquidity).sum();
+    let asks: f64 = book.price_levels.iter().map(|l| l.token0_liquidity).sum();
+    assert!((bids - 6000.0).abs() < 1e-6, "bids {bids}");
+    assert!((asks - 700.0).abs() < 1e-6, "asks {asks}");
+    assert!(book
+        .price_levels
+        .windows(2)
+        .all(|w| w[0].lower_price <= w[1].lower_price));
+    assert!(book
+        .price_levels
+        .iter()
+        .all(|l| l.lower_price > 19.0 && l.upper_price < 21.0));
+}
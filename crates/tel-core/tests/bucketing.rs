@@ -0,0 +1,72 @@
+//! Bucketing of price levels on linear, logarithmic and percent-from-spot grids.
+
+use tel_core::core::bucketing::{bucket_levels, Grid};
+use tel_core::models::{PriceLiquidity, Side};
+
+mod common;
+use common::level;
+
+fn summary(levels: &[PriceLiquidity]) -> Vec<(f64, f64, f64, f64)> {
+    levels
+        .iter()
+        .map(|l| (l.lower_price, l.upper_price, l.token0_liquidity, l.token1_liquidity))
+        .collect()
+}
+
+#[test]
+fn levels_straddling_buckets_are_split_by_price_range() {
+    let levels = vec![
+        level(Side::Buy, 95.0, 105.0, 0.0, 100.0),
+        level(Side::Buy, 101.0, 102.0, 0.0, 10.0),
+    ];
+
+    let linear = bucket_levels(&levels, Grid::Linear(10.0), 100.0, None);
+    assert_eq!(
+        summary(&linear),
+        vec![(90.0, 100.0, 0.0, 50.0), (100.0, 110.0, 0.0, 60.0)]
+    );
+
+    let percent = bucket_levels(&levels, Grid::Percent(0.04), 100.0, None);
+    let bounds: Vec<(f64, f64)> = percent.iter().map(|l| (l.lower_price, l.upper_price)).collect();
+    assert_eq!(bounds, vec![(94.0, 98.0), (98.0, 102.0), (102.0, 106.0)]);
+    let values: Vec<f64> = percent.iter().map(|l| l.token1_liquidity).collect();
+    assert!((values[0] - 30.0).abs() < 1e-9);
+    assert!((values[1] - 50.0).abs() < 1e-9);
+    assert!((values[2] - 30.0).abs() < 1e-9);
+    let total: f64 = values.iter().sum();
+    assert!((total - 110.0).abs() < 1e-9);
+}
+
+#[test]
+fn log_buckets_grow_with_price_and_range_clips() {
+    let levels = vec![
+        level(Side::Buy, 50.0, 90.0, 0.0, 40.0),
+        level(Side::Sell, 110.0, 400.0, 2.9, 0.0),
+    ];
+
+    let log = bucket_levels(&levels, Grid::Log(1.0), 100.0, None);
+    let bounds: Vec<(f64, f64)> = log
+        .iter()
+        .map(|l| (l.lower_price.round(), l.upper_price.round()))
+        .collect();
+    assert_eq!(bounds, vec![(32.0, 64.0), (64.0, 128.0), (128.0, 256.0), (256.0, 512.0)]);
+    assert_eq!(log[1].side, Side::Buy);
+    assert_eq!(log[2].side, Side::Sell);
+
+    let clipped = bucket_levels(&levels, Grid::Linear(10.0), 100.0, Some(0.2));
+    assert_eq!(clipped.len(), 2);
+    assert_eq!(summary(&clipped)[0], (80.0, 90.0, 0.0, 10.0));
+    assert_eq!((clipped[1].lower_price, clipped[1].upper_price), (110.0, 120.0));
+    assert!((clipped[1].token0_liquidity - 0.1).abs() < 1e-9);
+}
+
+#[test]
+fn grids_parse_from_query_strings() {
+    assert_eq!("linear:25".parse::<Grid>().unwrap(), Grid::Linear(25.0));
+    assert_eq!("log:0.005".parse::<Grid>().unwrap(), Grid::Log(0.005));
+    assert_eq!("Percent:0.01".parse::<Grid>().unwrap(), Grid::Percent(0.01));
+    assert_eq!(Grid::Percent(0.01).to_string(), "percent:0.01");
+    for bad in ["linear", "log:-1", "cubic:2", "percent:x"] {
+        assert!(bad.parse::<Grid>().is_err(), "{bad}");
+    }
+}
@@ -0,0 +1,54 @@
+//! Factories shared by the liquidity tests.
+
+#![allow(dead_code)]
+
+use alloy_primitives::Address;
+use chrono::Utc;
+use tel_core::models::{LiquidityDistribution, PriceLiquidity, Side, Token};
+
+/// An 18-decimal mainnet token whose address repeats the byte `n`.
+pub fn token(n: u8) -> Token {
+    Token {
+        address: Address::repeat_byte(n),
+        symbol: format!("TK{n}"),
+        name: format!("Token {n}"),
+        decimals: 18,
+        chain_id: 1,
+    }
+}
+
+/// Token `n` on `chain_id`, with an address that differs from its deployments on other chains.
+pub fn token_on(n: u8, chain_id: u64) -> Token {
+    let mut address = [n; 20];
+    address[19] = chain_id as u8;
+    Token {
+        address: Address::from(address),
+        chain_id,
+        ..token(n)
+    }
+}
+
+pub fn level(side: Side, lower_price: f64, upper_price: f64, token0: f64, token1: f64) -> PriceLiquidity {
+    PriceLiquidity {
+        side,
+        lower_price,
+        upper_price,
+        token0_liquidity: token0,
+        token1_liquidity: token1,
+        timestamp: Utc::now(),
+    }
+}
+
+/// A mainnet distribution of token 1 quoted in token 2.
+pub fn distribution(dex: &str, current_price: f64, price_levels: Vec<PriceLiquidity>) -> LiquidityDistribution {
+    LiquidityDistribution {
+        token0: token(1),
+        token1: token(2),
+        current_price,
+        dex: dex.to_string(),
+        chain_id: 1,
+        price_levels,
+        timestamp: Utc::now(),
+        pool: None,
+    }
+}
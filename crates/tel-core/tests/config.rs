@@ -0,0 +1,26 @@
+//! The shipped config files must keep parsing as the config schema grows.
+
+use tel_core::config::{load_config, IndexerMode};
+
+#[test]
+fn shipped_configs_parse() {
+    for path in ["../../config/config.toml", "../../config/default.toml"] {
+        let config = load_config(path).unwrap();
+        assert_eq!(config.indexer.mode, IndexerMode::Light);
+        assert!(config.retention.enabled);
+        assert_eq!(config.watchlist.pools[&1]["uniswap_v2"].len(), 9);
+        assert_eq!(config.watchlist.pools[&1]["uniswap_v3"].len(), 22);
+        assert_eq!(config.watchlist.pairs.len(), 1);
+        assert_eq!(config.watchlist.top_by_tvl, None);
+        assert_eq!(config.pricing.numeraires.len(), 3);
+        // Every chain with numeraires has a Uniswap V3 factory, off until its RPC is set
+        for chain_id in [10, 42161] {
+            let dex = config.dexes.iter().find(|d| d.chain_id == chain_id).unwrap();
+            assert_eq!(dex.name, "uniswap_v3");
+            assert!(!dex.enabled);
+        }
+        let usdc = config.assets.asset("USDC").unwrap();
+        assert_eq!(usdc.tokens.iter().filter(|t| t.chain_id == 42161).count(), 2);
+        assert!(config.assets.asset("weth").is_some());
+    }
+}
@@ -0,0 +1,129 @@
+//! Liquidity of one canonical asset merged across chains, with price divergence.
+
+use alloy_primitives::Address;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tel_core::config::{AssetToken, CanonicalAsset, Numeraire, PricingConfig};
+use tel_core::core::bucketing::Grid;
+use tel_core::core::pricing::PriceOracle;
+use tel_core::models::{LiquidityDistribution, Pool, Side};
+use tel_core::storage::{aggregate_asset_liquidity, MemoryStorage, Storage};
+
+mod common;
+use common::{level, token_on};
+
+const USDC: u8 = 0xa;
+const WETH: u8 = 0xb;
+const OPTIMISM: u64 = 10;
+
+/// Token `n` on `chain_id`, whose address ends in the chain ID to keep chains apart.
+/// A WETH/USDC pool on `chain_id` at `price`, with `bids` USDC below it and `asks` WETH above.
+async fn add_weth_pool(storage: &Arc<dyn Storage>, chain_id: u64, price: f64, bids: f64, asks: f64) {
+    let (weth, usdc) = (token_on(WETH, chain_id), token_on(USDC, chain_id));
+    storage.save_token(&weth).await.unwrap();
+    storage.save_token(&usdc).await.unwrap();
+    let pool = Pool {
+        address: Address::repeat_byte(chain_id as u8),
+        dex: "uniswap_v3".to_string(),
+        chain_id,
+        tokens: vec![weth.clone(), usdc.clone()],
+        creation_block: 0,
+        creation_timestamp: Utc::now(),
+        last_updated_block: 0,
+        last_updated_timestamp: Utc::now(),
+        fee: 500,
+    };
+    storage.save_pool(&pool).await.unwrap();
+    let distribution = LiquidityDistribution {
+        token0: weth,
+        token1: usdc,
+        current_price: price,
+        dex: "uniswap_v3".to_string(),
+        chain_id,
+        price_levels: vec![
+            level(Side::Buy, price - 10.0, price, 0.0, bids),
+            level(Side::Sell, price, price + 10.0, asks, 0.0),
+        ],
+        timestamp: Utc::now(),
+        pool: Some(pool.address),
+    };
+    storage.save_liquidity_distribution(&distribution).await.unwrap();
+}
+
+#[tokio::test]
+async fn books_on_each_chain_merge_with_attribution_and_divergence() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    add_weth_pool(&storage, 1, 2000.0, 1_000_000.0, 100.0).await;
+    add_weth_pool(&storage, OPTIMISM, 2030.0, 500_000.0, 50.0).await;
+
+    let prices = PriceOracle::new(
+        storage.clone(),
+        PricingConfig {
+            numeraires: [1, OPTIMISM]
+                .map(|chain_id| Numeraire {
+                    chain_id,
+                    token: token_on(USDC, chain_id).address.to_string(),
+                })
+                .to_vec(),
+            min_liquidity: 1000.0,
+            ..PricingConfig::default()
+        },
+    );
+    // Arbitrum has no pools and is left out.
+    let asset = CanonicalAsset {
+        id: "weth".to_string(),
+        tokens: [1, OPTIMISM, 42161]
+            .map(|chain_id| AssetToken {
+                chain_id,
+                token: token_on(WETH, chain_id).address.to_string(),
+            })
+            .to_vec(),
+    };
+    let dexes: HashMap<u64, Vec<String>> = [1, OPTIMISM, 42161]
+        .map(|chain_id| (chain_id, vec!["uniswap_v3".to_string()]))
+        .into();
+
+    let book = aggregate_asset_liquidity(storage, &prices, &asset, &dexes, Grid::Percent(0.02), None, 0.0075)
+        .await
+        .unwrap();
+
+    // Weighted by value: 1.2M USD on mainnet, 601.5k on Optimism.
+    let expected = (2000.0 * 1_200_000.0 + 2030.0 * 601_500.0) / 1_801_500.0;
+    assert!((book.price - expected).abs() < 1e-6, "price {}", book.price);
+    assert!((book.max_divergence - 30.0 / expected).abs() < 1e-9);
+
+    assert_eq!(book.chains.len(), 2);
+    assert_eq!(book.chains[0].chain_id, 1);
+    assert!(book.chains[0].divergence < 0.0 && !book.chains[0].divergent);
+    assert_eq!(book.chains[1].chain_id, OPTIMISM);
+    assert!(book.chains[1].divergence > 0.0 && book.chains[1].divergent);
+
+    let asks: f64 = book.levels.iter().map(|l| l.asset_liquidity).sum();
+    let bids: f64 = book.levels.iter().map(|l| l.usd_liquidity).sum();
+    assert!((asks - 150.0).abs() < 1e-6, "asks {asks}");
+    assert!((bids - 1_500_000.0).abs() < 1e-3, "bids {bids}");
+    assert!(book.levels.windows(2).all(|w| w[0].lower_price < w[1].lower_price));
+    // The 2% bucket around the cross-chain price spans mainnet's asks and Optimism's bids.
+    assert!(book
+        .levels
+        .iter()
+        .any(|l| l.chain_sources.contains_key(&1) && l.chain_sources.contains_key(&OPTIMISM)));
+}
+
+#[tokio::test]
+async fn an_asset_with_no_priced_token_is_an_error() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let prices = PriceOracle::new(storage.clone(), PricingConfig::default());
+    let asset = CanonicalAsset {
+        id: "weth".to_string(),
+        tokens: vec![AssetToken {
+            chain_id: 1,
+            token: token_on(WETH, 1).address.to_string(),
+        }],
+    };
+    let dexes = HashMap::from([(1, vec!["uniswap_v3".to_string()])]);
+
+    let result = aggregate_asset_liquidity(storage, &prices, &asset, &dexes, Grid::Percent(0.01), None, 0.005).await;
+    assert!(matches!(result, Err(tel_core::Error::DexError(_))));
+}
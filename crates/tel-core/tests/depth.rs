@@ -0,0 +1,64 @@
+//! Cumulative market depth at a set of price moves.
+
+use tel_core::core::depth::depth_curve;
+use tel_core::models::Side;
+
+mod common;
+use common::{distribution, level};
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn depth_integrates_each_side_per_dex() {
+    let distributions = [
+        distribution(
+            "uniswap_v3",
+            100.0,
+            vec![
+                level(Side::Sell, 100.0, 102.0, 10.0, 0.0),
+                level(Side::Buy, 98.0, 100.0, 0.0, 1000.0),
+            ],
+        ),
+        distribution(
+            "uniswap_v2",
+            100.0,
+            vec![
+                level(Side::Sell, 101.0, 103.0, 4.0, 0.0),
+                level(Side::Buy, 95.0, 99.0, 0.0, 400.0),
+            ],
+        ),
+    ];
+
+    let depth = depth_curve(&distributions, &[0.02, 0.01], Some(2.0));
+    assert!(close(depth.price, 100.0));
+
+    let [one, two] = &depth.asks[..] else {
+        panic!("expected two ask levels, got {:?}", depth.asks);
+    };
+    assert!(close(one.change, 0.01) && close(one.price, 101.0));
+    assert!(close(one.total.token0, 5.0) && close(one.total.token1, 502.5));
+    assert_eq!(one.total.usd, Some(1005.0));
+    assert_eq!(one.dex_sources.len(), 1);
+    assert!(close(two.total.token0, 12.0) && close(two.total.token1, 1213.0));
+    assert!(close(two.dex_sources["uniswap_v2"].token1, 203.0));
+
+    let bid = &depth.bids[1];
+    assert!(close(bid.change, -0.02) && close(bid.price, 98.0));
+    assert!(close(bid.total.token1, 1100.0));
+    assert!(close(bid.total.token0, 1000.0 / 99.0 + 100.0 / 98.5));
+    assert!(close(bid.dex_sources["uniswap_v3"].token1, 1000.0));
+}
+
+#[test]
+fn depth_without_a_price_is_empty() {
+    let depth = depth_curve(&[], &[0.01], None);
+    assert!(depth.asks.is_empty() && depth.bids.is_empty());
+
+    let distributions = [distribution("uniswap_v3", 100.0, vec![level(Side::Buy, 99.0, 100.0, 0.0, 50.0)])];
+    let depth = depth_curve(&distributions, &[0.01], None);
+    assert_eq!(depth.asks[0].total.token0, 0.0);
+    assert!(depth.asks[0].dex_sources.is_empty());
+    assert_eq!(depth.bids[0].total.usd, None);
+}
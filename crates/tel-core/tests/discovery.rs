@@ -0,0 +1,50 @@
+use tel_core::dexes::discovery::BlockScan;
+
+/// Runs a scan against a provider that rejects ranges wider than `limit` blocks.
+fn run(from: u64, head: u64, max_span: u64, limit: u64) -> Vec<(u64, u64)> {
+    let mut scan = BlockScan::new(from, head, max_span);
+    let mut done = Vec::new();
+    while let Some((start, end)) = scan.next_range() {
+        if end - start + 1 > limit {
+            assert!(scan.split(), "range {start}..{end} should still split");
+        } else {
+            done.push((start, end));
+            scan.advance();
+        }
+    }
+    done
+}
+
+#[test]
+fn covers_every_block_exactly_once() {
+    let ranges = run(100, 5_000, 1_000, 300);
+    assert_eq!(ranges.first().unwrap().0, 100);
+    assert_eq!(ranges.last().unwrap().1, 5_000);
+    for pair in ranges.windows(2) {
+        assert_eq!(pair[0].1 + 1, pair[1].0);
+    }
+    assert!(ranges.iter().all(|(from, to)| to - from < 300));
+}
+
+#[test]
+fn span_grows_back_after_success() {
+    let mut scan = BlockScan::new(0, 10_000, 1_000);
+    assert!(scan.split());
+    assert!(scan.split());
+    assert_eq!(scan.next_range(), Some((0, 249)));
+    scan.advance();
+    assert_eq!(scan.next_range(), Some((250, 749)));
+    scan.advance();
+    assert_eq!(scan.next_range(), Some((750, 1_749)));
+    scan.advance();
+    assert_eq!(scan.next_range(), Some((1_750, 2_749)));
+}
+
+#[test]
+fn single_block_cannot_split() {
+    let mut scan = BlockScan::new(42, 42, 1_000);
+    assert_eq!(scan.next_range(), Some((42, 42)));
+    assert!(!scan.split());
+    scan.advance();
+    assert_eq!(scan.next_range(), None);
+}
@@ -0,0 +1,138 @@
+//! End-to-end checks for `storage::export` in every output format.
+
+use alloy_primitives::Address;
+use chrono::{Duration, Utc};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::fs::File;
+use std::sync::Arc;
+use tel_core::models::{LiquidityDistribution, Pool, PriceLiquidity, Side, Token};
+use tel_core::storage::export::{export, ExportFilter, ExportFormat};
+use tel_core::storage::{MemoryStorage, Storage};
+
+fn token(n: u8) -> Token {
+    Token {
+        address: Address::repeat_byte(n),
+        symbol: format!("TK{n}"),
+        name: format!("Token {n}"),
+        decimals: 18,
+        chain_id: 1,
+    }
+}
+
+fn pool(n: u8, dex: &str, token0: u8, token1: u8) -> Pool {
+    Pool {
+        address: Address::repeat_byte(n),
+        dex: dex.to_string(),
+        chain_id: 1,
+        tokens: vec![token(token0), token(token1)],
+        creation_block: 0,
+        creation_timestamp: Utc::now(),
+        last_updated_block: 0,
+        last_updated_timestamp: Utc::now(),
+        fee: 3000,
+    }
+}
+
+fn distribution(token0: u8, token1: u8, dex: &str, hours_ago: i64) -> LiquidityDistribution {
+    let timestamp = Utc::now() - Duration::hours(hours_ago);
+    let level = |side, lower_price, upper_price| PriceLiquidity {
+        side,
+        lower_price,
+        upper_price,
+        token0_liquidity: 1.0,
+        token1_liquidity: 2.0,
+        timestamp,
+    };
+    LiquidityDistribution {
+        token0: token(token0),
+        token1: token(token1),
+        current_price: 100.0,
+        dex: dex.to_string(),
+        chain_id: 1,
+        price_levels: vec![level(Side::Buy, 99.0, 100.0), level(Side::Sell, 100.0, 101.0)],
+        timestamp,
+        pool: None,
+    }
+}
+
+async fn seeded_storage() -> Arc<dyn Storage> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    storage.save_pool(&pool(10, "uniswap_v3", 1, 2)).await.unwrap();
+    storage.save_pool(&pool(11, "uniswap_v2", 1, 3)).await.unwrap();
+    for snapshot in [
+        distribution(1, 2, "uniswap_v3", 3),
+        distribution(1, 2, "uniswap_v3", 1),
+        distribution(1, 3, "uniswap_v2", 1),
+    ] {
+        storage.save_liquidity_distribution(&snapshot).await.unwrap();
+    }
+    storage
+}
+
+#[tokio::test]
+async fn exports_every_format() {
+    let storage = seeded_storage().await;
+    let dir = tempfile::tempdir().unwrap();
+
+    for format in [ExportFormat::Csv, ExportFormat::JsonLines, ExportFormat::Parquet] {
+        let out = dir.path().join(format.extension());
+        let summary = export(storage.clone(), &ExportFilter::default(), format, &out)
+            .await
+            .unwrap();
+        assert_eq!((summary.tokens, summary.pools, summary.price_levels), (3, 2, 6));
+        assert_eq!(summary.files.len(), 3);
+
+        let levels = out.join(format!("price_levels.{}", format.extension()));
+        let rows = match format {
+            ExportFormat::Csv => {
+                let mut reader = csv::Reader::from_path(&levels).unwrap();
+                let headers = reader.headers().unwrap().clone();
+                assert_eq!(&headers[0], "timestamp");
+                assert!(headers.iter().any(|h| h == "token1_liquidity"));
+                reader.records().count()
+            }
+            ExportFormat::JsonLines => {
+                let text = std::fs::read_to_string(&levels).unwrap();
+                for line in text.lines() {
+                    let row: serde_json::Value = serde_json::from_str(line).unwrap();
+                    assert_eq!(row["chain_id"], 1);
+                    assert!(row["side"] == "buy" || row["side"] == "sell");
+                }
+                text.lines().count()
+            }
+            ExportFormat::Parquet => {
+                let reader = SerializedFileReader::new(File::open(&levels).unwrap()).unwrap();
+                let schema = reader.metadata().file_metadata().schema_descr();
+                assert_eq!(schema.num_columns(), 14);
+                reader.metadata().file_metadata().num_rows() as usize
+            }
+        };
+        assert_eq!(rows, 6);
+    }
+}
+
+#[tokio::test]
+async fn filters_by_dex_pair_and_time() {
+    let storage = seeded_storage().await;
+    let dir = tempfile::tempdir().unwrap();
+
+    // Pair given in reverse order still matches
+    let filter = ExportFilter {
+        pair: Some((Address::repeat_byte(2), Address::repeat_byte(1))),
+        from: Some(Utc::now() - Duration::hours(2)),
+        ..Default::default()
+    };
+    let summary = export(storage.clone(), &filter, ExportFormat::Csv, dir.path())
+        .await
+        .unwrap();
+    assert_eq!((summary.tokens, summary.pools, summary.price_levels), (2, 1, 2));
+
+    let filter = ExportFilter {
+        dex: Some("uniswap_v2".to_string()),
+        ..Default::default()
+    };
+    let summary = export(storage, &filter, ExportFormat::Csv, dir.path())
+        .await
+        .unwrap();
+    assert_eq!((summary.tokens, summary.pools, summary.price_levels), (2, 1, 2));
+}
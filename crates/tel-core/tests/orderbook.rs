@@ -0,0 +1,70 @@
+//! Synthetic L2 order books built from AMM liquidity distributions.
+
+use tel_core::core::orderbook::{default_tick, order_book};
+use tel_core::models::Side;
+
+mod common;
+use common::{distribution, level};
+
+#[test]
+fn levels_are_sized_in_base_on_the_tick_grid() {
+    let book = order_book(
+        &[distribution(
+            "uniswap_v3",
+            2000.0,
+            vec![
+                level(Side::Buy, 1998.0, 2000.0, 0.0, 3998.0),
+                level(Side::Sell, 2000.0, 2002.0, 4.0, 0.0),
+            ],
+        )],
+        1.0,
+        None,
+        100,
+    );
+
+    assert_eq!(book.price, 2000.0);
+    assert_eq!(book.tick, 1.0);
+    // Bids at the bottom of their tick, converted at the middle of the liquidity in it.
+    assert_eq!(book.bids.len(), 2);
+    assert_eq!(book.bids[0][0], 1999.0);
+    assert!((book.bids[0][1] - 1999.0 / 1999.5).abs() < 1e-12);
+    assert_eq!(book.bids[1][0], 1998.0);
+    assert!((book.bids[1][1] - 1999.0 / 1998.5).abs() < 1e-12);
+    // Asks at the top of their tick.
+    assert_eq!(book.asks, vec![[2001.0, 2.0], [2002.0, 2.0]]);
+
+    let asks_only = distribution("uniswap_v3", 2000.0, vec![level(Side::Sell, 2000.0, 2002.0, 4.0, 0.0)]);
+    let shallow = order_book(&[asks_only], 1.0, None, 1);
+    assert_eq!(shallow.asks, vec![[2001.0, 2.0]]);
+    assert!(shallow.bids.is_empty());
+}
+
+#[test]
+fn book_never_crosses_when_dexes_disagree() {
+    let book = order_book(
+        &[
+            distribution(
+                "uniswap_v3",
+                2000.0,
+                vec![
+                    level(Side::Buy, 1995.0, 2000.0, 0.0, 100_000.0),
+                    level(Side::Sell, 2000.0, 2005.0, 50.0, 0.0),
+                ],
+            ),
+            // Trades below the others: its asks sit under the average price.
+            distribution("sushiswap", 1990.0, vec![level(Side::Sell, 1990.0, 1995.0, 5.0, 0.0)]),
+        ],
+        default_tick(2000.0),
+        None,
+        1000,
+    );
+
+    assert!(book.price < 2000.0 && book.price > 1990.0);
+    let best_bid = book.bids[0][0];
+    let best_ask = book.asks[0][0];
+    assert!(best_bid <= book.price && book.price < best_ask, "{best_bid} {} {best_ask}", book.price);
+    assert!(book.bids.windows(2).all(|w| w[0][0] > w[1][0]));
+    assert!(book.asks.windows(2).all(|w| w[0][0] < w[1][0]));
+    assert!((default_tick(2000.0) - 0.1).abs() < 1e-12);
+    assert!((default_tick(1.0) - 1e-4).abs() < 1e-16);
+}
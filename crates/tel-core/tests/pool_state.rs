@@ -0,0 +1,183 @@
+use alloy_primitives::U256;
+use std::collections::BTreeMap;
+use tel_core::dexes::state::{PoolEvent, PoolState, V3State};
+
+fn v3(tick: i32, liquidity: u128) -> PoolState {
+    PoolState::V3(V3State {
+        sqrt_price_x96: U256::from(1u64) << 96,
+        liquidity,
+        tick,
+        tick_spacing: 10,
+        ticks: BTreeMap::from([(-100, (500, 500)), (100, (500, -500))]),
+        tick_range: None,
+    })
+}
+
+/// `v3` as read from the chain: only the ticks of one bitmap word are known.
+fn v3_read(tick: i32, liquidity: u128) -> PoolState {
+    match v3(tick, liquidity) {
+        PoolState::V3(state) => PoolState::V3(V3State {
+            tick_range: Some((-2560, 2559)),
+            ..state
+        }),
+        v2 => v2,
+    }
+}
+
+fn ticks(state: &PoolState) -> &BTreeMap<i32, (u128, i128)> {
+    match state {
+        PoolState::V3(s) => &s.ticks,
+        PoolState::V2 { .. } => panic!("expected V3 state"),
+    }
+}
+
+fn liquidity(state: &PoolState) -> u128 {
+    match state {
+        PoolState::V3(s) => s.liquidity,
+        PoolState::V2 { .. } => panic!("expected V3 state"),
+    }
+}
+
+#[test]
+fn sync_replaces_reserves() {
+    let mut state = PoolState::V2 {
+        reserve0: 10,
+        reserve1: 20,
+    };
+    let event = PoolEvent::Sync {
+        reserve0: 11,
+        reserve1: 19,
+    };
+    assert!(state.apply(&event));
+    assert_eq!(
+        state,
+        PoolState::V2 {
+            reserve0: 11,
+            reserve1: 19
+        }
+    );
+    assert!(!state.apply(&event), "same reserves are not a change");
+}
+
+#[test]
+fn mint_in_range_adds_active_liquidity() {
+    let mut state = v3(0, 500);
+    assert!(state.apply(&PoolEvent::Mint {
+        tick_lower: -50,
+        tick_upper: 50,
+        amount: 200,
+    }));
+    assert_eq!(liquidity(&state), 700);
+    assert_eq!(ticks(&state)[&-50], (200, 200));
+    assert_eq!(ticks(&state)[&50], (200, -200));
+}
+
+#[test]
+fn burn_out_of_range_only_touches_ticks() {
+    let mut state = v3(0, 500);
+    state.apply(&PoolEvent::Mint {
+        tick_lower: 200,
+        tick_upper: 300,
+        amount: 50,
+    });
+    assert!(state.apply(&PoolEvent::Burn {
+        tick_lower: 200,
+        tick_upper: 300,
+        amount: 50,
+    }));
+    assert_eq!(liquidity(&state), 500);
+    assert!(
+        !ticks(&state).contains_key(&200),
+        "emptied ticks are uninitialized"
+    );
+    assert!(!ticks(&state).contains_key(&300));
+
+    assert!(
+        !state.apply(&PoolEvent::Burn {
+            tick_lower: -100,
+            tick_upper: 100,
+            amount: 0,
+        }),
+        "zero-amount burns only collect fees"
+    );
+}
+
+#[test]
+fn swap_moves_price_and_ignores_foreign_events() {
+    let mut state = v3(0, 500);
+    let swap = PoolEvent::Swap {
+        sqrt_price_x96: U256::from(2u64) << 96,
+        liquidity: 500,
+        tick: 6931,
+    };
+    assert!(state.apply(&swap));
+    assert!(!state.apply(&swap));
+    assert!(!state.apply(&PoolEvent::Sync {
+        reserve0: 1,
+        reserve1: 1
+    }));
+}
+
+#[test]
+fn initialize_sets_the_starting_price() {
+    let mut state = v3(0, 0);
+    assert!(state.apply(&PoolEvent::Initialize {
+        sqrt_price_x96: U256::from(3u64) << 96,
+        tick: 21_972,
+    }));
+    match state {
+        PoolState::V3(s) => assert_eq!((s.tick, s.liquidity), (21_972, 0)),
+        PoolState::V2 { .. } => unreachable!(),
+    }
+}
+
+#[test]
+fn consistency_ignores_ticks_outside_the_fresh_read() {
+    let mut tracked = v3(0, 500);
+    tracked.apply(&PoolEvent::Mint {
+        tick_lower: 5_000,
+        tick_upper: 6_000,
+        amount: 10,
+    });
+    assert!(tracked.consistent_with(&v3_read(0, 500)));
+    assert!(!tracked.consistent_with(&v3_read(0, 501)));
+    // A full read compares every tick
+    assert!(!tracked.consistent_with(&v3(0, 500)));
+
+    // A tick the event-derived state has but the read doesn't is drift
+    let mut minted = v3_read(0, 500);
+    minted.apply(&PoolEvent::Mint {
+        tick_lower: 1_000,
+        tick_upper: 2_000,
+        amount: 10,
+    });
+    assert!(!minted.consistent_with(&v3_read(0, 500)));
+}
+
+#[test]
+fn events_outside_the_read_ticks_need_a_reload() {
+    let state = v3_read(0, 500);
+    let mint = |tick_lower, tick_upper| PoolEvent::Mint {
+        tick_lower,
+        tick_upper,
+        amount: 10,
+    };
+    assert!(state.covers(&mint(-50, 50)));
+    assert!(!state.covers(&mint(-50, 5_000)));
+    assert!(!state.covers(&PoolEvent::Burn {
+        tick_lower: -3_000,
+        tick_upper: 0,
+        amount: 10,
+    }));
+    let swap = |tick| PoolEvent::Swap {
+        sqrt_price_x96: U256::from(1u64) << 96,
+        liquidity: 500,
+        tick,
+    };
+    assert!(state.covers(&swap(2_559)));
+    assert!(!state.covers(&swap(2_560)));
+    assert!(!state.covers(&swap(-2_561)));
+
+    // States replayed from creation know every tick
+    assert!(v3(0, 500).covers(&mint(-800_000, 800_000)));
+}
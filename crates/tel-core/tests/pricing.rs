@@ -0,0 +1,106 @@
+//! USD pricing by routing through indexed pools to a numeraire.
+
+use alloy_primitives::Address;
+use chrono::Utc;
+use std::sync::Arc;
+use tel_core::config::{Numeraire, PricingConfig};
+use tel_core::core::pricing::{PriceGraph, PriceOracle};
+use tel_core::models::{LiquidityDistribution, PriceLiquidity, Side};
+use tel_core::storage::{MemoryStorage, Storage};
+
+mod common;
+use common::token;
+
+const USDC: u8 = 0xa;
+const WETH: u8 = 0xb;
+const TKN: u8 = 0xc;
+
+/// A `token0`/`token1` pool at `price` holding `depth` worth of token1.
+fn pool(token0: u8, token1: u8, price: f64, depth: f64) -> LiquidityDistribution {
+    LiquidityDistribution {
+        token0: token(token0),
+        token1: token(token1),
+        current_price: price,
+        dex: "uniswap_v2".to_string(),
+        chain_id: 1,
+        price_levels: vec![PriceLiquidity {
+            side: Side::Buy,
+            lower_price: price * 0.9,
+            upper_price: price,
+            token0_liquidity: 0.0,
+            token1_liquidity: depth,
+            timestamp: Utc::now(),
+        }],
+        timestamp: Utc::now(),
+        pool: None,
+    }
+}
+
+fn addr(n: u8) -> Address {
+    Address::repeat_byte(n)
+}
+
+#[test]
+fn prices_route_over_several_hops() {
+    let pools = [pool(WETH, USDC, 2000.0, 1e6), pool(TKN, WETH, 0.01, 100.0)];
+    let graph = PriceGraph::build(1, addr(USDC), &pools, 3, 1000.0);
+
+    assert_eq!(graph.price(addr(USDC)), Some(1.0));
+    assert_eq!(graph.price(addr(WETH)), Some(2000.0));
+    let tkn = graph.quote(addr(TKN)).unwrap();
+    assert!((tkn.price - 20.0).abs() < 1e-9);
+    assert!((tkn.liquidity - 200_000.0).abs() < 1e-6);
+    assert_eq!(tkn.path, vec![addr(TKN), addr(WETH), addr(USDC)]);
+
+    let one_hop = PriceGraph::build(1, addr(USDC), &pools, 1, 1000.0);
+    assert_eq!(one_hop.price(addr(TKN)), None);
+}
+
+#[test]
+fn deepest_path_wins_and_shallow_pools_are_skipped() {
+    let pools = [
+        pool(WETH, USDC, 2000.0, 1e6),
+        pool(TKN, WETH, 0.01, 100.0),
+        // Held the other way round, and far shallower.
+        pool(USDC, TKN, 1.0 / 25.0, 80.0),
+    ];
+
+    let graph = PriceGraph::build(1, addr(USDC), &pools, 3, 1000.0);
+    let tkn = graph.quote(addr(TKN)).unwrap();
+    assert!((tkn.price - 20.0).abs() < 1e-9);
+    assert_eq!(tkn.path.len(), 3);
+
+    let direct_only = [pools[2].clone()];
+    let graph = PriceGraph::build(1, addr(USDC), &direct_only, 3, 1000.0);
+    let tkn = graph.quote(addr(TKN)).unwrap();
+    assert!((tkn.price - 25.0).abs() < 1e-9);
+    assert!((tkn.liquidity - 2000.0).abs() < 1e-6);
+
+    let strict = PriceGraph::build(1, addr(USDC), &direct_only, 3, 5000.0);
+    assert_eq!(strict.price(addr(TKN)), None);
+}
+
+#[tokio::test]
+async fn oracle_prices_from_stored_snapshots() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    for n in [USDC, WETH, TKN] {
+        storage.save_token(&token(n)).await.unwrap();
+    }
+    for d in [pool(WETH, USDC, 2000.0, 1e6), pool(TKN, WETH, 0.01, 100.0)] {
+        storage.save_liquidity_distribution(&d).await.unwrap();
+    }
+    let config = PricingConfig {
+        numeraires: vec![Numeraire {
+            chain_id: 1,
+            token: addr(USDC).to_string(),
+        }],
+        min_liquidity: 1000.0,
+        ..PricingConfig::default()
+    };
+
+    let oracle = PriceOracle::new(storage, config);
+    let price = oracle.usd_price(addr(TKN), 1).await.unwrap().unwrap();
+    assert!((price - 20.0).abs() < 1e-9);
+    assert_eq!(oracle.usd_price(addr(0xd), 1).await.unwrap(), None);
+    assert!(oracle.graph(10).await.unwrap().is_none());
+}
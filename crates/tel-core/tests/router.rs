@@ -0,0 +1,110 @@
+//! Best-execution routing of swaps across the stored liquidity of every pool.
+
+use alloy_primitives::Address;
+use chrono::Utc;
+use tel_core::core::router::QuoteRouter;
+use tel_core::models::{LiquidityDistribution, PriceLiquidity, Side};
+
+mod common;
+use common::token;
+
+const USDC: u8 = 0xa;
+const WETH: u8 = 0xb;
+const TKN: u8 = 0xc;
+
+/// A book of token0 quoted in token1 with `token1` of bids in each of `bids` and `token0` of
+/// asks in each of `asks`.
+fn book(
+    dex: &str,
+    (token0, token1): (u8, u8),
+    price: f64,
+    bids: &[(f64, f64, f64)],
+    asks: &[(f64, f64, f64)],
+) -> LiquidityDistribution {
+    let level = |side, (lower_price, upper_price, amount): (f64, f64, f64)| PriceLiquidity {
+        side,
+        lower_price,
+        upper_price,
+        token0_liquidity: if side == Side::Sell { amount } else { 0.0 },
+        token1_liquidity: if side == Side::Buy { amount } else { 0.0 },
+        timestamp: Utc::now(),
+    };
+    LiquidityDistribution {
+        token0: token(token0),
+        token1: token(token1),
+        current_price: price,
+        dex: dex.to_string(),
+        chain_id: 1,
+        price_levels: bids
+            .iter()
+            .map(|b| level(Side::Buy, *b))
+            .chain(asks.iter().map(|a| level(Side::Sell, *a)))
+            .collect(),
+        timestamp: Utc::now(),
+        pool: None,
+    }
+}
+
+#[test]
+fn split_equalises_marginal_prices_and_beats_either_pool() {
+    let deep = book("uniswap_v3", (WETH, USDC), 2000.0, &[(1900.0, 2000.0, 200_000.0)], &[]);
+    let shallow = book("sushiswap", (WETH, USDC), 2000.0, &[(1950.0, 2000.0, 50_000.0)], &[]);
+    let weth = token(WETH).address;
+    let usdc = token(USDC).address;
+
+    let both = QuoteRouter::new([(&deep, None, 0.0), (&shallow, None, 0.0)]);
+    let split = both.quote(weth, usdc, 40.0, 2, 200).unwrap();
+    let deep_only = QuoteRouter::new([(&deep, None, 0.0)]).quote(weth, usdc, 40.0, 2, 200).unwrap();
+    let shallow_only = QuoteRouter::new([(&shallow, None, 0.0)])
+        .quote(weth, usdc, 40.0, 2, 200)
+        .unwrap();
+
+    assert_eq!(split.unfilled, 0.0);
+    assert_eq!(split.pools.len(), 2);
+    assert!(split.amount_out > deep_only.amount_out);
+    assert!(shallow_only.unfilled > 0.0 || split.amount_out > shallow_only.amount_out);
+
+    // Both pools end near the same price: the deep one spends 2000 USDC per 1 USD of price over
+    // 100 USD, the shallow one 1000 per 1 USD over 50, so a common price p takes
+    // 2000 (2000 - p) and 1000 (2000 - p) USDC, a 2:1 split of the output.
+    let deep_out = split.pools.iter().find(|p| p.dex == "uniswap_v3").unwrap().amount_out;
+    let shallow_out = split.pools.iter().find(|p| p.dex == "sushiswap").unwrap().amount_out;
+    assert!((deep_out / shallow_out - 2.0).abs() < 0.1, "{deep_out} / {shallow_out}");
+    assert!(split.spot_price > 1990.0 && split.spot_price <= 2000.0);
+}
+
+#[test]
+fn two_hop_path_is_used_when_no_pool_holds_the_pair() {
+    // TKN is token1 of its WETH pool, 100 TKN per WETH, and WETH sells for 2000 USDC.
+    let tkn_weth = book("uniswap_v2", (WETH, TKN), 100.0, &[], &[(100.0, 101.0, 10.0)]);
+    let weth_usdc = book("uniswap_v3", (WETH, USDC), 2000.0, &[(1990.0, 2000.0, 100_000.0)], &[]);
+    let router = QuoteRouter::new([
+        (&tkn_weth, Some(Address::repeat_byte(1)), 0.003),
+        (&weth_usdc, Some(Address::repeat_byte(2)), 0.0005),
+    ]);
+
+    let tkn = token(TKN).address;
+    let usdc = token(USDC).address;
+    assert!(router.quote(tkn, usdc, 100.0, 1, 10).is_none());
+
+    let swap = router.quote(tkn, usdc, 100.0, 2, 10).unwrap();
+    assert_eq!(swap.routes.len(), 1);
+    assert_eq!(swap.routes[0].path, vec![tkn, token(WETH).address, usdc]);
+    assert_eq!(swap.routes[0].dexes, vec!["uniswap_v2", "uniswap_v3"]);
+    // About 1 WETH after the 0.3% fee, sold near 2000 less 0.05%.
+    assert!(swap.amount_out > 1950.0 && swap.amount_out < 2000.0, "{}", swap.amount_out);
+    let first = swap.pools.iter().find(|p| p.token_in == tkn).unwrap();
+    assert_eq!(first.pool, Some(Address::repeat_byte(1)));
+    assert_eq!(first.fee, 0.003);
+}
+
+#[test]
+fn input_beyond_the_liquidity_is_unfilled() {
+    let weth_usdc = book("uniswap_v3", (WETH, USDC), 2000.0, &[(1990.0, 2000.0, 10_000.0)], &[]);
+    let router = QuoteRouter::new([(&weth_usdc, None, 0.0)]);
+
+    let swap = router.quote(token(WETH).address, token(USDC).address, 10.0, 2, 10).unwrap();
+    assert!(swap.unfilled > 0.0);
+    assert!(swap.amount_out <= 10_000.0 + 1e-6);
+    assert!(swap.amount_out > 7_000.0);
+}
@@ -0,0 +1,967 @@
+//! Behavioural checks shared by every `Storage` implementation.
+//!
+//! Each check takes a fresh, empty store. `storage_conformance!` instantiates the
+//! whole suite for one implementation so new backends only need a single line.
+
+use alloy_primitives::{Address, B256};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tel_core::config::RetentionConfig;
+use tel_core::models::{
+    BlockRef, IndexerRun, LiquidityDistribution, Pool, PoolAttempt, PriceLiquidity, Side, Token,
+};
+use tel_core::storage::{DryRunStorage, MemoryStorage, SqliteStorage, Storage};
+
+fn addr(n: u8) -> Address {
+    Address::repeat_byte(n)
+}
+
+fn token(n: u8, chain_id: u64) -> Token {
+    Token {
+        address: addr(n),
+        symbol: format!("TK{n}"),
+        name: format!("Token {n}"),
+        decimals: 6 + n % 13,
+        chain_id,
+    }
+}
+
+fn pool(n: u8, dex: &str, chain_id: u64, token0: u8, token1: u8, fee: u64) -> Pool {
+    Pool {
+        address: addr(n),
+        dex: dex.to_string(),
+        chain_id,
+        tokens: vec![token(token0, chain_id), token(token1, chain_id)],
+        creation_block: 0,
+        creation_timestamp: Utc::now(),
+        last_updated_block: 0,
+        last_updated_timestamp: Utc::now(),
+        fee,
+    }
+}
+
+fn distribution(
+    token0: u8,
+    token1: u8,
+    dex: &str,
+    chain_id: u64,
+    current_price: f64,
+    timestamp: DateTime<Utc>,
+) -> LiquidityDistribution {
+    LiquidityDistribution {
+        token0: token(token0, chain_id),
+        token1: token(token1, chain_id),
+        current_price,
+        dex: dex.to_string(),
+        chain_id,
+        price_levels: vec![PriceLiquidity {
+            side: Side::Buy,
+            lower_price: current_price * 0.99,
+            upper_price: current_price,
+            token0_liquidity: 0.0,
+            token1_liquidity: 1_000.0,
+            timestamp,
+        }],
+        timestamp,
+        pool: None,
+    }
+}
+
+fn block(number: u64, fork: u8) -> BlockRef {
+    BlockRef {
+        number,
+        hash: B256::repeat_byte(fork ^ number as u8),
+        parent_hash: B256::repeat_byte(fork ^ number.wrapping_sub(1) as u8),
+    }
+}
+
+fn assert_token_eq(actual: &Token, expected: &Token) {
+    assert_eq!(actual.address, expected.address);
+    assert_eq!(actual.chain_id, expected.chain_id);
+    assert_eq!(actual.symbol, expected.symbol);
+    assert_eq!(actual.name, expected.name);
+    assert_eq!(actual.decimals, expected.decimals);
+}
+
+fn assert_pool_eq(actual: &Pool, expected: &Pool) {
+    assert_eq!(actual.address, expected.address);
+    assert_eq!(actual.dex, expected.dex);
+    assert_eq!(actual.chain_id, expected.chain_id);
+    assert_eq!(actual.fee, expected.fee);
+    assert_eq!(actual.creation_block, expected.creation_block);
+    assert_eq!(actual.tokens.len(), expected.tokens.len());
+    for (a, e) in actual.tokens.iter().zip(&expected.tokens) {
+        assert_token_eq(a, e);
+    }
+}
+
+async fn token_round_trip(storage: Arc<dyn Storage>) {
+    let t = token(1, 1);
+    assert!(storage.get_token(t.address, 1).await.unwrap().is_none());
+
+    storage.save_token(&t).await.unwrap();
+    assert_token_eq(&storage.get_token(t.address, 1).await.unwrap().unwrap(), &t);
+
+    // Saving again overwrites metadata
+    let renamed = Token {
+        symbol: "NEW".to_string(),
+        ..t.clone()
+    };
+    storage.save_token(&renamed).await.unwrap();
+    assert_token_eq(
+        &storage.get_token(t.address, 1).await.unwrap().unwrap(),
+        &renamed,
+    );
+}
+
+async fn pool_round_trip(storage: Arc<dyn Storage>) {
+    let p = pool(10, "uniswap_v3", 1, 1, 2, 500);
+    assert!(storage.get_pool(p.address, 1).await.unwrap().is_none());
+
+    storage.save_pool(&p).await.unwrap();
+    assert_pool_eq(&storage.get_pool(p.address, 1).await.unwrap().unwrap(), &p);
+
+    // Pool tokens are stored alongside the pool
+    assert_token_eq(
+        &storage.get_token(addr(1), 1).await.unwrap().unwrap(),
+        &p.tokens[0],
+    );
+    assert_token_eq(
+        &storage.get_token(addr(2), 1).await.unwrap().unwrap(),
+        &p.tokens[1],
+    );
+
+    let by_dex = storage.get_pools_by_dex("uniswap_v3", 1).await.unwrap();
+    assert_eq!(by_dex.len(), 1);
+    assert_pool_eq(&by_dex[0], &p);
+    assert!(storage
+        .get_pools_by_dex("uniswap_v2", 1)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn pool_keeps_creation_block(storage: Arc<dyn Storage>) {
+    let created = Pool {
+        creation_block: 12_369_621,
+        ..pool(10, "uniswap_v3", 1, 1, 2, 500)
+    };
+    storage.save_pool(&created).await.unwrap();
+    assert_pool_eq(&storage.get_pool(created.address, 1).await.unwrap().unwrap(), &created);
+
+    // Saving the pool again without its creation block keeps the known one
+    storage.save_pool(&pool(10, "uniswap_v3", 1, 1, 2, 500)).await.unwrap();
+    let listed = storage.list_pools(Some(1), None).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_pool_eq(&listed[0], &created);
+}
+
+async fn pools_by_token_spans_fee_tiers(storage: Arc<dyn Storage>) {
+    for (n, fee) in [(10, 100), (11, 500), (12, 3000), (13, 10000)] {
+        storage
+            .save_pool(&pool(n, "uniswap_v3", 1, 1, 2, fee))
+            .await
+            .unwrap();
+    }
+    storage
+        .save_pool(&pool(14, "uniswap_v2", 1, 2, 1, 3000))
+        .await
+        .unwrap();
+    storage
+        .save_pool(&pool(15, "sushiswap", 1, 1, 2, 3000))
+        .await
+        .unwrap();
+    storage
+        .save_pool(&pool(16, "uniswap_v3", 1, 1, 3, 500))
+        .await
+        .unwrap();
+
+    let pools = storage
+        .get_pools_by_token(addr(1), addr(2), 1, None, None)
+        .await
+        .unwrap();
+    assert_eq!(pools.len(), 6);
+    let reversed = storage
+        .get_pools_by_token(addr(2), addr(1), 1, None, None)
+        .await
+        .unwrap();
+    let mut a: Vec<_> = pools.iter().map(|p| p.address).collect();
+    let mut b: Vec<_> = reversed.iter().map(|p| p.address).collect();
+    a.sort();
+    b.sort();
+    assert_eq!(a, b, "token order must not matter");
+
+    let v3 = storage
+        .get_pools_by_token(addr(1), addr(2), 1, Some("uniswap_v3"), None)
+        .await
+        .unwrap();
+    assert_eq!(v3.len(), 4);
+    assert!(v3.windows(2).all(|w| w[0].fee <= w[1].fee));
+
+    let v3_500 = storage
+        .get_pools_by_token(addr(1), addr(2), 1, Some("uniswap_v3"), Some(500))
+        .await
+        .unwrap();
+    assert_eq!(v3_500.len(), 1);
+    assert_pool_eq(&v3_500[0], &pool(11, "uniswap_v3", 1, 1, 2, 500));
+
+    let fee_3000 = storage
+        .get_pools_by_token(addr(1), addr(2), 1, None, Some(3000))
+        .await
+        .unwrap();
+    assert_eq!(fee_3000.len(), 3);
+
+    assert!(storage
+        .get_pools_by_token(addr(2), addr(3), 1, None, None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn pools_containing_token(storage: Arc<dyn Storage>) {
+    storage
+        .save_pool(&pool(10, "uniswap_v3", 1, 1, 2, 500))
+        .await
+        .unwrap();
+    storage
+        .save_pool(&pool(11, "uniswap_v2", 1, 3, 1, 3000))
+        .await
+        .unwrap();
+    storage
+        .save_pool(&pool(12, "uniswap_v2", 1, 2, 3, 3000))
+        .await
+        .unwrap();
+
+    let mut with_1: Vec<_> = storage
+        .get_pools_containing_token(addr(1), 1, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|p| p.address)
+        .collect();
+    with_1.sort();
+    assert_eq!(with_1, vec![addr(10), addr(11)]);
+
+    let v2 = storage
+        .get_pools_containing_token(addr(1), 1, Some("uniswap_v2"))
+        .await
+        .unwrap();
+    assert_eq!(v2.len(), 1);
+    assert_eq!(v2[0].address, addr(11));
+
+    assert!(storage
+        .get_pools_containing_token(addr(4), 1, None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn dex_pagination(storage: Arc<dyn Storage>) {
+    for n in 0..5u8 {
+        storage
+            .save_pool(&pool(10 + n, "uniswap_v2", 1, 1, 2 + n, 3000))
+            .await
+            .unwrap();
+    }
+    storage
+        .save_pool(&pool(30, "uniswap_v3", 1, 1, 2, 500))
+        .await
+        .unwrap();
+
+    let mut seen = Vec::new();
+    for (offset, expected_len) in [(0, 2), (2, 2), (4, 1), (6, 0)] {
+        let page = storage
+            .get_pools_by_dex_paginated("uniswap_v2", 1, 2, offset)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), expected_len, "page at offset {offset}");
+        assert!(page.iter().all(|p| p.dex == "uniswap_v2"));
+        seen.extend(page.into_iter().map(|p| p.address));
+    }
+
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen.len(), 5, "pages must be disjoint and cover every pool");
+
+    let all = storage.get_all_pools_paginated(1, 100, 0).await.unwrap();
+    assert_eq!(all.len(), 6);
+    let first_page = storage.get_all_pools_paginated(1, 4, 0).await.unwrap();
+    let second_page = storage.get_all_pools_paginated(1, 4, 4).await.unwrap();
+    assert_eq!(first_page.len() + second_page.len(), 6);
+}
+
+async fn latest_distribution_wins(storage: Arc<dyn Storage>) {
+    let now = Utc::now();
+    let older = distribution(1, 2, "uniswap_v3", 1, 100.0, now - Duration::minutes(10));
+    let newer = distribution(1, 2, "uniswap_v3", 1, 200.0, now);
+    storage.save_token(&older.token0).await.unwrap();
+    storage.save_token(&older.token1).await.unwrap();
+
+    assert!(storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap()
+        .is_empty());
+
+    storage.save_liquidity_distribution(&older).await.unwrap();
+    storage.save_liquidity_distribution(&newer).await.unwrap();
+    let latest = storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(latest.current_price, 200.0);
+    assert_eq!(latest.price_levels.len(), 1);
+
+    // A late-arriving older snapshot must not replace the newer one
+    storage.save_liquidity_distribution(&older).await.unwrap();
+    let latest = storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(latest.current_price, 200.0);
+
+    // Distributions are keyed by DEX and pair order
+    assert!(storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v2", 1)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(storage
+        .get_liquidity_distributions(addr(2), addr(1), "uniswap_v3", 1)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn fee_tiers_keep_their_own_distribution(storage: Arc<dyn Storage>) {
+    let now = Utc::now();
+    // Saved before distributions were kept per pool
+    let unattributed = distribution(1, 2, "uniswap_v3", 1, 100.0, now - Duration::minutes(10));
+    storage.save_token(&unattributed.token0).await.unwrap();
+    storage.save_token(&unattributed.token1).await.unwrap();
+    storage.save_liquidity_distribution(&unattributed).await.unwrap();
+
+    // The first per-pool snapshot replaces the pair's unattributed one
+    let mut low_fee = distribution(1, 2, "uniswap_v3", 1, 200.0, now);
+    low_fee.pool = Some(addr(10));
+    storage.save_liquidity_distribution(&low_fee).await.unwrap();
+    let mut high_fee = distribution(1, 2, "uniswap_v3", 1, 201.0, now);
+    high_fee.pool = Some(addr(11));
+    storage.save_liquidity_distribution(&high_fee).await.unwrap();
+
+    let latest = storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap();
+    let pools: Vec<_> = latest.iter().map(|d| (d.pool, d.current_price)).collect();
+    assert_eq!(pools, vec![(Some(addr(10)), 200.0), (Some(addr(11)), 201.0)]);
+    assert_eq!(storage.list_liquidity_distributions(Some(1)).await.unwrap().len(), 2);
+
+    // Each pool keeps its own latest snapshot
+    low_fee.current_price = 202.0;
+    low_fee.timestamp = now + Duration::minutes(1);
+    storage.save_liquidity_distribution(&low_fee).await.unwrap();
+    let prices: Vec<f64> = storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap()
+        .iter()
+        .map(|d| d.current_price)
+        .collect();
+    assert_eq!(prices, vec![202.0, 201.0]);
+
+    let (from, to) = (now - Duration::hours(1), now + Duration::hours(1));
+    let history = storage
+        .get_liquidity_history(addr(1), addr(2), "uniswap_v3", 1, from, to)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 4);
+}
+
+async fn chains_are_isolated(storage: Arc<dyn Storage>) {
+    storage
+        .save_pool(&pool(10, "uniswap_v3", 1, 1, 2, 500))
+        .await
+        .unwrap();
+    storage
+        .save_pool(&pool(11, "uniswap_v3", 42161, 3, 4, 500))
+        .await
+        .unwrap();
+    storage
+        .save_liquidity_distribution(&distribution(1, 2, "uniswap_v3", 1, 100.0, Utc::now()))
+        .await
+        .unwrap();
+
+    assert!(storage.get_token(addr(1), 42161).await.unwrap().is_none());
+    assert!(storage.get_token(addr(3), 1).await.unwrap().is_none());
+
+    let mainnet = storage.get_pools_by_dex("uniswap_v3", 1).await.unwrap();
+    assert_eq!(mainnet.len(), 1);
+    assert_eq!(mainnet[0].address, addr(10));
+    let arbitrum = storage.get_pools_by_dex("uniswap_v3", 42161).await.unwrap();
+    assert_eq!(arbitrum.len(), 1);
+    assert_eq!(arbitrum[0].address, addr(11));
+
+    assert_eq!(
+        storage
+            .get_all_pools_paginated(1, 100, 0)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(storage
+        .get_pools_by_token(addr(1), addr(2), 42161, None, None)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(storage
+        .get_pools_containing_token(addr(1), 42161, None)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 42161)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn one_address_on_two_chains(storage: Arc<dyn Storage>) {
+    // CREATE2 deployments and bridged tokens reuse addresses across chains.
+    let mut bridged = token(1, 42161);
+    bridged.symbol = "TK1.e".to_string();
+    storage.save_token(&token(1, 1)).await.unwrap();
+    storage.save_token(&bridged).await.unwrap();
+    assert_token_eq(&storage.get_token(addr(1), 1).await.unwrap().unwrap(), &token(1, 1));
+    assert_token_eq(&storage.get_token(addr(1), 42161).await.unwrap().unwrap(), &bridged);
+    assert_eq!(storage.list_tokens(None).await.unwrap().len(), 2);
+
+    let mainnet = pool(10, "uniswap_v3", 1, 1, 2, 500);
+    let arbitrum = pool(10, "uniswap_v3", 42161, 1, 2, 3000);
+    storage.save_pool(&mainnet).await.unwrap();
+    storage.save_pool(&arbitrum).await.unwrap();
+    let on_mainnet = storage.get_pools_by_dex("uniswap_v3", 1).await.unwrap();
+    assert_eq!(on_mainnet.len(), 1);
+    assert_pool_eq(&on_mainnet[0], &mainnet);
+    let on_arbitrum = storage.get_pools_by_dex("uniswap_v3", 42161).await.unwrap();
+    assert_eq!(on_arbitrum.len(), 1);
+    assert_pool_eq(&on_arbitrum[0], &arbitrum);
+    assert_eq!(storage.list_pools(None, None).await.unwrap().len(), 2);
+}
+
+async fn pool_lookup_is_per_chain(storage: Arc<dyn Storage>) {
+    let mainnet = pool(10, "uniswap_v3", 1, 1, 2, 500);
+    let arbitrum = pool(10, "uniswap_v3", 42161, 1, 2, 3000);
+    // Saved in both orders, so neither chain's row is simply the first or the last one
+    storage.save_pool(&arbitrum).await.unwrap();
+    storage.save_pool(&mainnet).await.unwrap();
+    storage.save_pool(&pool(11, "uniswap_v3", 42161, 1, 2, 500)).await.unwrap();
+
+    assert_pool_eq(&storage.get_pool(addr(10), 1).await.unwrap().unwrap(), &mainnet);
+    assert_pool_eq(&storage.get_pool(addr(10), 42161).await.unwrap().unwrap(), &arbitrum);
+    assert!(storage.get_pool(addr(10), 10).await.unwrap().is_none());
+    assert!(storage.get_pool(addr(11), 1).await.unwrap().is_none());
+}
+
+async fn compaction_downsamples_history(storage: Arc<dyn Storage>) {
+    // Midnight, so hour and day buckets line up with the offsets below
+    let now = DateTime::from_timestamp(1_704_844_800, 0).unwrap();
+    let policy = RetentionConfig {
+        enabled: true,
+        raw_hours: 1,
+        hourly_days: 1,
+        compaction_interval_secs: 60,
+    };
+    let v3 = |price: f64, ts: DateTime<Utc>| distribution(1, 2, "uniswap_v3", 1, price, ts);
+    let snapshots = [
+        // Raw tier: all kept
+        v3(1.0, now - Duration::minutes(10)),
+        v3(2.0, now - Duration::minutes(20)),
+        // Hourly tier: one hour bucket, only the latest survives
+        v3(3.0, now - Duration::hours(3) + Duration::minutes(5)),
+        v3(4.0, now - Duration::hours(3) + Duration::minutes(50)),
+        v3(5.0, now - Duration::hours(3) + Duration::minutes(30)),
+        // Daily tier: one day bucket, only the latest survives
+        v3(6.0, now - Duration::days(3) + Duration::hours(1)),
+        v3(7.0, now - Duration::days(3) + Duration::hours(10)),
+        v3(8.0, now - Duration::days(3) + Duration::hours(2)),
+        // Another DEX is a separate series with its own buckets
+        distribution(1, 2, "uniswap_v2", 1, 9.0, now - Duration::hours(3) + Duration::minutes(5)),
+    ];
+    storage.save_token(&snapshots[0].token0).await.unwrap();
+    storage.save_token(&snapshots[0].token1).await.unwrap();
+    for snapshot in &snapshots {
+        storage.save_liquidity_distribution(snapshot).await.unwrap();
+    }
+
+    let stats = storage.get_snapshot_stats().await.unwrap();
+    assert_eq!(stats.count, 9);
+    assert_eq!(stats.oldest, Some(now - Duration::days(3) + Duration::hours(1)));
+    assert_eq!(stats.newest, Some(now - Duration::minutes(10)));
+
+    let compaction = storage.compact_snapshots(&policy, now).await.unwrap();
+    assert_eq!(compaction.examined, 9);
+    assert_eq!(compaction.deleted, 4);
+
+    let history = storage
+        .get_liquidity_history(addr(1), addr(2), "uniswap_v3", 1, now - Duration::days(7), now)
+        .await
+        .unwrap();
+    let prices: Vec<f64> = history.iter().map(|d| d.current_price).collect();
+    assert_eq!(prices, vec![7.0, 4.0, 2.0, 1.0]);
+
+    let stats = storage.get_snapshot_stats().await.unwrap();
+    assert_eq!(stats.count, 5);
+    assert_eq!(stats.oldest, Some(now - Duration::days(3) + Duration::hours(10)));
+
+    // The latest snapshot is untouched and a second pass has nothing left to do
+    let latest = storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(latest.current_price, 1.0);
+    assert_eq!(storage.compact_snapshots(&policy, now).await.unwrap().deleted, 0);
+}
+
+async fn listings_filter_by_chain_and_dex(storage: Arc<dyn Storage>) {
+    storage.save_pool(&pool(10, "uniswap_v3", 1, 1, 2, 500)).await.unwrap();
+    storage.save_pool(&pool(11, "uniswap_v2", 1, 1, 3, 3000)).await.unwrap();
+    storage.save_pool(&pool(12, "uniswap_v3", 10, 4, 5, 500)).await.unwrap();
+
+    assert_eq!(storage.list_tokens(None).await.unwrap().len(), 5);
+    let tokens = storage.list_tokens(Some(1)).await.unwrap();
+    let addresses: Vec<Address> = tokens.iter().map(|t| t.address).collect();
+    assert_eq!(addresses, vec![addr(1), addr(2), addr(3)]);
+
+    assert_eq!(storage.list_pools(None, None).await.unwrap().len(), 3);
+    assert_eq!(storage.list_pools(Some(1), None).await.unwrap().len(), 2);
+    let v3 = storage.list_pools(None, Some("uniswap_v3")).await.unwrap();
+    let addresses: Vec<Address> = v3.iter().map(|p| p.address).collect();
+    assert_eq!(addresses, vec![addr(10), addr(12)]);
+
+    let now = Utc::now();
+    for snapshot in [
+        distribution(1, 2, "uniswap_v3", 1, 1.0, now - Duration::hours(2)),
+        distribution(1, 3, "uniswap_v2", 1, 2.0, now - Duration::hours(1)),
+        distribution(4, 5, "uniswap_v3", 10, 3.0, now),
+    ] {
+        storage.save_liquidity_distribution(&snapshot).await.unwrap();
+    }
+    let all = storage
+        .list_liquidity_history(None, None, now - Duration::days(1), now)
+        .await
+        .unwrap();
+    let prices: Vec<f64> = all.iter().map(|d| d.current_price).collect();
+    assert_eq!(prices, vec![1.0, 2.0, 3.0]);
+    let recent_mainnet = storage
+        .list_liquidity_history(Some(1), None, now - Duration::minutes(90), now)
+        .await
+        .unwrap();
+    assert_eq!(recent_mainnet.len(), 1);
+    assert_eq!(recent_mainnet[0].dex, "uniswap_v2");
+}
+
+async fn checkpoints_are_per_stream(storage: Arc<dyn Storage>) {
+    assert_eq!(storage.get_checkpoint(1, "uniswap_v2", "discovery").await.unwrap(), None);
+
+    storage.save_checkpoint(1, "uniswap_v2", "discovery", 100).await.unwrap();
+    storage.save_checkpoint(1, "uniswap_v2", "discovery", 250).await.unwrap();
+    storage.save_checkpoint(1, "uniswap_v3", "discovery", 7).await.unwrap();
+    storage.save_checkpoint(10, "uniswap_v2", "discovery", 9).await.unwrap();
+
+    assert_eq!(storage.get_checkpoint(1, "uniswap_v2", "discovery").await.unwrap(), Some(250));
+    assert_eq!(storage.get_checkpoint(1, "uniswap_v3", "discovery").await.unwrap(), Some(7));
+    assert_eq!(storage.get_checkpoint(10, "uniswap_v2", "discovery").await.unwrap(), Some(9));
+    assert_eq!(storage.get_checkpoint(1, "uniswap_v2", "events").await.unwrap(), None);
+}
+
+async fn rollback_discards_orphaned_snapshots(storage: Arc<dyn Storage>) {
+    let now = Utc::now();
+    for (n, chain_id) in [(1, 1), (2, 1), (3, 1), (4, 1), (1, 10), (2, 10)] {
+        storage.save_token(&token(n, chain_id)).await.unwrap();
+    }
+    for n in 100..=103 {
+        storage.save_block(1, &block(n, 0)).await.unwrap();
+    }
+    storage.save_block(10, &block(103, 0)).await.unwrap();
+    let at = |price: f64, minutes: i64| {
+        distribution(1, 2, "uniswap_v3", 1, price, now - Duration::minutes(minutes))
+    };
+    storage.save_liquidity_distribution_at(&at(100.0, 3), &block(101, 0)).await.unwrap();
+    storage.save_liquidity_distribution_at(&at(102.0, 2), &block(102, 0)).await.unwrap();
+    storage.save_liquidity_distribution_at(&at(103.0, 1), &block(103, 0)).await.unwrap();
+    // A pool whose only snapshot is orphaned disappears entirely
+    let orphan = distribution(3, 4, "uniswap_v2", 1, 5.0, now);
+    storage.save_liquidity_distribution_at(&orphan, &block(103, 0)).await.unwrap();
+    // Other chains are untouched
+    let other = distribution(1, 2, "uniswap_v3", 10, 7.0, now);
+    storage.save_liquidity_distribution_at(&other, &block(103, 0)).await.unwrap();
+    storage.save_checkpoint(1, "uniswap_v3", "events", 103).await.unwrap();
+    storage.save_checkpoint(1, "uniswap_v3", "discovery", 90).await.unwrap();
+
+    let stats = storage.rollback_to(1, 101).await.unwrap();
+    assert_eq!((stats.snapshots, stats.blocks), (3, 2));
+
+    let latest = storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(latest.current_price, 100.0);
+    assert!(storage
+        .get_liquidity_distributions(addr(3), addr(4), "uniswap_v2", 1)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(!storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let blocks = storage.get_recent_blocks(1, 10).await.unwrap();
+    assert_eq!(blocks, vec![block(101, 0), block(100, 0)]);
+    assert_eq!(storage.get_recent_blocks(10, 10).await.unwrap().len(), 1);
+    assert_eq!(storage.get_checkpoint(1, "uniswap_v3", "events").await.unwrap(), Some(101));
+    assert_eq!(storage.get_checkpoint(1, "uniswap_v3", "discovery").await.unwrap(), Some(90));
+}
+
+async fn finalize_marks_confirmed_snapshots(storage: Arc<dyn Storage>) {
+    let now = Utc::now();
+    for n in 1..=4 {
+        storage.save_token(&token(n, 1)).await.unwrap();
+    }
+    for n in 100..=102 {
+        storage.save_block(1, &block(n, 0)).await.unwrap();
+        let snapshot = distribution(1, 2, "uniswap_v3", 1, n as f64, now + Duration::seconds(n as i64));
+        storage.save_liquidity_distribution_at(&snapshot, &block(n, 0)).await.unwrap();
+    }
+    // Snapshots without a block are never rolled back, so they never need finalizing
+    storage
+        .save_liquidity_distribution(&distribution(3, 4, "uniswap_v2", 1, 1.0, now))
+        .await
+        .unwrap();
+
+    assert_eq!(storage.finalize_blocks(1, 101).await.unwrap(), 2);
+    assert_eq!(storage.finalize_blocks(1, 101).await.unwrap(), 0);
+    let stats = storage.get_snapshot_stats().await.unwrap();
+    assert_eq!((stats.count, stats.finalized), (4, 2));
+
+    // Blocks below the finalized one are no longer needed for reorg checks
+    let blocks = storage.get_recent_blocks(1, 10).await.unwrap();
+    assert_eq!(blocks, vec![block(102, 0), block(101, 0)]);
+}
+
+async fn pool_status_tracks_failures(storage: Arc<dyn Storage>) {
+    let at = |secs: i64| DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap();
+    let attempt = |n: u8, chain_id: u64, secs: i64, error: Option<&str>| PoolAttempt {
+        chain_id,
+        dex: "uniswap_v3".to_string(),
+        pool: addr(n),
+        at: at(secs),
+        duration_ms: secs as u64,
+        error: error.map(str::to_string),
+    };
+    storage.record_pool_attempt(&attempt(2, 1, 0, None)).await.unwrap();
+    storage.record_pool_attempt(&attempt(2, 1, 10, Some("rpc timeout"))).await.unwrap();
+    storage.record_pool_attempt(&attempt(2, 1, 20, Some("rpc timeout"))).await.unwrap();
+    storage.record_pool_attempt(&attempt(1, 1, 5, Some("reverted"))).await.unwrap();
+    storage.record_pool_attempt(&attempt(1, 10, 5, None)).await.unwrap();
+
+    let statuses = storage.list_pool_status(Some(1), None).await.unwrap();
+    assert_eq!(statuses.len(), 2);
+    let (never_ok, failing) = (&statuses[0], &statuses[1]);
+    assert_eq!((never_ok.pool, never_ok.last_success), (addr(1), None));
+    assert_eq!(never_ok.error_count, 1);
+    assert_eq!(failing.pool, addr(2));
+    assert_eq!(failing.last_success, Some(at(0)));
+    assert_eq!(failing.last_attempt, at(20));
+    assert_eq!(failing.last_error.as_deref(), Some("rpc timeout"));
+    assert_eq!((failing.error_count, failing.duration_ms), (2, 20));
+
+    // A success resets the count but keeps the last error for context
+    storage.record_pool_attempt(&attempt(2, 1, 30, None)).await.unwrap();
+    let statuses = storage.list_pool_status(Some(1), Some("uniswap_v3")).await.unwrap();
+    assert_eq!(statuses[1].error_count, 0);
+    assert_eq!(statuses[1].last_success, Some(at(30)));
+    assert_eq!(statuses[1].last_error_at, Some(at(20)));
+
+    assert_eq!(storage.list_pool_status(None, None).await.unwrap().len(), 3);
+    assert!(storage.list_pool_status(None, Some("uniswap_v2")).await.unwrap().is_empty());
+}
+
+async fn indexer_runs_newest_first(storage: Arc<dyn Storage>) {
+    assert!(storage.get_indexer_runs(10).await.unwrap().is_empty());
+    for n in 0..3u64 {
+        let run = IndexerRun {
+            started_at: DateTime::from_timestamp(1_700_000_000 + n as i64 * 60, 0).unwrap(),
+            duration_ms: 100 * n,
+            pools: 10,
+            snapshotted: 4,
+            failed: n,
+        };
+        storage.save_indexer_run(&run).await.unwrap();
+    }
+    let runs = storage.get_indexer_runs(2).await.unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!((runs[0].failed, runs[1].failed), (2, 1));
+    assert_eq!(runs[0].started_at.timestamp(), 1_700_000_120);
+}
+
+macro_rules! storage_conformance {
+    ($name:ident, $make:expr) => {
+        mod $name {
+            use super::*;
+
+            fn storage() -> Arc<dyn Storage> {
+                Arc::new($make)
+            }
+
+            #[tokio::test]
+            async fn token_round_trip() {
+                super::token_round_trip(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn pool_round_trip() {
+                super::pool_round_trip(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn pool_keeps_creation_block() {
+                super::pool_keeps_creation_block(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn pools_by_token_spans_fee_tiers() {
+                super::pools_by_token_spans_fee_tiers(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn pools_containing_token() {
+                super::pools_containing_token(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn dex_pagination() {
+                super::dex_pagination(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn latest_distribution_wins() {
+                super::latest_distribution_wins(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn fee_tiers_keep_their_own_distribution() {
+                super::fee_tiers_keep_their_own_distribution(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn chains_are_isolated() {
+                super::chains_are_isolated(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn one_address_on_two_chains() {
+                super::one_address_on_two_chains(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn pool_lookup_is_per_chain() {
+                super::pool_lookup_is_per_chain(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn listings_filter_by_chain_and_dex() {
+                super::listings_filter_by_chain_and_dex(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn compaction_downsamples_history() {
+                super::compaction_downsamples_history(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn checkpoints_are_per_stream() {
+                super::checkpoints_are_per_stream(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn rollback_discards_orphaned_snapshots() {
+                super::rollback_discards_orphaned_snapshots(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn finalize_marks_confirmed_snapshots() {
+                super::finalize_marks_confirmed_snapshots(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn pool_status_tracks_failures() {
+                super::pool_status_tracks_failures(storage()).await;
+            }
+
+            #[tokio::test]
+            async fn indexer_runs_newest_first() {
+                super::indexer_runs_newest_first(storage()).await;
+            }
+        }
+    };
+}
+
+storage_conformance!(memory, MemoryStorage::new());
+storage_conformance!(sqlite, SqliteStorage::new(":memory:").unwrap());
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn sqlite_file_serves_reads_while_writing() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tel.db");
+    let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::new(path.to_str().unwrap()).unwrap());
+
+    let writer = {
+        let storage = storage.clone();
+        tokio::spawn(async move {
+            for n in 0..100u8 {
+                storage
+                    .save_pool(&pool(n, "uniswap_v2", 1, 200, 201, 3000))
+                    .await
+                    .unwrap();
+            }
+        })
+    };
+
+    let readers: Vec<_> = (0..8)
+        .map(|_| {
+            let storage = storage.clone();
+            tokio::spawn(async move {
+                let mut last_seen = 0;
+                for _ in 0..50 {
+                    let seen = storage
+                        .get_pools_by_dex("uniswap_v2", 1)
+                        .await
+                        .unwrap()
+                        .len();
+                    assert!(seen >= last_seen, "readers must never go back in time");
+                    last_seen = seen;
+                }
+            })
+        })
+        .collect();
+
+    writer.await.unwrap();
+    for reader in readers {
+        reader.await.unwrap();
+    }
+    assert_eq!(
+        storage
+            .get_pools_by_dex("uniswap_v2", 1)
+            .await
+            .unwrap()
+            .len(),
+        100
+    );
+}
+
+#[tokio::test]
+async fn sqlite_rekeys_tables_keyed_by_address_or_pair() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("legacy.db");
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tokens (
+                address TEXT PRIMARY KEY, chain_id INTEGER NOT NULL, name TEXT NOT NULL,
+                symbol TEXT NOT NULL, decimals INTEGER NOT NULL
+            );
+            CREATE TABLE pools (
+                address TEXT PRIMARY KEY, chain_id INTEGER NOT NULL, dex TEXT NOT NULL,
+                token0_address TEXT NOT NULL, token1_address TEXT NOT NULL, fee INTEGER
+            );
+            CREATE TABLE liquidity_distributions (
+                token0_address TEXT NOT NULL, token1_address TEXT NOT NULL, dex TEXT NOT NULL,
+                chain_id INTEGER NOT NULL, data TEXT NOT NULL, timestamp INTEGER NOT NULL,
+                PRIMARY KEY (token0_address, token1_address, dex, chain_id)
+            );",
+        )
+        .unwrap();
+        let t = |n: u8| token(n, 1);
+        for t in [t(1), t(2)] {
+            conn.execute(
+                "INSERT INTO tokens VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![t.address.to_string(), 1, t.name, t.symbol, t.decimals],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO pools VALUES (?1, 1, 'uniswap_v3', ?2, ?3, 500)",
+            rusqlite::params![addr(10).to_string(), addr(1).to_string(), addr(2).to_string()],
+        )
+        .unwrap();
+        let pair = distribution(1, 2, "uniswap_v3", 1, 100.0, Utc::now() - Duration::minutes(1));
+        conn.execute(
+            "INSERT INTO liquidity_distributions VALUES (?1, ?2, 'uniswap_v3', 1, ?3, ?4)",
+            rusqlite::params![
+                addr(1).to_string(),
+                addr(2).to_string(),
+                serde_json::to_string(&pair).unwrap(),
+                pair.timestamp.timestamp()
+            ],
+        )
+        .unwrap();
+    }
+
+    let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::new(path.to_str().unwrap()).unwrap());
+    assert_pool_eq(
+        &storage.get_pools_by_dex("uniswap_v3", 1).await.unwrap()[0],
+        &pool(10, "uniswap_v3", 1, 1, 2, 500),
+    );
+    storage.save_pool(&pool(10, "uniswap_v3", 42161, 1, 2, 500)).await.unwrap();
+    assert!(storage.get_token(addr(1), 1).await.unwrap().is_some());
+    assert!(storage.get_token(addr(1), 42161).await.unwrap().is_some());
+    assert_eq!(storage.list_pools(None, None).await.unwrap().len(), 2);
+
+    // The pair's snapshot survives until its pool saves one of its own
+    let pair = storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap();
+    assert_eq!(pair.len(), 1);
+    assert_eq!(pair[0].pool, None);
+    let mut pool_snapshot = distribution(1, 2, "uniswap_v3", 1, 101.0, Utc::now());
+    pool_snapshot.pool = Some(addr(10));
+    storage.save_liquidity_distribution(&pool_snapshot).await.unwrap();
+    let pools: Vec<_> = storage
+        .get_liquidity_distributions(addr(1), addr(2), "uniswap_v3", 1)
+        .await
+        .unwrap()
+        .iter()
+        .map(|d| d.pool)
+        .collect();
+    assert_eq!(pools, vec![Some(addr(10))]);
+}
+
+#[tokio::test]
+async fn dry_run_reads_through_and_drops_writes() {
+    let inner: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    inner.save_token(&token(1, 1)).await.unwrap();
+    let dry_run = DryRunStorage::new(inner.clone());
+
+    assert!(dry_run.get_token(addr(1), 1).await.unwrap().is_some());
+    dry_run.save_token(&token(2, 1)).await.unwrap();
+    dry_run.save_pool(&pool(3, "uniswap_v2", 1, 1, 2, 3000)).await.unwrap();
+    dry_run.save_checkpoint(1, "uniswap_v2", "discovery", 10).await.unwrap();
+
+    assert_eq!(dry_run.skipped_writes(), 3);
+    assert!(inner.get_token(addr(2), 1).await.unwrap().is_none());
+    assert!(inner.get_pool(addr(3), 1).await.unwrap().is_none());
+    assert_eq!(inner.get_checkpoint(1, "uniswap_v2", "discovery").await.unwrap(), None);
+}
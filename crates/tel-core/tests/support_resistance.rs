@@ -0,0 +1,56 @@
+use chrono::Utc;
+use tel_core::core::support_resistance::identify_support_resistance;
+use tel_core::models::{LevelType, PriceLiquidity, Side};
+
+mod common;
+use common::token;
+
+/// A one-unit-wide level centred on `price`.
+fn level(price: f64, token0: f64, token1: f64) -> PriceLiquidity {
+    PriceLiquidity {
+        side: Side::Buy,
+        lower_price: price - 0.5,
+        upper_price: price + 0.5,
+        token0_liquidity: token0,
+        token1_liquidity: token1,
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn peaks_are_ranked_and_sided_by_spot() {
+    let levels = vec![
+        level(104.0, 1.0, 0.0),
+        level(95.0, 0.0, 500.0),
+        level(96.0, 0.0, 100.0),
+        level(97.0, 0.0, 200.0),
+        level(100.0, 0.25, 25.0),
+        level(103.0, 3.0, 0.0),
+    ];
+
+    let found = identify_support_resistance(&levels, 100.0, Some(2.0), &token(1), &token(2), 0.0);
+    let summary: Vec<(f64, LevelType)> = found.iter().map(|l| (l.price, l.level_type)).collect();
+    assert_eq!(
+        summary,
+        vec![
+            (95.0, LevelType::Support),
+            (103.0, LevelType::Resistance),
+            (97.0, LevelType::Support),
+        ]
+    );
+    assert_eq!(found[0].strength, 1.0);
+    assert_eq!(found[0].liquidity_usd, Some(1000.0));
+    assert!((found[1].strength - 0.618).abs() < 1e-9);
+
+    let strong = identify_support_resistance(&levels, 100.0, None, &token(1), &token(2), 0.5);
+    assert_eq!(strong.len(), 2);
+    assert_eq!(strong[0].liquidity_usd, None);
+}
+
+#[test]
+fn level_at_spot_is_neutral() {
+    let levels = vec![level(99.0, 0.0, 10.0), level(100.0, 1.0, 100.0), level(101.0, 0.1, 0.0)];
+    let found = identify_support_resistance(&levels, 100.2, None, &token(1), &token(2), 0.0);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].level_type, LevelType::Neutral);
+}
@@ -0,0 +1,135 @@
+//! Merging of per-DEX distributions into the liquidity walls of a pair.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tel_core::config::WallsConfig;
+use tel_core::core::bucketing::Grid;
+use tel_core::core::liquidity::{detect_walls, merge_walls};
+use tel_core::models::{LiquidityWall, Side};
+use tel_core::storage::{get_pair_distributions, MemoryStorage, Storage};
+
+mod common;
+use common::{distribution, level, token};
+
+#[test]
+fn walls_sum_every_dex_on_one_grid() {
+    let v3 = distribution(
+        "uniswap_v3",
+        100.0,
+        vec![
+            level(Side::Buy, 97.9, 98.1, 0.0, 300.0),
+            level(Side::Buy, 98.9, 99.1, 0.0, 100.0),
+            level(Side::Sell, 101.9, 102.1, 2.0, 0.0),
+        ],
+    );
+    let v2 = distribution(
+        "uniswap_v2",
+        100.0,
+        vec![
+            level(Side::Buy, 97.8, 98.2, 0.0, 200.0),
+            level(Side::Sell, 101.8, 102.2, 1.0, 0.0),
+        ],
+    );
+
+    let walls = merge_walls(&[v3, v2], Grid::Percent(0.01), None);
+    assert!((walls.price - 100.0).abs() < 1e-9);
+
+    let prices: Vec<f64> = walls.buy_walls.iter().map(|w| w.price_lower).collect();
+    assert_eq!(prices.len(), 2);
+    assert!((prices[0] - 97.5).abs() < 1e-9 && (prices[1] - 98.5).abs() < 1e-9);
+    let deepest = &walls.buy_walls[0];
+    assert_eq!(deepest.liquidity_value, 500.0);
+    assert_eq!(deepest.dex_sources["uniswap_v3"], 300.0);
+    assert_eq!(deepest.dex_sources["uniswap_v2"], 200.0);
+
+    let [sell] = &walls.sell_walls_in_wall_price[..] else {
+        panic!("expected one sell wall, got {:?}", walls.sell_walls_in_wall_price);
+    };
+    assert!((sell.liquidity_value - 306.0).abs() < 1e-9);
+    assert!((sell.dex_sources["uniswap_v3"] - 204.0).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn reversed_pools_are_inverted() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let mut stored = distribution(
+        "uniswap_v2",
+        4.0,
+        vec![
+            level(Side::Buy, 3.0, 4.0, 0.0, 8.0),
+            level(Side::Sell, 4.0, 5.0, 2.0, 0.0),
+        ],
+    );
+    stored.token0 = token(2);
+    stored.token1 = token(1);
+    for t in [token(1), token(2)] {
+        storage.save_token(&t).await.unwrap();
+    }
+    storage.save_liquidity_distribution(&stored).await.unwrap();
+
+    let pair = get_pair_distributions(storage, token(1).address, token(2).address, "uniswap_v2", 1)
+        .await
+        .unwrap()
+        .pop()
+        .expect("stored the other way round");
+    assert_eq!(pair.token0.address, token(1).address);
+    assert_eq!(pair.current_price, 0.25);
+
+    let bid = &pair.price_levels[1];
+    assert_eq!(bid.side, Side::Buy);
+    assert_eq!((bid.lower_price, bid.upper_price), (0.2, 0.25));
+    assert_eq!(bid.token1_liquidity, 2.0);
+    let ask = &pair.price_levels[0];
+    assert_eq!(ask.side, Side::Sell);
+    assert_eq!(ask.token0_liquidity, 8.0);
+}
+
+/// One-unit-wide buckets starting at price 90, all from one DEX.
+fn buckets(values: &[f64]) -> Vec<LiquidityWall> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| LiquidityWall {
+            price_lower: 90.0 + i as f64,
+            price_upper: 91.0 + i as f64,
+            liquidity_value: value,
+            dex_sources: HashMap::from([("uniswap_v3".to_string(), value)]),
+            liquidity_usd: None,
+        })
+        .collect()
+}
+
+#[test]
+fn outliers_become_walls_ranked_by_size() {
+    let mut values = vec![1.0; 30];
+    values[10] = 10.0;
+    values[11] = 12.0;
+    values[22] = 4.0;
+    let config = WallsConfig::default();
+
+    let walls = detect_walls(&buckets(&values), &config);
+    let found: Vec<(f64, f64, f64)> = walls
+        .iter()
+        .map(|w| (w.price_lower, w.price_upper, w.liquidity_value))
+        .collect();
+    assert_eq!(found, vec![(100.0, 102.0, 22.0), (112.0, 113.0, 4.0)]);
+    assert_eq!(walls[0].dex_sources["uniswap_v3"], 22.0);
+
+    let strict = WallsConfig {
+        min_prominence: 0.5,
+        ..config.clone()
+    };
+    assert_eq!(detect_walls(&buckets(&values), &strict).len(), 1);
+    let top = WallsConfig {
+        max_walls: 1,
+        ..config.clone()
+    };
+    assert_eq!(detect_walls(&buckets(&values), &top)[0].liquidity_value, 22.0);
+}
+
+#[test]
+fn flat_liquidity_has_no_walls() {
+    let config = WallsConfig::default();
+    assert!(detect_walls(&buckets(&[5.0; 20]), &config).is_empty());
+    assert!(detect_walls(&[], &config).is_empty());
+}
@@ -0,0 +1,154 @@
+use tel_core::dexes::discovery::BlockScan;
+use tel_core::dexes::state::PoolState;
+use tel_core::dexes::DexProtocol;
+use tel_core::error::Error;
+use tel_core::models::Pool;
+use tracing::{debug, info, warn};
+
+/// Blocks a backfill writes snapshots at: `from`, then every `every` blocks through `to`.
+#[derive(Debug, Clone)]
+pub struct SnapshotSchedule {
+    next: u64,
+    to: u64,
+    every: u64,
+}
+
+impl SnapshotSchedule {
+    pub fn new(from: u64, to: u64, every: u64) -> Self {
+        Self {
+            next: from,
+            to,
+            every: every.max(1),
+        }
+    }
+
+    /// Takes the next scheduled block below `block`.
+    ///
+    /// Once every log before `block` has been applied, the state at the returned block is final.
+    pub fn pop_before(&mut self, block: u64) -> Option<u64> {
+        if self.next >= block || self.next > self.to {
+            return None;
+        }
+        let due = self.next;
+        self.next = self.next.saturating_add(self.every);
+        Some(due)
+    }
+}
+
+/// Outcome of a backfill run.
+#[derive(Debug, Clone, Default)]
+pub struct BackfillSummary {
+    pub snapshots: usize,
+    pub first_block: Option<u64>,
+    pub last_block: Option<u64>,
+}
+
+impl BackfillSummary {
+    fn record(&mut self, block: u64) {
+        self.snapshots += 1;
+        self.first_block.get_or_insert(block);
+        self.last_block = Some(block);
+    }
+}
+
+/// Writes historical snapshots of `pool` every `every` blocks from `from` through `to`.
+///
+/// DEXes that can rebuild state from logs (V3) replay every log since the pool's creation
+/// block; the others (V2) read the state at each snapshot block, which needs an archive node.
+/// Snapshots are tagged with their block and stamped with its timestamp, so they land in the
+/// history without replacing a newer latest distribution and are covered by reorg rollback
+/// and finalization. `to` defaults to the chain head and `from` to the creation block.
+pub async fn backfill_pool(
+    dex: &dyn DexProtocol,
+    pool: &Pool,
+    from: Option<u64>,
+    to: Option<u64>,
+    every: u64,
+    log_range: u64,
+) -> Result<BackfillSummary, Error> {
+    let head = match to {
+        Some(block) => block,
+        None => dex.provider().block_number().await?,
+    };
+    let creation = match pool.creation_block {
+        0 => dex.deployment_block(),
+        block => block,
+    };
+    let from = from.unwrap_or(creation).max(creation);
+    let mut schedule = SnapshotSchedule::new(from, head, every);
+    let mut summary = BackfillSummary::default();
+    info!(
+        "Backfilling pool {} on {} from block {} to {} every {} blocks",
+        pool.address, pool.dex, from, head, every
+    );
+
+    match dex.initial_pool_state(pool).await {
+        Ok(mut state) => {
+            let mut scan = BlockScan::new(creation, head, log_range);
+            while let Some((start, end)) = scan.next_range() {
+                match dex.get_pool_logs(&[pool.address], start, end).await {
+                    Ok(mut logs) => {
+                        logs.sort_by_key(|l| (l.block_number, l.log_index));
+                        for log in logs {
+                            while let Some(block) = schedule.pop_before(log.block_number) {
+                                write_snapshot(dex, pool, block, &state).await?;
+                                summary.record(block);
+                            }
+                            state.apply(&log.event);
+                        }
+                        while let Some(block) = schedule.pop_before(end + 1) {
+                            write_snapshot(dex, pool, block, &state).await?;
+                            summary.record(block);
+                        }
+                        scan.advance();
+                    }
+                    Err(Error::ProviderError(e)) if scan.split() => {
+                        warn!(
+                            "getLogs {}..{} rejected, splitting range: {}",
+                            start, end, e
+                        );
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Err(Error::NotImplemented) => {
+            while let Some(block) = schedule.pop_before(head + 1) {
+                let state = dex.load_pool_state_at(pool, block).await?;
+                write_snapshot(dex, pool, block, &state).await?;
+                summary.record(block);
+            }
+        }
+        Err(e) => return Err(e),
+    }
+
+    info!(
+        "Backfilled {} snapshots for pool {}",
+        summary.snapshots, pool.address
+    );
+    Ok(summary)
+}
+
+async fn write_snapshot(
+    dex: &dyn DexProtocol,
+    pool: &Pool,
+    block: u64,
+    state: &PoolState,
+) -> Result<(), Error> {
+    let provider = dex.provider();
+    let block_ref = provider
+        .block_at(block)
+        .await?
+        .ok_or_else(|| Error::ProviderError(format!("block {block} not found")))?;
+    let timestamp = provider.block_timestamp(block).await?;
+    let mut distribution = dex.distribution_from_state(pool, state)?;
+    distribution.timestamp = timestamp;
+    for level in &mut distribution.price_levels {
+        level.timestamp = timestamp;
+    }
+    dex.storage()
+        .save_liquidity_distribution_at(&distribution, &block_ref)
+        .await?;
+    debug!("Wrote snapshot of pool {} at block {}", pool.address, block);
+    Ok(())
+}
@@ -0,0 +1,263 @@
+use crate::status::record_attempt;
+use alloy_primitives::Address;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tel_core::dexes::discovery::BlockScan;
+use tel_core::dexes::state::{PoolEvent, PoolState, EVENTS_STREAM};
+use tel_core::dexes::DexProtocol;
+use tel_core::error::Error;
+use tel_core::models::{BlockRef, Pool};
+use tel_core::storage::Storage;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+struct TrackedState {
+    state: PoolState,
+    /// Block the state is current as of; earlier logs are already reflected in it.
+    block: u64,
+    /// Price of the last distribution built from the state.
+    price: Option<f64>,
+}
+
+/// Outcome of a [`PoolTracker::sync`].
+#[derive(Debug, Default)]
+pub struct SyncOutcome {
+    /// Pools brought up to the head, with their current price.
+    pub synced: Vec<(Pool, f64)>,
+    /// Pools whose DEX cannot be followed through events, which need a full snapshot instead.
+    pub snapshot_only: Vec<Pool>,
+}
+
+/// Keeps pool states current from their logs and persists a distribution only when a pool
+/// actually changed.
+///
+/// Pools are read in full the first time they are seen, after a log touches state the read
+/// did not cover, and again every `full_refresh`, which also checks the event-derived state
+/// against the chain.
+pub struct PoolTracker {
+    storage: Arc<dyn Storage>,
+    log_range: u64,
+    full_refresh: Duration,
+    states: Mutex<HashMap<(u64, Address), TrackedState>>,
+    last_full_refresh: Mutex<Option<Instant>>,
+    /// Swaps applied per pool since the last `take_swaps`.
+    swaps: Mutex<HashMap<(u64, Address), u64>>,
+}
+
+impl PoolTracker {
+    pub fn new(storage: Arc<dyn Storage>, log_range: u64, full_refresh: Duration) -> Self {
+        Self {
+            storage,
+            log_range,
+            full_refresh,
+            states: Mutex::new(HashMap::new()),
+            last_full_refresh: Mutex::new(None),
+            swaps: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether this cycle should re-read every tracked pool; starts a new period if so.
+    pub async fn full_refresh_due(&self) -> bool {
+        let mut last = self.last_full_refresh.lock().await;
+        match *last {
+            Some(at) if at.elapsed() < self.full_refresh => false,
+            _ => {
+                *last = Some(Instant::now());
+                true
+            }
+        }
+    }
+
+    /// Returns and resets the number of swaps seen in each pool's logs.
+    ///
+    /// V2 `Sync` logs count as swaps; they are also emitted on mints and burns.
+    pub async fn take_swaps(&self) -> HashMap<(u64, Address), u64> {
+        std::mem::take(&mut *self.swaps.lock().await)
+    }
+
+    /// Drops every tracked state on `chain_id`, so its pools are re-read on the next sync.
+    ///
+    /// Called after a reorg, since the states may include logs from orphaned blocks.
+    pub async fn forget_chain(&self, chain_id: u64) {
+        self.states.lock().await.retain(|(chain, _), _| *chain != chain_id);
+    }
+
+    /// Brings the given pools of `dex` up to the chain head.
+    ///
+    /// Known pools are advanced with the logs emitted since they were last synced, so pools
+    /// left out of a sync catch up on the next one; new pools, or all of them when
+    /// `full_refresh` is set, are read from the chain as of `head_block`. Changed
+    /// distributions are recorded at `head_block`.
+    pub async fn sync(
+        &self,
+        dex: &dyn DexProtocol,
+        pools: &[Pool],
+        full_refresh: bool,
+        head_block: &BlockRef,
+    ) -> Result<SyncOutcome, Error> {
+        let started = Instant::now();
+        let (chain_id, name) = (dex.chain_id(), dex.name());
+        let head = head_block.number;
+        let mut states = self.states.lock().await;
+        let mut changed = HashSet::new();
+
+        let known: Vec<Address> = pools
+            .iter()
+            .filter(|p| states.contains_key(&(chain_id, p.address)))
+            .map(|p| p.address)
+            .collect();
+        if !known.is_empty() && !full_refresh {
+            let from = known
+                .iter()
+                .map(|a| states[&(chain_id, *a)].block)
+                .min()
+                .unwrap_or(head)
+                + 1;
+
+            let mut scan = BlockScan::new(from, head, self.log_range);
+            while let Some((from, to)) = scan.next_range() {
+                match dex.get_pool_logs(&known, from, to).await {
+                    Ok(mut logs) => {
+                        logs.sort_by_key(|l| (l.block_number, l.log_index));
+                        let mut swaps = self.swaps.lock().await;
+                        for log in logs {
+                            let Some(tracked) = states.get_mut(&(chain_id, log.pool)) else {
+                                continue;
+                            };
+                            if log.block_number <= tracked.block {
+                                continue;
+                            }
+                            if matches!(log.event, PoolEvent::Swap { .. } | PoolEvent::Sync { .. }) {
+                                *swaps.entry((chain_id, log.pool)).or_default() += 1;
+                            }
+                            if !tracked.state.covers(&log.event) {
+                                // Read again below; its later logs are skipped as untracked.
+                                debug!(
+                                    "Pool {} on {} moved past the ticks it was read with, reloading",
+                                    log.pool, name
+                                );
+                                states.remove(&(chain_id, log.pool));
+                                changed.remove(&log.pool);
+                                continue;
+                            }
+                            if tracked.state.apply(&log.event) {
+                                changed.insert(log.pool);
+                            }
+                        }
+                        drop(swaps);
+                        for address in &known {
+                            if let Some(tracked) = states.get_mut(&(chain_id, *address)) {
+                                tracked.block = tracked.block.max(to);
+                            }
+                        }
+                        self.storage
+                            .save_checkpoint(chain_id, name, EVENTS_STREAM, to)
+                            .await?;
+                        scan.advance();
+                    }
+                    Err(Error::ProviderError(e)) if scan.split() => {
+                        warn!(
+                            "getLogs {}..{} rejected for {}, splitting range: {}",
+                            from, to, name, e
+                        );
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            debug!(
+                "Applied logs up to block {} for {} {} pools",
+                head,
+                known.len(),
+                name
+            );
+        }
+
+        let mut snapshot_only = Vec::new();
+        let mut failed = HashMap::new();
+        for pool in pools {
+            let key = (chain_id, pool.address);
+            if !full_refresh && states.contains_key(&key) {
+                continue;
+            }
+            // Read at `head`, which the logs above were applied up to.
+            let state = match dex.load_pool_state_at(pool, head).await {
+                Ok(state) => state,
+                Err(Error::NotImplemented) => {
+                    snapshot_only.push(pool.clone());
+                    continue;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to read state of pool {} on {}: {}",
+                        pool.address, name, e
+                    );
+                    failed.insert(pool.address, e);
+                    continue;
+                }
+            };
+            match states.get(&key) {
+                Some(tracked) if tracked.state.consistent_with(&state) => {}
+                Some(_) => {
+                    warn!(
+                        "Pool {} on {} drifted from its event-derived state, resyncing",
+                        pool.address, name
+                    );
+                    changed.insert(pool.address);
+                }
+                None => {
+                    changed.insert(pool.address);
+                }
+            }
+            let price = states.get(&key).and_then(|t| t.price);
+            states.insert(
+                key,
+                TrackedState {
+                    state,
+                    block: head,
+                    price,
+                },
+            );
+        }
+
+        let mut persisted = 0;
+        for pool in pools.iter().filter(|p| changed.contains(&p.address)) {
+            let tracked = states.get_mut(&(chain_id, pool.address)).unwrap();
+            match dex.distribution_from_state(pool, &tracked.state) {
+                Ok(distribution) => {
+                    self.storage
+                        .save_liquidity_distribution_at(&distribution, head_block)
+                        .await?;
+                    tracked.price = Some(distribution.current_price);
+                    persisted += 1;
+                }
+                Err(e) => warn!(
+                    "Failed to build distribution for pool {}: {}",
+                    pool.address, e
+                ),
+            }
+        }
+        let snapshot_addresses: HashSet<Address> = snapshot_only.iter().map(|p| p.address).collect();
+        for pool in pools.iter().filter(|p| !snapshot_addresses.contains(&p.address)) {
+            record_attempt(self.storage.as_ref(), pool, started, failed.get(&pool.address)).await;
+        }
+        info!(
+            "Synced {} {} pools to block {}: {} changed",
+            pools.len() - snapshot_only.len(),
+            name,
+            head,
+            persisted
+        );
+        let synced = pools
+            .iter()
+            .filter_map(|pool| {
+                let price = states.get(&(chain_id, pool.address))?.price?;
+                Some((pool.clone(), price))
+            })
+            .collect();
+        Ok(SyncOutcome {
+            synced,
+            snapshot_only,
+        })
+    }
+}
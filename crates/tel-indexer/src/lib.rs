@@ -1,79 +1,57 @@
 use crate::storage::Storage;
 use alloy_primitives::Address;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use tel_core::config::Config;
+use std::time::{Duration, Instant};
+use tel_core::config::{Config, IndexerMode};
+use tel_core::core::pricing::PriceOracle;
+use tel_core::dexes::discovery::discover_pools;
 use tel_core::dexes::{get_dex_by_name, DexProtocol};
 use tel_core::error::Error;
-use tel_core::models::{LiquidityDistribution, Pool, Token};
+use tel_core::models::{BlockRef, IndexerRun, IndexerStatus, LiquidityDistribution, Pool};
 use tel_core::providers::ProviderManager;
 use tel_core::storage;
-use tel_core::storage::SqliteStorage;
+use tel_core::storage::export::{export, ExportFilter, ExportFormat, ExportSummary};
+use tel_core::storage::{estimate_pool_tvl_usd, indexer_status, DryRunStorage, SqliteStorage};
+use tel_core::utils::shutdown_signal;
 use tokio::time;
 use tracing::{debug, error, info, warn};
+use backfill::{backfill_pool, BackfillSummary};
+use futures::future::join_all;
+use events::PoolTracker;
+use schedule::RefreshScheduler;
+use status::record_attempt;
+use watchlist::Watchlist;
+use workers::{run_bounded, CycleStats};
+
+pub mod backfill;
+pub mod events;
+pub mod reorg;
+pub mod schedule;
+pub mod status;
+pub mod watchlist;
+pub mod workers;
 
 pub struct Indexer {
     config: Config,
     storage: Arc<dyn Storage>,
     provider_manager: Arc<ProviderManager>,
-    dexes: HashMap<String, Box<dyn DexProtocol>>,
+    /// Enabled DEXes by chain ID and name; the same DEX can run on several chains.
+    dexes: HashMap<(u64, String), Box<dyn DexProtocol>>,
+    tracker: PoolTracker,
+    /// USD prices for pool TVL, routed through the stored snapshots.
+    prices: PriceOracle,
+    /// Present when `[scheduler]` is enabled; otherwise every pool is refreshed each cycle.
+    scheduler: Option<RefreshScheduler>,
+    /// Pools selected in light mode, resolved again each cycle.
+    watchlist: Watchlist,
+    /// Set on shutdown; no new pool refreshes are started once it is.
+    stopping: AtomicBool,
 }
 
-// Only these pools are indexed in light mode!
-pub const LIGHT_MODE_POOLS: [&str; 36] = [
-    "0xb4e16d0168e52d35cacd2c6185b44281ec28c9dc",
-    "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640",
-    "0xCBCdF9626bC03E24f779434178A73a0B4bad62eD",
-    "0x99ac8cA7087fA4A2A1FB6357269965A2014ABc35",
-    "0xe8f7c89C5eFa061e340f2d2F206EC78FD8f7e124",
-    "0x5777d92f208679DB4b9778590Fa3CAB3aC9e2168",
-    "0x4e68Ccd3E89f51C3074ca5072bbAC773960dFa36",
-    "0xC5c134A1f112efA96003f8559Dba6fAC0BA77692",
-    "0x1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801",
-    "0x9Db9e0e53058C89e5B94e29621a205198648425B",
-    "0x8ad599c3A0ff1De082011EFDDc58f1908eb6e6D8",
-    "0x1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801",
-    "0xD0fC8bA7E267f2bc56044A7715A489d851dC6D78",
-    "0x3470447f3cecffac709d3e783a307790b0208d60",
-    "0xe0554a476a092703abdb3ef35c80e0d76d32939f",
-    "0xa43fe16908251ee70ef74718545e4fe6c5ccec9f",
-    "0x11950d141ecb863f01007add7d1a342041227b58",
-    "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc",
-    "0x3139Ffc91B99aa94DA8A2dc13f1fC36F9BDc98eE",
-    "0x12EDE161c702D1494612d19f05992f43aa6A26FB",
-    "0xA478c2975Ab1Ea89e8196811F51A7B7Ade33eB11",
-    "0x07F068ca326a469Fc1d87d85d448990C8cBa7dF9",
-    "0xAE461cA67B15dc8dc81CE7615e0320dA1A9aB8D5",
-    "0xCe407CD7b95B39d3B4d53065E711e713dd5C5999",
-    "0x33C2d48Bc95FB7D0199C5C693e7a9F527145a9Af",
-    "0xB6909B960DbbE7392D405429eB2b3649752b4838",
-    "0x30EB5E15476E6a80F4F3cd8479749b4881DAB1b8",
-    "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc", //USDC/ETH
-    "0xBb2b8038a1640196FbE3e38816F3e67Cba72D940", //WBTC/ETH
-    "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852", //ETH/USDT
-    "0xA478c2975Ab1Ea89e8196811F51A7B7Ade33eB11", //DAI/ETH
-    "0xd3d2E2692501A5c9Ca623199D38826e513033a17", //UNI/ETH
-    "0xd3d2E2692501A5c9Ca623199D38826e513033a17", //DAI/USDC
-    "0xebfb684dd2b01e698ca6c14f10e4f289934a54d6", //UNI/USDC
-    "0x5ac13261c181a9c3938bfe1b649e65d10f98566b", //UNI/USDT
-    "0x004375dff511095cc5a197a54140a24efef3a416", //WBTC/USDC
-];
-
-pub const V2_POOLS: [&str; 10] = [
-    "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc", //USDC/ETH
-    "0xBb2b8038a1640196FbE3e38816F3e67Cba72D940", //WBTC/ETH
-    "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852", //ETH/USDT
-    "0xA478c2975Ab1Ea89e8196811F51A7B7Ade33eB11", //DAI/ETH
-    "0xd3d2E2692501A5c9Ca623199D38826e513033a17", //UNI/ETH
-    "0xd3d2E2692501A5c9Ca623199D38826e513033a17", //DAI/USDC
-    "0xebfb684dd2b01e698ca6c14f10e4f289934a54d6", //UNI/USDC
-    "0x5ac13261c181a9c3938bfe1b649e65d10f98566b", //UNI/USDT
-    "0xA43fe16908251ee70EF74718545e4FE6C5cCEc9f", //PEPE/WETH
-    "0x004375dff511095cc5a197a54140a24efef3a416", //WBTC/USDC
-];
-
 impl Indexer {
     /// Creates a new `Indexer` instance with configured providers and DEX implementations.
     ///
@@ -102,7 +80,7 @@ impl Indexer {
                 if let Some(dex) =
                     get_dex_by_name(&dex_config.name, provider, factory_address, storage.clone())
                 {
-                    dexes.insert(dex_config.name.clone(), dex);
+                    dexes.insert((dex_config.chain_id, dex_config.name.clone()), dex);
                 } else {
                     warn!("DEX implementation not found for: {}", dex_config.name);
                 }
@@ -114,145 +92,444 @@ impl Indexer {
             }
         }
 
+        let tracker = PoolTracker::new(
+            storage.clone(),
+            config.indexer.log_range_blocks,
+            Duration::from_secs(config.indexer.full_refresh_secs),
+        );
+
+        let scheduler = config
+            .scheduler
+            .enabled
+            .then(|| RefreshScheduler::new(&config.scheduler));
+        let prices = PriceOracle::new(storage.clone(), config.pricing.clone());
+        let watchlist = Watchlist::new(config.watchlist.clone());
+
         Ok(Self {
             config,
             storage,
             provider_manager,
             dexes,
+            tracker,
+            prices,
+            scheduler,
+            watchlist,
+            stopping: AtomicBool::new(false),
         })
     }
 
     /// Runs the indexer in continuous mode, periodically fetching and processing pools from all configured DEXes.
     ///
-    /// This asynchronous method enters an infinite loop, retrieving pools from each DEX at the configured interval and processing their liquidity data. Errors encountered during pool retrieval or processing are logged, but do not interrupt the indexing cycle.
+    /// This asynchronous method loops until `shutdown` resolves, retrieving pools from each DEX at the configured interval and processing their liquidity data. Errors encountered during pool retrieval or processing are logged, but do not interrupt the indexing cycle.
     ///
-    /// # Returns
-    /// Returns `Ok(())` if the loop is externally stopped; otherwise, runs indefinitely.
-    pub async fn start(&self) {
-        let light_mode: bool = true; // Only index first 10 pools for each dex. TODO: make it configurable
-
-        if light_mode {
-            info!(
-                "Starting indexer in light mode... light_mode_pools: {:?}",
-                LIGHT_MODE_POOLS
-            );
-        } else {
-            info!("Starting indexer in full mode...");
+    /// On shutdown no new pool refreshes are started; the ones in flight get
+    /// `shutdown_timeout_secs` to finish before they are abandoned, and storage is flushed.
+    pub async fn start(&self, shutdown: impl Future<Output = ()>) {
+        match self.config.indexer.mode {
+            IndexerMode::Light => info!("Starting indexer in light mode (watchlist only)..."),
+            IndexerMode::Full => info!("Starting indexer in full mode..."),
         }
+
+        let compaction = self.spawn_compaction();
+
         let interval = Duration::from_secs(self.config.indexer.interval_secs);
+        if let Some(scheduler) = &self.scheduler {
+            if scheduler.min_interval() < interval {
+                warn!(
+                    "Scheduler tiers refresh every {:?} but cycles run every {:?}; lower interval_secs to match",
+                    scheduler.min_interval(),
+                    interval
+                );
+            }
+        }
         let mut interval_timer = time::interval(interval);
+        tokio::pin!(shutdown);
 
         loop {
-            interval_timer.tick().await;
+            tokio::select! {
+                _ = interval_timer.tick() => {}
+                _ = &mut shutdown => break,
+            }
             info!("Indexer cycle running");
+            let cycle = self.run_cycle();
+            tokio::pin!(cycle);
+            tokio::select! {
+                _ = &mut cycle => {}
+                _ = &mut shutdown => {
+                    self.drain(cycle).await;
+                    break;
+                }
+            }
+        }
+
+        if let Some(compaction) = compaction {
+            compaction.abort();
+        }
+        self.flush().await;
+        info!("Indexer stopped");
+    }
 
-            // Process each configured DEX
-            for (dex_name, dex) in &self.dexes {
-                info!("Indexing pool states from DEX: {}", dex_name);
-
-                // Get pools for this DEX
-                match dex.get_all_pools_local().await {
-                    Ok(pools) => {
-                        info!("Found {} pools for {}", pools.len(), dex_name);
-                        let pools: Vec<Pool> = if light_mode {
-                            let light_mode_pools_addresses: Vec<Address> = LIGHT_MODE_POOLS
-                                .iter()
-                                .map(|addr| Address::from_str(addr).unwrap())
-                                .collect();
-
-                            pools
-                                .into_iter()
-                                .filter(|p| light_mode_pools_addresses.contains(&p.address))
-                                .collect()
-                        } else {
-                            pools
-                        };
-                        for pool in pools {
-                            match self.process_pool(&pool).await {
-                                Ok(_) => debug!("Processed pool {} on {}", pool.address, pool.dex),
-                                Err(e) => warn!(
-                                    "Failed to process pool {} on {}: {}",
-                                    pool.address, pool.dex, e
-                                ),
-                            }
+    /// Stops scheduling pool refreshes and waits up to `shutdown_timeout_secs` for `cycle` to
+    /// finish the ones in flight. Returns `None` if the deadline passed first.
+    async fn drain(&self, cycle: impl Future<Output = CycleStats>) -> Option<CycleStats> {
+        self.stopping.store(true, Ordering::SeqCst);
+        let deadline = Duration::from_secs(self.config.indexer.shutdown_timeout_secs);
+        info!("Shutting down, waiting up to {:?} for in-flight pools", deadline);
+        let stats = time::timeout(deadline, cycle).await.ok();
+        if stats.is_none() {
+            warn!("In-flight pools did not finish within {:?}, abandoning them", deadline);
+        }
+        stats
+    }
+
+    /// Makes every completed write durable; failures are logged.
+    async fn flush(&self) {
+        if let Err(e) = self.storage.flush().await {
+            error!("Failed to flush storage: {}", e);
+        }
+    }
+
+    fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// The enabled DEX `name` on `chain_id`.
+    fn dex(&self, chain_id: u64, name: &str) -> Result<&dyn DexProtocol, Error> {
+        self.dexes
+            .get(&(chain_id, name.to_string()))
+            .map(|dex| dex.as_ref())
+            .ok_or_else(|| Error::UnknownDEX(format!("{name} on chain {chain_id}")))
+    }
+
+    /// The enabled DEX `name` on `chain_id`, or on its only chain when no chain is given.
+    fn dex_on(&self, chain_id: Option<u64>, name: &str) -> Result<&dyn DexProtocol, Error> {
+        if let Some(chain_id) = chain_id {
+            return self.dex(chain_id, name);
+        }
+        let mut found = self.dexes.values().filter(|dex| dex.name() == name);
+        match (found.next(), found.next()) {
+            (Some(dex), None) => Ok(dex.as_ref()),
+            (None, _) => Err(Error::UnknownDEX(name.to_string())),
+            (Some(_), Some(_)) => Err(Error::DexError(format!(
+                "{name} is enabled on several chains, pass --chain-id to pick one"
+            ))),
+        }
+    }
+
+    /// Returns the pools refreshed each cycle under the configured mode.
+    ///
+    /// Light mode resolves the `[watchlist]` config; full mode takes every stored pool of every
+    /// enabled DEX.
+    pub async fn tracked_pools(&self) -> Vec<Pool> {
+        match self.config.indexer.mode {
+            IndexerMode::Light => {
+                self.watchlist
+                    .resolve(&self.dexes, self.storage.clone(), &self.prices, |chain_id| {
+                        self.concurrency(chain_id)
+                    })
+                    .await
+            }
+            IndexerMode::Full => {
+                let mut pools = Vec::new();
+                for ((chain_id, dex_name), dex) in &self.dexes {
+                    match dex.get_all_pools_local().await {
+                        Ok(found) => {
+                            info!("Found {} pools for {} on chain {}", found.len(), dex_name, chain_id);
+                            pools.extend(found);
                         }
-                    }
-                    Err(e) => {
-                        warn!("Failed to get pools for {}: {}", dex_name, e);
+                        Err(e) => warn!("Failed to get pools for {} on chain {}: {}", dex_name, chain_id, e),
                     }
                 }
+                pools
+            }
+        }
+    }
 
-                info!("Finished indexing pool states from DEX: {}", dex_name);
+    /// Scans every enabled DEX's factory events for pools created since the last checkpoint.
+    ///
+    /// Failures are logged per DEX; the checkpoint keeps whatever progress was made.
+    pub async fn discover(&self) {
+        for dex_config in self.config.dexes.iter().filter(|d| d.enabled) {
+            let Some(dex) = self.dexes.get(&(dex_config.chain_id, dex_config.name.clone())) else {
+                continue;
+            };
+            match discover_pools(
+                dex.as_ref(),
+                dex_config.start_block,
+                self.config.indexer.log_range_blocks,
+            )
+            .await
+            {
+                Ok(_) => {}
+                Err(Error::NotImplemented) => {
+                    debug!("Factory discovery not supported for {}", dex_config.name)
+                }
+                Err(e) => warn!("Pool discovery failed for {}: {}", dex_config.name, e),
             }
         }
     }
 
-    pub async fn fetch(&self) -> Result<(), Error> {
-        info!("Starting indexer fetch mode...");
+    /// Reads the head of every chain with an enabled DEX, rolling back snapshots orphaned by a
+    /// reorg and finalizing those `confirmations` blocks deep.
+    ///
+    /// Chains whose head cannot be read are left out of the result for this cycle.
+    async fn follow_heads(&self) -> HashMap<u64, BlockRef> {
+        let depth = self.config.indexer.confirmations;
+        let chains: HashSet<u64> = self.dexes.values().map(|dex| dex.chain_id()).collect();
+        let mut heads = HashMap::new();
+        for chain_id in chains {
+            let Some(provider) = self.provider_manager.by_chain_id(chain_id) else {
+                continue;
+            };
+            let head = match provider.head_block().await {
+                Ok(head) => head,
+                Err(e) => {
+                    warn!("Failed to read head of chain {}: {}", chain_id, e);
+                    continue;
+                }
+            };
+            match reorg::handle_reorg(&provider, self.storage.as_ref(), &head, depth).await {
+                Ok(Some(_)) => self.tracker.forget_chain(chain_id).await,
+                Ok(None) => {}
+                Err(e) => warn!("Reorg check failed on chain {}: {}", chain_id, e),
+            }
+            if let Err(e) = self.storage.save_block(chain_id, &head).await {
+                warn!("Failed to record block {} of chain {}: {}", head.number, chain_id, e);
+            }
+            match self
+                .storage
+                .finalize_blocks(chain_id, head.number.saturating_sub(depth))
+                .await
+            {
+                Ok(0) => {}
+                Ok(n) => debug!("Finalized {} snapshots on chain {}", n, chain_id),
+                Err(e) => warn!("Failed to finalize snapshots on chain {}: {}", chain_id, e),
+            }
+            heads.insert(chain_id, head);
+        }
+        heads
+    }
 
-        // Fetch all pools from each DEX
-        for (dex_name, dex) in &self.dexes {
-            if dex_name == "uniswap_v2" {
-                info!("Fetching pools for DEX: {}", dex_name);
-
-                for pool_address in V2_POOLS{
-                    let pool_address = Address::from_str(pool_address)
-                                            .map_err(|_| Error::InvalidAddress(pool_address.to_string()))?;
-                    match dex.get_pool(pool_address).await {
-                        Ok(pool) => {
-                            match self.process_pool(&pool).await {
-                                Ok(_) => debug!("Processed pool {} on {}", pool.address, pool.dex),
-                                Err(e) => warn!(
-                                    "Failed to process pool {} on {}: {}",
-                                    pool.address, pool.dex, e
-                                ),
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to fetch pools for {}: {}", dex_name, e);
-                        }
+    /// Advances the event-tracked state of `pools` and persists the ones that changed.
+    ///
+    /// Each synced pool counts as a refresh for the scheduler, with the swaps seen in its logs.
+    /// Returns the pools that still need a full snapshot: those of DEXes without event support
+    /// or a known head, and all pools of a DEX whose sync failed.
+    async fn sync_events(&self, pools: Vec<Pool>, heads: &HashMap<u64, BlockRef>) -> Vec<Pool> {
+        let full_refresh = self.tracker.full_refresh_due().await;
+        let mut by_dex: HashMap<(u64, String), Vec<Pool>> = HashMap::new();
+        for pool in pools {
+            by_dex.entry((pool.chain_id, pool.dex.clone())).or_default().push(pool);
+        }
+
+        let mut remaining = Vec::new();
+        let mut synced = Vec::new();
+        for (key, pools) in by_dex {
+            let Some((dex, head)) = self
+                .dexes
+                .get(&key)
+                .and_then(|dex| Some((dex, heads.get(&dex.chain_id())?)))
+            else {
+                remaining.extend(pools);
+                continue;
+            };
+            match self.tracker.sync(dex.as_ref(), &pools, full_refresh, head).await {
+                Ok(outcome) => {
+                    remaining.extend(outcome.snapshot_only);
+                    synced.extend(outcome.synced);
+                }
+                Err(e) => {
+                    warn!(
+                        "Event sync failed for {} on chain {}, falling back to snapshots: {}",
+                        key.1, key.0, e
+                    );
+                    remaining.extend(pools);
+                }
+            }
+        }
+
+        let swaps = self.tracker.take_swaps().await;
+        if let Some(scheduler) = &self.scheduler {
+            for ((chain_id, pool), swaps) in swaps {
+                scheduler.record_swaps(chain_id, pool, swaps);
+            }
+        }
+        for (pool, price) in &synced {
+            self.reschedule(pool, *price).await;
+        }
+        remaining
+    }
+
+    /// Refreshes the liquidity distribution of every tracked pool once.
+    ///
+    /// Chain heads are checked for reorgs first and snapshots are tagged with them; pools of a
+    /// chain whose head cannot be read are left for the next cycle. In full
+    /// mode, and in light mode when the watchlist ranks pools by TVL, new pools are discovered
+    /// from factory events. With the scheduler enabled only the
+    /// pools that are due are refreshed, most active first. With event updates enabled only pools
+    /// that changed are persisted; the rest are snapshotted in full, in parallel per chain.
+    async fn run_cycle(&self) -> CycleStats {
+        let started_at = chrono::Utc::now();
+        let started = Instant::now();
+        let heads = self.follow_heads().await;
+        if self.config.indexer.mode == IndexerMode::Full || self.watchlist.ranks_by_tvl() {
+            self.discover().await;
+        }
+        let mut pools = self.tracked_pools().await;
+        let tracked = pools.len();
+        if let Some(scheduler) = &self.scheduler {
+            let (due, deferred) = scheduler.due(pools, Instant::now());
+            if deferred > 0 {
+                info!("{} pools are not due for a refresh yet", deferred);
+            }
+            pools = due;
+        }
+        if self.config.indexer.event_updates && !self.is_stopping() {
+            pools = self.sync_events(pools, &heads).await;
+        }
+        info!("Refreshing {} pools", pools.len());
+
+        let mut by_chain: HashMap<u64, Vec<Pool>> = HashMap::new();
+        for pool in pools {
+            by_chain.entry(pool.chain_id).or_default().push(pool);
+        }
+        // A snapshot without a block could not be rolled back by a reorg, so the pools of a
+        // chain whose head is unknown wait for the next cycle
+        by_chain.retain(|chain_id, pools| {
+            let known = heads.contains_key(chain_id);
+            if !known {
+                warn!("Head of chain {} unknown, deferring its {} pools", chain_id, pools.len());
+            }
+            known
+        });
+        let scheduled: usize = by_chain.values().map(Vec::len).sum();
+        let batches = by_chain.into_iter().map(|(chain_id, pools)| {
+            let head = heads[&chain_id];
+            let pools = pools.into_iter().take_while(|_| !self.is_stopping());
+            run_bounded(pools, self.concurrency(chain_id), move |pool| async move {
+                let started = Instant::now();
+                let result = self.process_pool(&pool, &head).await;
+                record_attempt(self.storage.as_ref(), &pool, started, result.as_ref().err()).await;
+                match &result {
+                    Ok(price) => {
+                        debug!("Processed pool {} on {}", pool.address, pool.dex);
+                        self.reschedule(&pool, *price).await;
                     }
+                    Err(e) => warn!(
+                        "Failed to process pool {} on {}: {}",
+                        pool.address, pool.dex, e
+                    ),
                 }
-                
+                result.map(|_| ())
+            })
+        });
+
+        let mut stats = CycleStats::default();
+        for batch in join_all(batches).await {
+            stats.merge(&batch);
+        }
+        stats.elapsed = started.elapsed();
+        if stats.pools < scheduled {
+            info!("Skipped {} pools during shutdown", scheduled - stats.pools);
+        }
+        info!(
+            "Cycle finished in {:.2?}: {} pools tracked, {} snapshotted ({} failed, slowest {:.2?})",
+            stats.elapsed,
+            tracked,
+            stats.pools,
+            stats.failed,
+            stats.slowest
+        );
+        let run = IndexerRun {
+            started_at,
+            duration_ms: stats.elapsed.as_millis() as u64,
+            pools: tracked as u64,
+            snapshotted: stats.pools as u64,
+            failed: stats.failed as u64,
+        };
+        if let Err(e) = self.storage.save_indexer_run(&run).await {
+            warn!("Failed to record indexer run: {}", e);
+        }
+        stats
+    }
+
+    /// Feeds a successful refresh of `pool` to the scheduler, if enabled.
+    async fn reschedule(&self, pool: &Pool, price: f64) {
+        let Some(scheduler) = &self.scheduler else {
+            return;
+        };
+        let tvl = match estimate_pool_tvl_usd(self.storage.clone(), &self.prices, pool).await {
+            Ok(tvl) => tvl,
+            Err(e) => {
+                debug!("Failed to estimate TVL of pool {}: {}", pool.address, e);
+                None
             }
-            // match dex.get_all_pools().await {
-            //     Ok(pools) => {
-            //         info!("Found {} pools for {}", pools.len(), dex_name);
-            //         for pool in pools {
-            //             match self.process_pool(&pool).await {
-            //                 Ok(_) => debug!("Processed pool {} on {}", pool.address, pool.dex),
-            //                 Err(e) => warn!(
-            //                     "Failed to process pool {} on {}: {}",
-            //                     pool.address, pool.dex, e
-            //                 ),
-            //             }
-            //         }
-            //     }
-            //     Err(e) => {
-            //         warn!("Failed to fetch pools for {}: {}", dex_name, e);
-            //     }
-            // }
+        };
+        let tier = scheduler.observe(pool, price, tvl, Instant::now());
+        debug!("Pool {} on {} is in refresh tier {}", pool.address, pool.dex, tier);
+    }
+
+    /// Number of pools refreshed in parallel on `chain_id`.
+    fn concurrency(&self, chain_id: u64) -> usize {
+        self.provider_manager
+            .by_chain_id(chain_id)
+            .and_then(|provider| provider.max_concurrency())
+            .unwrap_or(self.config.indexer.batch_size)
+            .max(1)
+    }
+
+    /// Spawns the background task that enforces the snapshot retention policy.
+    ///
+    /// The task compacts the snapshot history every `compaction_interval_secs`; failures are
+    /// logged and retried on the next tick. Returns `None` when retention is disabled.
+    pub fn spawn_compaction(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let policy = self.config.retention.clone();
+        if !policy.enabled {
+            info!("Snapshot retention disabled, keeping full history");
+            return None;
         }
 
-        Ok(())
+        let storage = self.storage.clone();
+        Some(tokio::spawn(async move {
+            let period = Duration::from_secs(policy.compaction_interval_secs.max(1));
+            let mut timer = time::interval(period);
+            loop {
+                timer.tick().await;
+                match storage.compact_snapshots(&policy, chrono::Utc::now()).await {
+                    Ok(stats) => info!(
+                        "Compacted snapshot history: examined {}, deleted {}",
+                        stats.examined, stats.deleted
+                    ),
+                    Err(e) => error!("Snapshot compaction failed: {}", e),
+                }
+            }
+        }))
+    }
+
+    /// Runs a single indexing cycle: registers the tracked pools and stores their distributions.
+    pub async fn fetch(&self) -> Result<CycleStats, Error> {
+        info!("Starting indexer fetch mode...");
+        Ok(self.run_cycle().await)
     }
 
     /// Processes a liquidity pool by retrieving and storing its liquidity distribution.
     ///
     /// Attempts to obtain the DEX implementation for the given pool, fetches the pool's liquidity distribution asynchronously, and saves the result to storage.
+    /// The snapshot is tagged with the chain `head`, so a reorg can roll it back.
     ///
     /// # Errors
     ///
     /// Returns an error if the DEX is unknown, if retrieving the liquidity distribution fails, or if saving to storage fails.
-    async fn process_pool(&self, pool: &Pool) -> Result<(), Error> {
-        let dex = self
-            .dexes
-            .get(&pool.dex)
-            .ok_or_else(|| Error::UnknownDEX(pool.dex.clone()))?;
+    /// On success returns the pool's current price.
+    async fn process_pool(&self, pool: &Pool, head: &BlockRef) -> Result<f64, Error> {
+        let dex = self.dex(pool.chain_id, &pool.dex)?;
 
         let distribution = dex.get_liquidity_distribution(pool.address).await?;
-        storage::save_liquidity_distribution_async(self.storage.clone(), distribution).await?;
-        Ok(())
+        let price = distribution.current_price;
+        self.storage
+            .save_liquidity_distribution_at(&distribution, head)
+            .await?;
+        Ok(price)
     }
 
     pub async fn index_pool(
@@ -271,10 +548,7 @@ impl Indexer {
             .map_err(|_| Error::InvalidAddress(pool_address_str.to_string()))?;
 
         // Get DEX implementation
-        let dex = self
-            .dexes
-            .get(dex_name)
-            .ok_or_else(|| Error::UnknownDEX(dex_name.to_string()))?;
+        let dex = self.dex(chain_id, dex_name)?;
 
         // Get pool details
         let pool = dex.get_pool(pool_address).await?;
@@ -285,54 +559,15 @@ impl Indexer {
         Ok(pool)
     }
 
-    async fn get_or_create_token(&self, address: Address, chain_id: u64) -> Result<Token, Error> {
-        // Try to get from database first
-        if let Ok(Some(token)) =
-            storage::get_token_async(self.storage.clone(), address, chain_id).await
-        {
-            return Ok(token);
-        }
-
-        // Get provider
-        let _provider = self
-            .provider_manager
-            .by_chain_id(chain_id)
-            .ok_or_else(|| Error::ProviderError(format!("No provider for chain {}", chain_id)))?;
-
-        // We'll use the relevant DEX to get token information
-        for dex in self.dexes.values() {
-            if dex.chain_id() == chain_id {
-                if let Ok(token) = dex.get_token(address).await {
-                    // Store in database
-                    storage::save_token_async(self.storage.clone(), token.clone()).await?;
-                    return Ok(token);
-                }
-            }
-        }
-
-        // Fallback with unknown token info
-        let token = Token {
-            address,
-            symbol: "UNKNOWN".to_string(),
-            name: "Unknown Token".to_string(),
-            decimals: 18,
-            chain_id,
-        };
-
-        // Store in database
-        storage::save_token_async(self.storage.clone(), token.clone()).await?;
-
-        Ok(token)
-    }
-
     pub async fn get_liquidity_distribution(
         &self,
         dex_name: &str,
         pool_address_str: &str,
+        chain_id: u64,
     ) -> Result<LiquidityDistribution, Error> {
         info!(
-            "Getting liquidity distribution for {} on {}",
-            pool_address_str, dex_name
+            "Getting liquidity distribution for {} on {} (chain {})",
+            pool_address_str, dex_name, chain_id
         );
 
         // Parse address
@@ -340,10 +575,7 @@ impl Indexer {
             .map_err(|_| Error::InvalidAddress(pool_address_str.to_string()))?;
 
         // Get DEX implementation
-        let dex = self
-            .dexes
-            .get(dex_name)
-            .ok_or_else(|| Error::UnknownDEX(dex_name.to_string()))?;
+        let dex = self.dex(chain_id, dex_name)?;
 
         // Get liquidity distribution
         let distribution = dex.get_liquidity_distribution(pool_address).await?;
@@ -352,87 +584,178 @@ impl Indexer {
     }
 }
 
-/// Runs the DEX indexer in either continuous or single-pool mode.
-///
-/// If both `dex` and `pair` are provided, indexes a specific pool for the given DEX and saves its liquidity distribution. Otherwise, starts the indexer in continuous mode to periodically index all configured DEXes and pools.
-///
-/// # Returns
-/// Returns `Ok(())` on success, or an error if initialization or indexing fails.
-///
-/// # Examples
+/// Opens the configured database, wrapped in a [`DryRunStorage`] when `indexer.dry_run` is set.
+pub fn open_storage(config: &Config) -> Result<Arc<dyn Storage>, Error> {
+    let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::new(&config.database.url)?);
+    if config.indexer.dry_run {
+        info!("Dry run: storage writes are skipped");
+        return Ok(Arc::new(DryRunStorage::new(storage)));
+    }
+    Ok(storage)
+}
+
+/// Runs the indexer continuously, one cycle every `indexer.interval_secs`.
+pub async fn run_indexer(config: Config) -> Result<(), Error> {
+    let storage = open_storage(&config)?;
+    let indexer = Indexer::new(config, storage)?;
+    info!("Indexer running in continuous mode");
+    indexer.start(shutdown_signal()).await;
+    Ok(())
+}
+
+/// Runs a single indexing cycle and returns its stats.
 ///
-/// ```
-/// let config = Config::default();
-/// let result = run_indexer(config, Some("UniswapV2".to_string()), Some("0x1234...".to_string())).await;
-/// assert!(result.is_ok());
-/// ```
-pub async fn run_indexer(
-    config: Config,
-    dex: Option<String>,
-    pair: Option<String>,
-    test_mode: bool,
-) -> Result<(), Error> {
-    // Initialize the database connection
-    let storage = Arc::new(SqliteStorage::new(&config.database.url)?);
+/// A shutdown signal drains the cycle like in continuous mode.
+pub async fn run_once(config: Config) -> Result<CycleStats, Error> {
+    let storage = open_storage(&config)?;
     let indexer = Indexer::new(config, storage)?;
+    let cycle = indexer.run_cycle();
+    tokio::pin!(cycle);
+    let stats = tokio::select! {
+        stats = &mut cycle => Some(stats),
+        _ = shutdown_signal() => indexer.drain(cycle).await,
+    };
+    indexer.flush().await;
+    stats.ok_or_else(|| Error::Unknown("shutdown deadline passed with pools in flight".to_string()))
+}
 
-    match (dex, pair) {
-        (Some(dex_name), Some(pool_address)) => {
-            info!("Indexer running in single pool mode");
-            if !indexer.dexes.contains_key(&dex_name) {
-                return Err(Error::UnknownDEX(dex_name));
-            }
-            let chain_id = indexer
-                .dexes
-                .get(&dex_name)
-                .map(|dex| dex.chain_id())
-                .unwrap_or(1);
-            let pool = indexer
-                .index_pool(&dex_name, &pool_address, chain_id)
-                .await?;
-            info!("Indexed pool: {} on {}", pool.address, pool.dex);
-            match indexer
-                .get_liquidity_distribution(&dex_name, &pool_address)
+/// Registers one pool of `dex_name` and stores its current liquidity distribution, tagged with
+/// the chain head.
+pub async fn run_pool(config: Config, dex_name: &str, pool_address: &str) -> Result<Pool, Error> {
+    let storage = open_storage(&config)?;
+    let indexer = Indexer::new(config, storage)?;
+    let chain_id = indexer.dex_on(None, dex_name)?.chain_id();
+
+    let started = Instant::now();
+    let pool = indexer.index_pool(dex_name, pool_address, chain_id).await?;
+    info!("Indexed pool: {} on {}", pool.address, pool.dex);
+    let head = indexer.dex(chain_id, dex_name)?.provider().head_block().await?;
+    let result = match indexer.get_liquidity_distribution(dex_name, pool_address, chain_id).await {
+        Ok(distribution) => {
+            indexer
+                .storage
+                .save_liquidity_distribution_at(&distribution, &head)
                 .await
-            {
-                Ok(distribution) => {
-                    info!(
-                        "Got liquidity distribution for pool {} on {}",
-                        pool_address, dex_name
-                    );
-                    storage::save_liquidity_distribution_async(
-                        indexer.storage.clone(),
-                        distribution,
-                    )
-                    .await?;
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to get liquidity distribution for pool {} on {}: {}",
-                        pool_address, dex_name, e
-                    );
-                }
-            }
         }
-        _ => {
-            info!("Indexer running in continuous mode");
-            indexer.start().await;
-        }
-    }
+        Err(e) => Err(e),
+    };
+    record_attempt(indexer.storage.as_ref(), &pool, started, result.as_ref().err()).await;
+    result?;
+    Ok(pool)
+}
+
+/// Scans the factory events of every enabled DEX for new pools, whatever the indexer mode.
+pub async fn run_discover(config: Config) -> Result<(), Error> {
+    let storage = open_storage(&config)?;
+    let indexer = Indexer::new(config, storage)?;
+    indexer.discover().await;
+    Ok(())
+}
 
+/// Creates the database, or brings an existing one up to the current schema.
+///
+/// Opening a [`SqliteStorage`] applies the schema, so this only opens it.
+pub fn run_migrate(config: &Config) -> Result<(), Error> {
+    if config.indexer.dry_run {
+        info!("Dry run: not migrating {}", config.database.url);
+        return Ok(());
+    }
+    SqliteStorage::new(&config.database.url)?;
+    info!("Database {} is up to date", config.database.url);
     Ok(())
 }
 
-pub async fn run_indexer_fetch(
+/// Reads the indexer status from the configured database, see [`indexer_status`].
+pub async fn run_status(
+    config: &Config,
+    chain_id: Option<u64>,
+    dex: Option<&str>,
+    stale_secs: Option<i64>,
+    runs: usize,
+) -> Result<IndexerStatus, Error> {
+    let storage = open_storage(config)?;
+    indexer_status(storage, chain_id, dex, stale_secs, runs, chrono::Utc::now()).await
+}
+
+/// Exports tokens, pools and price levels from the configured database to `out_dir`.
+///
+/// Only reads the database; no RPC providers are needed.
+pub async fn run_export(
+    config: Config,
+    filter: ExportFilter,
+    format: ExportFormat,
+    out_dir: &std::path::Path,
+) -> Result<ExportSummary, Error> {
+    let storage = open_storage(&config)?;
+    let summary = export(storage, &filter, format, out_dir).await?;
+    info!(
+        "Exported {} tokens, {} pools and {} price levels to {}",
+        summary.tokens,
+        summary.pools,
+        summary.price_levels,
+        out_dir.display()
+    );
+    Ok(summary)
+}
+
+/// Writes historical snapshots of one pool, see [`backfill_pool`].
+///
+/// The pool is looked up in the database by its address on `chain_id`; without a chain the
+/// address must be stored on a single one. If it is not stored yet, `dex` must name the DEX
+/// it belongs to so it can be registered from the chain. `every` defaults to the configured
+/// `backfill_every_blocks`.
+pub async fn run_backfill(
     config: Config,
-) -> Result<(), Error> {
-    // Initialize the database connection
-    let storage = Arc::new(SqliteStorage::new(&config.database.url)?);
+    chain_id: Option<u64>,
+    dex: Option<String>,
+    pool_address: &str,
+    from: Option<u64>,
+    to: Option<u64>,
+    every: Option<u64>,
+) -> Result<BackfillSummary, Error> {
+    let address = Address::from_str(pool_address)
+        .map_err(|_| Error::InvalidAddress(pool_address.to_string()))?;
+    let every = every.unwrap_or(config.indexer.backfill_every_blocks);
+    let log_range = config.indexer.log_range_blocks;
+    let storage = open_storage(&config)?;
     let indexer = Indexer::new(config, storage)?;
 
-    info!("Indexer running in fetch mode");
-    indexer.fetch().await?;
-    
+    let stored = match chain_id {
+        Some(chain_id) => indexer.storage.get_pool(address, chain_id).await?,
+        None => {
+            let mut matches: Vec<Pool> = indexer
+                .storage
+                .list_pools(None, None)
+                .await?
+                .into_iter()
+                .filter(|pool| pool.address == address)
+                .collect();
+            if matches.len() > 1 {
+                let chains: Vec<String> = matches.iter().map(|p| p.chain_id.to_string()).collect();
+                return Err(Error::DexError(format!(
+                    "pool {address} is indexed on chains {}, pass --chain-id to pick one",
+                    chains.join(", ")
+                )));
+            }
+            matches.pop()
+        }
+    };
+    let pool = match (stored, dex) {
+        (Some(pool), _) => pool,
+        (None, Some(dex_name)) => {
+            let dex = indexer.dex_on(chain_id, &dex_name)?;
+            dex.get_pools_by_address(&[address])
+                .await?
+                .pop()
+                .ok_or_else(|| Error::DexError(format!("{address} is not a {dex_name} pool")))?
+        }
+        (None, None) => {
+            return Err(Error::DexError(format!(
+                "pool {address} is not indexed yet, pass --dex to register it"
+            )))
+        }
+    };
+    let dex = indexer.dex(pool.chain_id, &pool.dex)?;
 
-    Ok(())
+    backfill_pool(dex, &pool, from, to, every, log_range).await
 }
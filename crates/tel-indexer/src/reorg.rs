@@ -0,0 +1,80 @@
+use tel_core::error::Error;
+use tel_core::models::BlockRef;
+use tel_core::providers::EthereumProvider;
+use tel_core::storage::Storage;
+use tracing::{error, warn};
+
+/// Newest stored block that is still canonical, given the canonical block at each stored
+/// height (`canonical[i]` is the chain's block at `stored[i].number`).
+///
+/// `stored` is ordered newest first. Returns `None` when none of them is canonical.
+pub fn fork_point(stored: &[BlockRef], canonical: &[Option<BlockRef>]) -> Option<u64> {
+    stored
+        .iter()
+        .zip(canonical)
+        .find(|(block, canonical)| canonical.is_some_and(|c| c.hash == block.hash))
+        .map(|(block, _)| block.number)
+}
+
+/// Checks `head` against the blocks recorded for its chain and rolls back on a reorg.
+///
+/// A head that directly follows the last recorded block is checked through its parent hash;
+/// otherwise the last recorded block is compared with the canonical block at its height. On a
+/// mismatch the recorded blocks (at most `depth + 1` of them) are walked back to the newest
+/// canonical one and every snapshot above it is discarded. Returns the fork block, if any.
+pub async fn handle_reorg(
+    provider: &EthereumProvider,
+    storage: &dyn Storage,
+    head: &BlockRef,
+    depth: u64,
+) -> Result<Option<u64>, Error> {
+    let chain_id = provider.chain_id();
+    let stored = storage
+        .get_recent_blocks(chain_id, depth as usize + 1)
+        .await?;
+    let Some(last) = stored.first() else {
+        return Ok(None);
+    };
+
+    let consistent = if head.number == last.number + 1 {
+        head.parent_hash == last.hash
+    } else if head.number == last.number {
+        head.hash == last.hash
+    } else {
+        provider
+            .block_at(last.number)
+            .await?
+            .is_some_and(|b| b.hash == last.hash)
+    };
+    if consistent {
+        return Ok(None);
+    }
+
+    let mut canonical = Vec::with_capacity(stored.len());
+    for block in &stored {
+        let current = provider.block_at(block.number).await?;
+        let matches = current.is_some_and(|c| c.hash == block.hash);
+        canonical.push(current);
+        if matches {
+            break;
+        }
+    }
+    let fork = match fork_point(&stored, &canonical) {
+        Some(fork) => fork,
+        None => {
+            let fork = stored.last().map_or(0, |b| b.number.saturating_sub(1));
+            error!(
+                "Reorg on chain {} is deeper than {} blocks, rolling back to block {}",
+                chain_id, depth, fork
+            );
+            fork
+        }
+    };
+
+    let stats = storage.rollback_to(chain_id, fork).await?;
+    warn!(
+        "Reorg on chain {} at block {}: rolled back to block {} ({} snapshots, {} blocks)",
+        chain_id, last.number, fork, stats.snapshots, stats.blocks
+    );
+    Ok(Some(fork))
+}
@@ -0,0 +1,184 @@
+use alloy_primitives::Address;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tel_core::config::{RefreshTier, SchedulerConfig};
+use tel_core::models::Pool;
+
+/// Weight of the newest observation in the smoothed swap rate and volatility.
+const SMOOTHING: f64 = 0.3;
+
+/// Recent activity of a pool, from which its refresh tier is picked.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PoolActivity {
+    pub swaps_per_hour: f64,
+    /// Hourly volatility of the pool price (0.01 = 1%).
+    pub volatility: f64,
+    pub tvl_usd: Option<f64>,
+}
+
+impl PoolActivity {
+    /// Whether any threshold of `tier` is met; a tier without thresholds takes every pool.
+    fn meets(&self, tier: &RefreshTier) -> bool {
+        let checks: Vec<bool> = [
+            tier.min_tvl_usd
+                .map(|min| self.tvl_usd.is_some_and(|tvl| tvl >= min)),
+            tier.min_swaps_per_hour.map(|min| self.swaps_per_hour >= min),
+            tier.min_volatility.map(|min| self.volatility >= min),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        checks.is_empty() || checks.contains(&true)
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    activity: PoolActivity,
+    tier: usize,
+    /// Price and time of the last refresh.
+    last: Option<(f64, Instant)>,
+    /// Whether `activity` holds a measured swap rate and volatility yet.
+    measured: bool,
+    interval: Duration,
+    next_due: Option<Instant>,
+    /// Swaps seen in the pool's logs since the last refresh.
+    swaps: u64,
+}
+
+/// Decides which pools are refreshed each cycle from their recent activity.
+///
+/// Every refresh, whether a full snapshot or a sync from the pool's logs, measures the swap
+/// rate, price volatility and TVL of the pool and places it in a tier (see [`SchedulerConfig`]). A pool is refreshed again after its tier's minimum
+/// interval if it changed, and after twice its previous interval, up to the tier's maximum,
+/// if it did not. Pools that were never refreshed, or whose last refresh failed, are always
+/// due. The schedule is kept in memory and starts over on restart.
+pub struct RefreshScheduler {
+    tiers: Vec<RefreshTier>,
+    pools: Mutex<HashMap<(u64, Address), Entry>>,
+}
+
+impl RefreshScheduler {
+    /// Creates a scheduler with the configured tiers, or the default ones if none are set.
+    pub fn new(config: &SchedulerConfig) -> Self {
+        let tiers = if config.tiers.is_empty() {
+            SchedulerConfig::default().tiers
+        } else {
+            config.tiers.clone()
+        };
+        Self {
+            tiers,
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Shortest interval of any tier.
+    pub fn min_interval(&self) -> Duration {
+        self.tiers
+            .iter()
+            .map(|t| Duration::from_secs(t.min_interval_secs))
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Splits `pools` into those due for a refresh at `now`, most active tier first, and the
+    /// number of pools that are not due yet.
+    pub fn due(&self, pools: Vec<Pool>, now: Instant) -> (Vec<Pool>, usize) {
+        let entries = self.pools.lock().unwrap();
+        let mut due = Vec::with_capacity(pools.len());
+        let mut deferred = 0;
+        for pool in pools {
+            let entry = entries.get(&(pool.chain_id, pool.address));
+            match entry.and_then(|e| e.next_due) {
+                Some(at) if at > now => deferred += 1,
+                _ => due.push((entry.map_or(0, |e| e.tier), pool)),
+            }
+        }
+        due.sort_by_key(|(tier, _)| *tier);
+        (due.into_iter().map(|(_, pool)| pool).collect(), deferred)
+    }
+
+    /// Counts `swaps` seen in the logs of a pool towards its swap rate.
+    pub fn record_swaps(&self, chain_id: u64, pool: Address, swaps: u64) {
+        let mut entries = self.pools.lock().unwrap();
+        entries.entry((chain_id, pool)).or_default().swaps += swaps;
+    }
+
+    /// Records a successful refresh of `pool` at `now`, which read `price` and valued the pool
+    /// at `tvl_usd`, and schedules the next one. Returns the name of the pool's tier.
+    ///
+    /// Without logs for the pool, a refresh in which the price moved counts as one swap.
+    pub fn observe(&self, pool: &Pool, price: f64, tvl_usd: Option<f64>, now: Instant) -> String {
+        let mut entries = self.pools.lock().unwrap();
+        let entry = entries.entry((pool.chain_id, pool.address)).or_default();
+
+        let mut changed = entry.swaps > 0;
+        match entry.last {
+            Some((last_price, at)) => {
+                let moved = price != last_price;
+                changed |= moved;
+                let hours = now.saturating_duration_since(at).as_secs_f64() / 3600.0;
+                if hours > 0.0 {
+                    let swaps = if entry.swaps == 0 && moved { 1 } else { entry.swaps };
+                    let swaps_per_hour = swaps as f64 / hours;
+                    let volatility = if price > 0.0 && last_price > 0.0 {
+                        (price / last_price).ln().abs() / hours.sqrt()
+                    } else {
+                        0.0
+                    };
+                    let activity = &mut entry.activity;
+                    if entry.measured {
+                        activity.swaps_per_hour +=
+                            SMOOTHING * (swaps_per_hour - activity.swaps_per_hour);
+                        activity.volatility += SMOOTHING * (volatility - activity.volatility);
+                    } else {
+                        activity.swaps_per_hour = swaps_per_hour;
+                        activity.volatility = volatility;
+                        entry.measured = true;
+                    }
+                }
+            }
+            None => changed = true,
+        }
+        if tvl_usd.is_some() {
+            entry.activity.tvl_usd = tvl_usd;
+        }
+        entry.swaps = 0;
+        entry.last = Some((price, now));
+
+        let tier = self
+            .tiers
+            .iter()
+            .position(|t| entry.activity.meets(t))
+            .unwrap_or(self.tiers.len() - 1);
+        let min = Duration::from_secs(self.tiers[tier].min_interval_secs);
+        let max = Duration::from_secs(self.tiers[tier].max_interval_secs).max(min);
+        entry.interval = if changed || tier != entry.tier {
+            min
+        } else {
+            (entry.interval * 2).clamp(min, max)
+        };
+        entry.tier = tier;
+        entry.next_due = Some(now + entry.interval);
+        self.tiers[tier].name.clone()
+    }
+
+    /// Activity measured for a pool, if it has been refreshed.
+    pub fn activity(&self, chain_id: u64, pool: Address) -> Option<PoolActivity> {
+        let entries = self.pools.lock().unwrap();
+        entries
+            .get(&(chain_id, pool))
+            .filter(|e| e.last.is_some())
+            .map(|e| e.activity)
+    }
+
+    /// Current refresh interval of a pool, if it has been refreshed.
+    pub fn interval(&self, chain_id: u64, pool: Address) -> Option<Duration> {
+        let entries = self.pools.lock().unwrap();
+        entries
+            .get(&(chain_id, pool))
+            .filter(|e| e.next_due.is_some())
+            .map(|e| e.interval)
+    }
+}
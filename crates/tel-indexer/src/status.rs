@@ -0,0 +1,29 @@
+use chrono::Utc;
+use std::time::Instant;
+use tel_core::error::Error;
+use tel_core::models::{Pool, PoolAttempt};
+use tel_core::storage::Storage;
+use tracing::warn;
+
+/// Records the outcome of refreshing `pool`, started at `started`, in its pool status.
+///
+/// Failing to record is logged rather than returned, so status bookkeeping never fails a
+/// refresh.
+pub async fn record_attempt(
+    storage: &dyn Storage,
+    pool: &Pool,
+    started: Instant,
+    error: Option<&Error>,
+) {
+    let attempt = PoolAttempt {
+        chain_id: pool.chain_id,
+        dex: pool.dex.clone(),
+        pool: pool.address,
+        at: Utc::now(),
+        duration_ms: started.elapsed().as_millis() as u64,
+        error: error.map(ToString::to_string),
+    };
+    if let Err(e) = storage.record_pool_attempt(&attempt).await {
+        warn!("Failed to record status of pool {}: {}", pool.address, e);
+    }
+}
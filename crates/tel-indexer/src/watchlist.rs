@@ -0,0 +1,333 @@
+use crate::workers::run_bounded;
+use alloy_primitives::Address;
+use futures::future::join_all;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tel_core::config::WatchlistConfig;
+use tel_core::core::pricing::PriceOracle;
+use tel_core::dexes::DexProtocol;
+use tel_core::error::Error;
+use tel_core::models::Pool;
+use tel_core::storage::{estimate_pool_tvl_usd, Storage};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// How long the on-chain balances of a pool without a snapshot are used to rank it.
+const ONCHAIN_TVL_TTL: Duration = Duration::from_secs(3600);
+
+/// Most pools without a snapshot whose balances are read in one cycle; the rest wait for the
+/// next cycles, so the first ranking of a large database is spread out instead of stalling.
+const ONCHAIN_READS_PER_CYCLE: usize = 256;
+
+/// Token balances of a pool read on-chain, or `None` if the read failed.
+type Balances = Option<(f64, f64)>;
+
+/// A pair looked up on one DEX: chain, DEX name and the two tokens.
+type PairKey = (u64, String, Address, Address);
+
+/// A pool without a snapshot, with the DEX its balances are read through.
+type Unsnapshotted<'a> = (&'a dyn DexProtocol, Pool);
+
+fn parse_addresses(addresses: &[String]) -> Vec<Address> {
+    addresses
+        .iter()
+        .filter_map(|a| match Address::from_str(a.trim()) {
+            Ok(address) => Some(address),
+            Err(_) => {
+                warn!("Ignoring invalid watchlist address: {}", a);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The `[watchlist]` config with what it resolved to so far, kept between cycles.
+///
+/// Explicit pools and the pools the factories hold for each pair are registered on their DEX
+/// once and reused, so they are not re-read and re-saved every cycle. Pools ranked for
+/// `top_by_tvl` without a stored snapshot are valued from their token balances, read at most
+/// `ONCHAIN_READS_PER_CYCLE` at a time and reused for `ONCHAIN_TVL_TTL`.
+pub struct Watchlist {
+    config: WatchlistConfig,
+    /// Explicit pools resolved so far, by chain, DEX and address.
+    explicit: Mutex<HashMap<(u64, String, Address), Pool>>,
+    /// Pools found through the factory for each pair, by chain, DEX and pair.
+    pairs: Mutex<HashMap<PairKey, Vec<Pool>>>,
+    /// Balances of pools that had no snapshot when ranked, by chain and address.
+    onchain: Mutex<HashMap<(u64, Address), (Instant, Balances)>>,
+}
+
+impl Watchlist {
+    pub fn new(config: WatchlistConfig) -> Self {
+        Self {
+            config,
+            explicit: Mutex::new(HashMap::new()),
+            pairs: Mutex::new(HashMap::new()),
+            onchain: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `top_by_tvl` ranks pools, which must be discovered from the factories first.
+    pub fn ranks_by_tvl(&self) -> bool {
+        self.config.top_by_tvl.is_some()
+    }
+
+    /// Resolves the watchlist to the pools it currently selects on the enabled DEXes.
+    ///
+    /// Explicit pools are registered on their DEX if needed, pairs expand to every pool of the
+    /// pair stored or held by the factories, and `top_by_tvl` adds the largest stored pools of
+    /// each chain. On-chain reads run at most `concurrency(chain_id)` at a time per chain. The
+    /// result has no duplicates and keeps that order.
+    pub async fn resolve(
+        &self,
+        dexes: &HashMap<(u64, String), Box<dyn DexProtocol>>,
+        storage: Arc<dyn Storage>,
+        prices: &PriceOracle,
+        concurrency: impl Fn(u64) -> usize,
+    ) -> Vec<Pool> {
+        let mut pools = self.explicit_pools(dexes).await;
+
+        for pair in &self.config.pairs {
+            let [token0, token1] = parse_addresses(pair)[..] else {
+                continue;
+            };
+            for ((chain_id, dex_name), dex) in dexes {
+                match storage
+                    .get_pools_by_token(token0, token1, *chain_id, Some(dex_name), None)
+                    .await
+                {
+                    Ok(found) => pools.extend(found),
+                    Err(e) => warn!(
+                        "Failed to resolve pair {}/{} on {}: {}",
+                        token0, token1, dex_name, e
+                    ),
+                }
+                pools.extend(self.factory_pools(dex.as_ref(), token0, token1).await);
+            }
+        }
+
+        if let Some(limit) = self.config.top_by_tvl {
+            pools.extend(
+                self.top_pools_by_tvl(dexes, storage, prices, limit, concurrency)
+                    .await,
+            );
+        }
+
+        let mut seen = HashSet::new();
+        pools.retain(|p| seen.insert((p.chain_id, p.address)));
+        pools
+    }
+
+    /// The configured pool addresses, registering only those not resolved in an earlier cycle.
+    async fn explicit_pools(
+        &self,
+        dexes: &HashMap<(u64, String), Box<dyn DexProtocol>>,
+    ) -> Vec<Pool> {
+        let mut resolved = self.explicit.lock().await;
+        let mut pools = Vec::new();
+        let configured = self.config.pools.iter().flat_map(|(chain_id, by_dex)| {
+            by_dex.iter().map(move |(name, a)| (*chain_id, name, a))
+        });
+        for (chain_id, dex_name, addresses) in configured {
+            let Some(dex) = dexes.get(&(chain_id, dex_name.clone())) else {
+                warn!(
+                    "Watchlist references DEX {} on chain {} which is not enabled",
+                    dex_name, chain_id
+                );
+                continue;
+            };
+            let addresses = parse_addresses(addresses);
+            let key = |address: Address| (chain_id, dex_name.clone(), address);
+            let missing: Vec<Address> = addresses
+                .iter()
+                .copied()
+                .filter(|a| !resolved.contains_key(&key(*a)))
+                .collect();
+            if !missing.is_empty() {
+                match dex.get_pools_by_address(&missing).await {
+                    Ok(found) => {
+                        for pool in found {
+                            resolved.insert(key(pool.address), pool);
+                        }
+                    }
+                    Err(e) => warn!(
+                        "Failed to resolve watchlist pools on {} (chain {}): {}",
+                        dex_name, chain_id, e
+                    ),
+                }
+            }
+            pools.extend(
+                addresses
+                    .iter()
+                    .filter_map(|a| resolved.get(&key(*a)).cloned()),
+            );
+        }
+        pools
+    }
+
+    /// The pools `dex`'s factory holds for a pair, asked once and then reused.
+    ///
+    /// A failed lookup is asked again next cycle; DEXes that cannot look pairs up get none.
+    async fn factory_pools(
+        &self,
+        dex: &dyn DexProtocol,
+        token0: Address,
+        token1: Address,
+    ) -> Vec<Pool> {
+        let key = (dex.chain_id(), dex.name().to_string(), token0, token1);
+        if let Some(pools) = self.pairs.lock().await.get(&key) {
+            return pools.clone();
+        }
+        let found = match dex.find_pools(token0, token1).await {
+            Ok(addresses) if addresses.is_empty() => Ok(Vec::new()),
+            Ok(addresses) => dex.get_pools_by_address(&addresses).await,
+            Err(Error::NotImplemented) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        };
+        match found {
+            Ok(pools) => {
+                self.pairs.lock().await.insert(key, pools.clone());
+                pools
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to look up pair {}/{} on {}: {}",
+                    token0,
+                    token1,
+                    dex.name(),
+                    e
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// The `limit` stored pools with the highest estimated TVL on each chain.
+    ///
+    /// Pools are valued from their latest snapshot, or from their token balances when they have
+    /// none yet. Pools whose balances were not read yet, or neither of whose tokens can be
+    /// priced, are left out.
+    async fn top_pools_by_tvl(
+        &self,
+        dexes: &HashMap<(u64, String), Box<dyn DexProtocol>>,
+        storage: Arc<dyn Storage>,
+        prices: &PriceOracle,
+        limit: usize,
+        concurrency: impl Fn(u64) -> usize,
+    ) -> Vec<Pool> {
+        let mut by_chain: HashMap<u64, Vec<(f64, Pool)>> = HashMap::new();
+        let mut unsnapshotted = Vec::new();
+        for dex in dexes.values() {
+            let pools = match dex.get_all_pools_local().await {
+                Ok(pools) => pools,
+                Err(e) => {
+                    warn!("Failed to load pools for {}: {}", dex.name(), e);
+                    continue;
+                }
+            };
+            for pool in pools {
+                match estimate_pool_tvl_usd(storage.clone(), prices, &pool).await {
+                    Ok(Some(tvl)) => by_chain.entry(pool.chain_id).or_default().push((tvl, pool)),
+                    Ok(None) => unsnapshotted.push((dex.as_ref(), pool)),
+                    Err(e) => debug!("Failed to estimate TVL of pool {}: {}", pool.address, e),
+                }
+            }
+        }
+
+        self.read_balances(&unsnapshotted, concurrency).await;
+        let balances: Vec<Balances> = {
+            let onchain = self.onchain.lock().await;
+            unsnapshotted
+                .iter()
+                .map(|(_, pool)| {
+                    onchain
+                        .get(&(pool.chain_id, pool.address))
+                        .and_then(|(_, b)| *b)
+                })
+                .collect()
+        };
+        for ((_, pool), balances) in unsnapshotted.into_iter().zip(balances) {
+            let Some(balances) = balances else {
+                continue;
+            };
+            if let Some(tvl) = balances_tvl_usd(prices, &pool, balances).await {
+                by_chain.entry(pool.chain_id).or_default().push((tvl, pool));
+            }
+        }
+
+        by_chain
+            .into_values()
+            .flat_map(|mut ranked| {
+                ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+                ranked.into_iter().take(limit).map(|(_, pool)| pool)
+            })
+            .collect()
+    }
+
+    /// Reads the balances of the `pools` not read within `ONCHAIN_TVL_TTL`, at most
+    /// `ONCHAIN_READS_PER_CYCLE` of them: those never read first, then the oldest reads.
+    ///
+    /// The cache is only locked to pick the pools and to store each result, not over the reads.
+    async fn read_balances(
+        &self,
+        pools: &[Unsnapshotted<'_>],
+        concurrency: impl Fn(u64) -> usize,
+    ) {
+        let mut due: Vec<(Option<Instant>, &Unsnapshotted)> = {
+            let onchain = self.onchain.lock().await;
+            pools
+                .iter()
+                .map(|entry| {
+                    let key = (entry.1.chain_id, entry.1.address);
+                    (onchain.get(&key).map(|(read_at, _)| *read_at), entry)
+                })
+                .filter(|(read_at, _)| read_at.is_none_or(|at| at.elapsed() >= ONCHAIN_TVL_TTL))
+                .collect()
+        };
+        due.sort_by_key(|(read_at, _)| *read_at);
+        due.truncate(ONCHAIN_READS_PER_CYCLE);
+
+        let mut by_chain: HashMap<u64, Vec<&Unsnapshotted>> = HashMap::new();
+        for (_, entry) in due {
+            by_chain.entry(entry.1.chain_id).or_default().push(entry);
+        }
+        let batches = by_chain.into_iter().map(|(chain_id, entries)| {
+            run_bounded(entries, concurrency(chain_id), |(dex, pool)| async move {
+                let read = dex.pool_balances(pool).await;
+                if let Err(e) = &read {
+                    debug!("Failed to read balances of pool {}: {}", pool.address, e);
+                }
+                let balances = read.as_ref().ok().copied();
+                self.onchain
+                    .lock()
+                    .await
+                    .insert((pool.chain_id, pool.address), (Instant::now(), balances));
+                read.map(|_| ())
+            })
+        });
+        join_all(batches).await;
+    }
+}
+
+/// USD value of `pool` holding `balances` of its tokens.
+///
+/// A side whose token cannot be priced is valued like the other one, as pools hold about equal
+/// value of both. Returns `None` when neither token can be priced.
+async fn balances_tvl_usd(prices: &PriceOracle, pool: &Pool, balances: (f64, f64)) -> Option<f64> {
+    let mut usd = [None; 2];
+    for (price, token) in usd.iter_mut().zip(&pool.tokens) {
+        *price = prices
+            .usd_price(token.address, pool.chain_id)
+            .await
+            .ok()
+            .flatten();
+    }
+    match usd {
+        [Some(usd0), Some(usd1)] => Some(balances.0 * usd0 + balances.1 * usd1),
+        [Some(usd0), None] => Some(2.0 * balances.0 * usd0),
+        [None, Some(usd1)] => Some(2.0 * balances.1 * usd1),
+        [None, None] => None,
+    }
+}
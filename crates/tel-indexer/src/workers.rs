@@ -0,0 +1,70 @@
+use futures::stream::{self, StreamExt};
+use futures::FutureExt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::{Duration, Instant};
+use tel_core::error::Error;
+use tracing::error;
+
+/// Timing of the pool refreshes of one cycle.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CycleStats {
+    pub pools: usize,
+    pub failed: usize,
+    /// Wall-clock time of the whole batch.
+    pub elapsed: Duration,
+    /// Longest single refresh.
+    pub slowest: Duration,
+}
+
+impl CycleStats {
+    /// Folds in the stats of a batch that ran in parallel with this one.
+    pub fn merge(&mut self, other: &CycleStats) {
+        self.pools += other.pools;
+        self.failed += other.failed;
+        self.elapsed = self.elapsed.max(other.elapsed);
+        self.slowest = self.slowest.max(other.slowest);
+    }
+}
+
+/// Runs `task` for every item with at most `limit` of them in flight.
+///
+/// Items are pulled lazily as slots free up, so an iterator that stops yielding stops new
+/// tasks while the running ones finish. An error or panic in one task is counted as a failure
+/// and does not affect the others; tasks are expected to log their own errors.
+pub async fn run_bounded<I, F, Fut>(items: I, limit: usize, task: F) -> CycleStats
+where
+    I: IntoIterator,
+    F: Fn(I::Item) -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let started = Instant::now();
+    let mut stats = CycleStats::default();
+
+    let mut results = stream::iter(items)
+        .map(|item| {
+            let run = AssertUnwindSafe(task(item)).catch_unwind();
+            async move {
+                let started = Instant::now();
+                let ok = match run.await {
+                    Ok(result) => result.is_ok(),
+                    Err(_) => {
+                        error!("Pool refresh task panicked");
+                        false
+                    }
+                };
+                (ok, started.elapsed())
+            }
+        })
+        .buffer_unordered(limit.max(1));
+    while let Some((ok, took)) = results.next().await {
+        stats.pools += 1;
+        if !ok {
+            stats.failed += 1;
+        }
+        stats.slowest = stats.slowest.max(took);
+    }
+
+    stats.elapsed = started.elapsed();
+    stats
+}
@@ -0,0 +1,23 @@
+use tel_indexer::backfill::SnapshotSchedule;
+
+fn drain_before(schedule: &mut SnapshotSchedule, block: u64) -> Vec<u64> {
+    std::iter::from_fn(|| schedule.pop_before(block)).collect()
+}
+
+#[test]
+fn snapshots_fall_between_logs() {
+    let mut schedule = SnapshotSchedule::new(100, 1_000, 250);
+
+    // A log at block 100 must be applied before the snapshot at 100 is taken.
+    assert!(drain_before(&mut schedule, 100).is_empty());
+    assert_eq!(drain_before(&mut schedule, 351), vec![100, 350]);
+    assert!(drain_before(&mut schedule, 600).is_empty());
+    assert_eq!(drain_before(&mut schedule, 601), vec![600]);
+}
+
+#[test]
+fn schedule_stops_at_the_last_block() {
+    let mut schedule = SnapshotSchedule::new(0, 1_000, 400);
+    assert_eq!(drain_before(&mut schedule, u64::MAX), vec![0, 400, 800]);
+    assert_eq!(schedule.pop_before(u64::MAX), None);
+}
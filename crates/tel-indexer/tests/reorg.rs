@@ -0,0 +1,33 @@
+use alloy_primitives::B256;
+use tel_core::models::BlockRef;
+use tel_indexer::reorg::fork_point;
+
+fn block(number: u64, fork: u8) -> BlockRef {
+    BlockRef {
+        number,
+        hash: B256::repeat_byte(fork ^ number as u8),
+        parent_hash: B256::repeat_byte(fork ^ number.wrapping_sub(1) as u8),
+    }
+}
+
+#[test]
+fn fork_is_the_newest_canonical_block() {
+    let stored = [block(103, 0), block(102, 0), block(101, 0)];
+    let canonical = [
+        Some(block(103, 0xf0)),
+        Some(block(102, 0xf0)),
+        Some(block(101, 0)),
+    ];
+    assert_eq!(fork_point(&stored, &canonical), Some(101));
+
+    // The node may not have the orphaned heights yet after a shorter reorg
+    let canonical = [None, Some(block(102, 0))];
+    assert_eq!(fork_point(&stored, &canonical), Some(102));
+}
+
+#[test]
+fn reorg_deeper_than_the_window_has_no_fork() {
+    let stored = [block(103, 0), block(102, 0)];
+    let canonical = [Some(block(103, 0xf0)), Some(block(102, 0xf0))];
+    assert_eq!(fork_point(&stored, &canonical), None);
+}
@@ -0,0 +1,128 @@
+use alloy_primitives::Address;
+use chrono::Utc;
+use std::time::{Duration, Instant};
+use tel_core::config::SchedulerConfig;
+use tel_core::models::{Pool, Token};
+use tel_indexer::schedule::RefreshScheduler;
+
+fn pool(byte: u8) -> Pool {
+    let token = |b: u8| Token {
+        address: Address::repeat_byte(b),
+        symbol: format!("T{b}"),
+        name: format!("Token {b}"),
+        decimals: 18,
+        chain_id: 1,
+    };
+    Pool {
+        address: Address::repeat_byte(byte),
+        dex: "uniswap_v3".to_string(),
+        chain_id: 1,
+        tokens: vec![token(0xa0), token(0xb0)],
+        creation_block: 0,
+        creation_timestamp: Utc::now(),
+        last_updated_block: 0,
+        last_updated_timestamp: Utc::now(),
+        fee: 3000,
+    }
+}
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+#[test]
+fn idle_pools_back_off_to_the_tier_maximum() {
+    let scheduler = RefreshScheduler::new(&SchedulerConfig::default());
+    let idle = pool(1);
+    let mut now = Instant::now();
+
+    assert_eq!(scheduler.observe(&idle, 2.0, None, now), "cold");
+    let mut intervals = Vec::new();
+    for _ in 0..5 {
+        now += scheduler.interval(1, idle.address).unwrap();
+        scheduler.observe(&idle, 2.0, None, now);
+        intervals.push(scheduler.interval(1, idle.address).unwrap().as_secs());
+    }
+    assert_eq!(intervals, vec![3600, 7200, 14400, 21600, 21600]);
+
+    let (due, deferred) = scheduler.due(vec![idle.clone()], now + HOUR);
+    assert!(due.is_empty());
+    assert_eq!(deferred, 1);
+    let (due, _) = scheduler.due(vec![idle], now + 6 * HOUR);
+    assert_eq!(due.len(), 1);
+}
+
+#[test]
+fn swaps_volatility_and_tvl_promote_pools() {
+    let scheduler = RefreshScheduler::new(&SchedulerConfig::default());
+    let (busy, volatile, large) = (pool(1), pool(2), pool(3));
+    let start = Instant::now();
+    for p in [&busy, &volatile, &large] {
+        scheduler.observe(p, 2.0, None, start);
+    }
+
+    scheduler.record_swaps(1, busy.address, 120);
+    assert_eq!(scheduler.observe(&busy, 2.0, None, start + HOUR), "hot");
+    assert_eq!(scheduler.interval(1, busy.address), Some(Duration::from_secs(60)));
+    let activity = scheduler.activity(1, busy.address).unwrap();
+    assert!((activity.swaps_per_hour - 120.0).abs() < 1e-9);
+
+    // A 0.5% move in an hour is warm, not hot.
+    assert_eq!(scheduler.observe(&volatile, 2.01, None, start + HOUR), "warm");
+    assert_eq!(scheduler.observe(&large, 2.0, Some(2e7), start + HOUR), "hot");
+}
+
+#[test]
+fn due_pools_come_most_active_first() {
+    let scheduler = RefreshScheduler::new(&SchedulerConfig::default());
+    let (cold, hot, new) = (pool(1), pool(2), pool(3));
+    let start = Instant::now();
+    scheduler.observe(&cold, 2.0, None, start);
+    scheduler.observe(&hot, 2.0, Some(2e7), start);
+
+    let (due, deferred) = scheduler.due(
+        vec![cold.clone(), hot.clone(), new.clone()],
+        start + Duration::from_secs(120),
+    );
+    assert_eq!(deferred, 1);
+    let order: Vec<Address> = due.iter().map(|p| p.address).collect();
+    assert_eq!(order, vec![hot.address, new.address]);
+
+    let (due, deferred) = scheduler.due(vec![cold, hot, new], start + HOUR);
+    assert_eq!(deferred, 0);
+    assert_eq!(due.len(), 3);
+}
+
+#[test]
+fn swaps_seen_in_logs_move_a_pool_to_a_faster_tier() {
+    let scheduler = RefreshScheduler::new(&SchedulerConfig::default());
+    let (busy, idle) = (pool(1), pool(2));
+    let start = Instant::now();
+    let mut pending = 0;
+    let mut tiers = Vec::new();
+    let mut refreshes = [0, 0];
+    // Each minute the indexer syncs the due pools from their logs, recording the swaps seen
+    // since their last sync and observing the sync as a refresh. The busy pool swaps twice a
+    // minute.
+    for minute in 0..120 {
+        let now = start + Duration::from_secs(60 * minute);
+        pending += 2;
+        let (due, _) = scheduler.due(vec![busy.clone(), idle.clone()], now);
+        for p in due {
+            if p.address == busy.address {
+                scheduler.record_swaps(1, p.address, std::mem::take(&mut pending));
+                tiers.push(scheduler.observe(&p, 2.0, None, now));
+                refreshes[0] += 1;
+            } else {
+                scheduler.observe(&p, 2.0, None, now);
+                refreshes[1] += 1;
+            }
+        }
+    }
+
+    // Unmeasured at first, then hot once its swap rate is known.
+    assert_eq!(tiers[0], "cold");
+    assert!(tiers[1..].iter().all(|t| t == "hot"), "{tiers:?}");
+    assert_eq!(scheduler.interval(1, busy.address), Some(Duration::from_secs(60)));
+    let activity = scheduler.activity(1, busy.address).unwrap();
+    assert!((activity.swaps_per_hour - 120.0).abs() < 1e-9);
+    assert!(refreshes[0] > 10 * refreshes[1], "{refreshes:?}");
+}
@@ -0,0 +1,262 @@
+//! Watchlist resolution on a database without snapshots, and its cache between cycles.
+
+use alloy_primitives::Address;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tel_core::config::{Numeraire, PricingConfig, RpcConfig, WatchlistConfig};
+use tel_core::core::pricing::PriceOracle;
+use tel_core::dexes::DexProtocol;
+use tel_core::error::Error;
+use tel_core::models::{LiquidityDistribution, Pool, Token};
+use tel_core::providers::EthereumProvider;
+use tel_core::storage::{MemoryStorage, Storage};
+use tel_indexer::watchlist::Watchlist;
+
+const USDC: u8 = 0xa;
+const TKN: u8 = 0xb;
+const DEEP: u8 = 0x10;
+const SHALLOW: u8 = 0x11;
+
+fn token(n: u8) -> Token {
+    Token {
+        address: Address::repeat_byte(n),
+        symbol: format!("TK{n}"),
+        name: format!("Token {n}"),
+        decimals: 18,
+        chain_id: 1,
+    }
+}
+
+fn pool(n: u8) -> Pool {
+    pool_on(n, 1)
+}
+
+fn pool_on(n: u8, chain_id: u64) -> Pool {
+    Pool {
+        address: Address::repeat_byte(n),
+        dex: "fake".to_string(),
+        chain_id,
+        tokens: vec![token(TKN), token(USDC)],
+        creation_block: 0,
+        creation_timestamp: Utc::now(),
+        last_updated_block: 0,
+        last_updated_timestamp: Utc::now(),
+        fee: 3000,
+    }
+}
+
+fn provider() -> Arc<EthereumProvider> {
+    let rpc = RpcConfig {
+        url: "http://127.0.0.1:1".to_string(),
+        timeout_secs: 1,
+        max_concurrency: None,
+    };
+    Arc::new(EthereumProvider::new(&rpc, 1).unwrap())
+}
+
+fn prices(storage: Arc<dyn Storage>) -> PriceOracle {
+    PriceOracle::new(
+        storage,
+        PricingConfig {
+            numeraires: vec![Numeraire {
+                chain_id: 1,
+                token: token(USDC).address.to_string(),
+            }],
+            ..PricingConfig::default()
+        },
+    )
+}
+
+async fn storage_with_tokens() -> Arc<dyn Storage> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    for n in [TKN, USDC] {
+        storage.save_token(&token(n)).await.unwrap();
+    }
+    storage
+}
+
+/// A DEX whose chain holds a fixed amount of USDC in each of its pools, counting its reads.
+struct FakeDex {
+    chain_id: u64,
+    storage: Arc<dyn Storage>,
+    provider: Arc<EthereumProvider>,
+    /// Pools on the chain, registered or not, with the USDC they hold.
+    onchain: HashMap<Address, (Pool, f64)>,
+    lookups: Arc<AtomicUsize>,
+    reads: Arc<AtomicUsize>,
+}
+
+impl FakeDex {
+    fn new(chain_id: u64, storage: Arc<dyn Storage>, onchain: &[(Pool, f64)]) -> Self {
+        Self {
+            chain_id,
+            storage,
+            provider: provider(),
+            onchain: onchain.iter().map(|(p, usdc)| (p.address, (p.clone(), *usdc))).collect(),
+            lookups: Arc::new(AtomicUsize::new(0)),
+            reads: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+#[async_trait]
+impl DexProtocol for FakeDex {
+    fn name(&self) -> &str {
+        "fake"
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn factory_address(&self) -> Address {
+        Address::ZERO
+    }
+
+    fn provider(&self) -> Arc<EthereumProvider> {
+        self.provider.clone()
+    }
+
+    fn storage(&self) -> Arc<dyn Storage> {
+        self.storage.clone()
+    }
+
+    async fn get_pool(&self, pool_address: Address) -> Result<Pool, Error> {
+        if let Some(pool) = self.storage.get_pool(pool_address, self.chain_id).await? {
+            return Ok(pool);
+        }
+        self.onchain
+            .get(&pool_address)
+            .map(|(pool, _)| pool.clone())
+            .ok_or_else(|| Error::DexError(format!("Pool not found: {pool_address}")))
+    }
+
+    async fn get_all_pools(&self) -> Result<Vec<Pool>, Error> {
+        self.get_all_pools_local().await
+    }
+
+    async fn get_liquidity_distribution(&self, _: Address) -> Result<LiquidityDistribution, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    async fn calculate_swap_impact(&self, _: Address, _: Address, _: f64) -> Result<f64, Error> {
+        Ok(0.0)
+    }
+
+    async fn get_pools_by_address(&self, addresses: &[Address]) -> Result<Vec<Pool>, Error> {
+        self.lookups.fetch_add(addresses.len(), Ordering::SeqCst);
+        let mut pools = Vec::new();
+        for address in addresses {
+            pools.push(self.get_pool(*address).await?);
+        }
+        Ok(pools)
+    }
+
+    async fn find_pools(&self, token0: Address, token1: Address) -> Result<Vec<Address>, Error> {
+        Ok(self
+            .onchain
+            .values()
+            .filter(|(pool, _)| {
+                let tokens = [pool.tokens[0].address, pool.tokens[1].address];
+                tokens == [token0, token1] || tokens == [token1, token0]
+            })
+            .map(|(pool, _)| pool.address)
+            .collect())
+    }
+
+    async fn pool_balances(&self, pool: &Pool) -> Result<(f64, f64), Error> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        Ok((0.0, self.onchain[&pool.address].1))
+    }
+}
+
+#[tokio::test]
+async fn pools_without_snapshots_are_ranked_and_resolved_once() {
+    let storage = storage_with_tokens().await;
+    for n in [DEEP, SHALLOW] {
+        storage.save_pool(&pool(n)).await.unwrap();
+    }
+    let dex = FakeDex::new(
+        1,
+        storage.clone(),
+        &[(pool(DEEP), 1_000_000.0), (pool(SHALLOW), 1_000.0)],
+    );
+    let (lookups, reads) = (dex.lookups.clone(), dex.reads.clone());
+    let mut dexes: HashMap<(u64, String), Box<dyn DexProtocol>> = HashMap::new();
+    dexes.insert((1, "fake".to_string()), Box::new(dex));
+    let prices = prices(storage.clone());
+    let watchlist = Watchlist::new(WatchlistConfig {
+        pools: HashMap::from([(
+            1,
+            HashMap::from([("fake".to_string(), vec![pool(SHALLOW).address.to_string()])]),
+        )]),
+        top_by_tvl: Some(1),
+        ..WatchlistConfig::default()
+    });
+
+    for _ in 0..2 {
+        let pools = watchlist.resolve(&dexes, storage.clone(), &prices, |_| 4).await;
+        let addresses: Vec<Address> = pools.iter().map(|p| p.address).collect();
+        assert_eq!(addresses, vec![pool(SHALLOW).address, pool(DEEP).address]);
+    }
+
+    // The second cycle reused the first one's explicit lookup and balance reads.
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn pair_without_stored_pools_is_found_through_the_factory() {
+    let storage = storage_with_tokens().await;
+    let dex = FakeDex::new(1, storage.clone(), &[(pool(DEEP), 1_000_000.0)]);
+    let lookups = dex.lookups.clone();
+    let mut dexes: HashMap<(u64, String), Box<dyn DexProtocol>> = HashMap::new();
+    dexes.insert((1, "fake".to_string()), Box::new(dex));
+    let watchlist = Watchlist::new(WatchlistConfig {
+        pairs: vec![[token(USDC).address.to_string(), token(TKN).address.to_string()]],
+        ..WatchlistConfig::default()
+    });
+
+    for _ in 0..2 {
+        let pools = watchlist
+            .resolve(&dexes, storage.clone(), &prices(storage.clone()), |_| 4)
+            .await;
+        let addresses: Vec<Address> = pools.iter().map(|p| p.address).collect();
+        assert_eq!(addresses, vec![pool(DEEP).address]);
+    }
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn one_dex_on_two_chains_resolves_each_chains_pools() {
+    let storage = storage_with_tokens().await;
+    storage.save_pool(&pool_on(DEEP, 1)).await.unwrap();
+    storage.save_pool(&pool_on(SHALLOW, 42161)).await.unwrap();
+    let mut dexes: HashMap<(u64, String), Box<dyn DexProtocol>> = HashMap::new();
+    for chain_id in [1, 42161] {
+        let dex = FakeDex::new(chain_id, storage.clone(), &[]);
+        dexes.insert((chain_id, "fake".to_string()), Box::new(dex));
+    }
+    let watchlist = Watchlist::new(WatchlistConfig {
+        pools: HashMap::from([
+            (1, HashMap::from([("fake".to_string(), vec![pool(DEEP).address.to_string()])])),
+            (
+                42161,
+                HashMap::from([("fake".to_string(), vec![pool(SHALLOW).address.to_string()])]),
+            ),
+        ]),
+        ..WatchlistConfig::default()
+    });
+
+    let mut pools: Vec<(u64, Address)> = watchlist
+        .resolve(&dexes, storage.clone(), &prices(storage.clone()), |_| 4)
+        .await
+        .iter()
+        .map(|p| (p.chain_id, p.address))
+        .collect();
+    pools.sort();
+    assert_eq!(pools, vec![(1, pool(DEEP).address), (42161, pool(SHALLOW).address)]);
+}
@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tel_core::error::Error;
+use tel_indexer::workers::run_bounded;
+
+#[tokio::test]
+async fn never_exceeds_the_limit() {
+    let in_flight = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let stats = run_bounded(0..20, 4, |_: u32| async {
+        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+        Ok(())
+    })
+    .await;
+
+    assert_eq!((stats.pools, stats.failed), (20, 0));
+    assert_eq!(peak.load(Ordering::SeqCst), 4);
+    assert!(stats.slowest <= stats.elapsed);
+}
+
+#[tokio::test]
+async fn failures_are_isolated() {
+    let done = AtomicUsize::new(0);
+    let stats = run_bounded(0..6, 2, |n: u32| {
+        let done = &done;
+        async move {
+            match n {
+                1 => Err(Error::NotImplemented),
+                2 => panic!("task {n} blew up"),
+                _ => {
+                    done.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            }
+        }
+    })
+    .await;
+
+    assert_eq!((stats.pools, stats.failed), (6, 2));
+    assert_eq!(done.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn stops_pulling_items_once_the_iterator_ends() {
+    let stopping = AtomicBool::new(false);
+    let items = (0..10u32).take_while(|_| !stopping.load(Ordering::SeqCst));
+    let stats = run_bounded(items, 2, |_| async {
+        stopping.store(true, Ordering::SeqCst);
+        Ok(())
+    })
+    .await;
+
+    assert_eq!(stats.pools, 2, "only the tasks already scheduled run");
+}
@@ -34,6 +34,8 @@ struct LiquidityWall {
     price_upper: f64,
     liquidity_value: f64,
     dex_sources: HashMap<String, f64>,
+    #[serde(default)]
+    liquidity_usd: Option<f64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -42,6 +44,7 @@ struct LiquidityWallsResponse {
     token1: Token,
     price: f64,
     buy_walls: Vec<LiquidityWall>,
+    #[serde(rename = "sell_walls_in_wall_price")]
     sell_walls: Vec<LiquidityWall>,
     timestamp: chrono::DateTime<chrono::Utc>,
 }
@@ -413,6 +416,7 @@ impl TelOnChainUI {
                             current_price: 0.0,
                             price_levels: vec![],
                             timestamp: chrono::Utc::now(),
+                            pool: None,
                         });
                     let price_points = distribution.price_levels.len();
                     Ok(DbLiquidityDistribution {
@@ -1069,7 +1073,10 @@ impl TelOnChainUI {
                     "Price: {:.4} - {:.4}",
                     wall.price_lower, wall.price_upper
                 ));
-                ui.label(format!("Liquidity: ${:.2}", wall.liquidity_value));
+                match wall.liquidity_usd {
+                    Some(usd) => ui.label(format!("Liquidity: ${:.2}", usd)),
+                    None => ui.label(format!("Liquidity: {:.2}", wall.liquidity_value)),
+                };
             });
 
             // Show DEX breakdown if available
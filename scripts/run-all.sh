@@ -34,9 +34,9 @@ API_PID=$!
 # Start tel-indexer
 echo -e "${BLUE}Starting tel-indexer...${NC}"
 if [[ $TEST_MODE -eq 1 ]]; then
-    cargo run --bin tel-indexer -- --test-mode &
+    cargo run --bin tel-indexer -- --dry-run run &
 else
-    cargo run --bin tel-indexer &
+    cargo run --bin tel-indexer -- run &
 fi
 INDEXER_PID=$!
 
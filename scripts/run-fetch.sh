@@ -7,8 +7,8 @@ NC='\033[0m' # No Color
 
 echo -e "${BLUE}Starting tel-indexer to fetch all blocks...${NC}"
 
-# Run the indexer with the --fetch-all flag
-cargo run --bin tel-indexer -- --fetch-all
+# Run a single indexer cycle
+cargo run --bin tel-indexer -- once
 
 EXIT_CODE=$?
 